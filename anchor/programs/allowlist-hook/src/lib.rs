@@ -0,0 +1,416 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{create_account, CreateAccount};
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use spl_tlv_account_resolution::account::ExtraAccountMeta;
+use spl_tlv_account_resolution::seeds::Seed;
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_token_2022::extension::transfer_hook::{TransferHook, TransferHookAccount};
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+// Allowlist Hook - transfer policy for the confidential mint
+// Token-2022 calls `execute` on every transfer of a mint whose TransferHook
+// extension points here, public or confidential (confidential transfers pass
+// u64::MAX as the amount since it is encrypted). The transfer only goes
+// through if the owners of both the source and destination token accounts
+// have an allowlist entry for the mint.
+
+declare_id!("7nnuJUjRS6n2LVKUP2WcbJDfe1XsbbB9wHAu4KTjujEU");
+
+pub const ALLOWLIST_CONFIG_SEED: &[u8] = b"allowlist_config";
+pub const ALLOWLIST_ENTRY_SEED: &[u8] = b"allowlist";
+pub const EXTRA_ACCOUNT_METAS_SEED: &[u8] = b"extra-account-metas";
+
+/// Owner field of an SPL token account
+const TOKEN_ACCOUNT_OWNER_OFFSET: u8 = 32;
+
+#[program]
+pub mod allowlist_hook {
+    use super::*;
+    
+    /// Create the allowlist config and the ExtraAccountMetaList of a mint
+    ///
+    /// Only the TransferHook authority of the mint can set the allowlist up
+    /// and name its `admin`. The authority may be a PDA of the program that
+    /// owns the mint, so rent comes from a separate payer. The meta list
+    /// tells Token-2022 (and clients building transfers) to pass the
+    /// allowlist entries of the source and destination owners to `execute`.
+    pub fn initialize_allowlist(ctx: Context<InitializeAllowlist>, admin: Pubkey) -> Result<()> {
+        let mint_info = ctx.accounts.mint.to_account_info();
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+        let hook = mint
+            .get_extension::<TransferHook>()
+            .map_err(|_| HookError::TransferHookNotConfigured)?;
+        require!(
+            Option::<Pubkey>::from(hook.program_id) == Some(crate::ID),
+            HookError::TransferHookNotConfigured
+        );
+        require!(
+            Option::<Pubkey>::from(hook.authority) == Some(ctx.accounts.authority.key()),
+            HookError::UnauthorizedAdmin
+        );
+        drop(mint_data);
+    
+        let metas = extra_account_metas()?;
+        let size = ExtraAccountMetaList::size_of(metas.len())?;
+        let mint_key = ctx.accounts.mint.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            EXTRA_ACCOUNT_METAS_SEED,
+            mint_key.as_ref(),
+            &[ctx.bumps.extra_account_meta_list],
+        ]];
+        create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                CreateAccount {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.extra_account_meta_list.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            Rent::get()?.minimum_balance(size),
+            size as u64,
+            &crate::ID,
+        )?;
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &metas,
+        )?;
+    
+        let config = &mut ctx.accounts.config;
+        config.mint = mint_key;
+        config.admin = admin;
+        config.entry_count = 0;
+        config.bump = ctx.bumps.config;
+    
+        msg!("✅ Allowlist initialized for mint {}", mint_key);
+        Ok(())
+    }
+    
+    /// Allow `wallet` to send and receive the mint (admin only)
+    /// `wallet` is the owner of token accounts, e.g. a user or a strategy
+    /// vault PDA
+    pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, wallet: Pubkey) -> Result<()> {
+        let clock = Clock::get()?;
+        let entry = &mut ctx.accounts.entry;
+        entry.mint = ctx.accounts.config.mint;
+        entry.wallet = wallet;
+        entry.added_by = ctx.accounts.admin.key();
+        entry.added_at = clock.unix_timestamp;
+        entry.bump = ctx.bumps.entry;
+    
+        let config = &mut ctx.accounts.config;
+        config.entry_count = config
+            .entry_count
+            .checked_add(1)
+            .ok_or(HookError::MathOverflow)?;
+    
+        emit!(AllowlistEntryAdded {
+            mint: config.mint,
+            wallet,
+            admin: ctx.accounts.admin.key(),
+            timestamp: clock.unix_timestamp,
+        });
+    
+        Ok(())
+    }
+    
+    /// Revoke `wallet` (admin only), returning the entry rent to the admin
+    pub fn remove_from_allowlist(ctx: Context<RemoveFromAllowlist>, wallet: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.entry_count = config
+            .entry_count
+            .checked_sub(1)
+            .ok_or(HookError::MathOverflow)?;
+    
+        emit!(AllowlistEntryRemoved {
+            mint: config.mint,
+            wallet,
+            admin: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    
+        Ok(())
+    }
+    
+    /// Hand the allowlist admin role to a new key
+    pub fn set_allowlist_admin(ctx: Context<SetAllowlistAdmin>, new_admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous_admin = config.admin;
+        config.admin = new_admin;
+    
+        emit!(AllowlistAdminChanged {
+            mint: config.mint,
+            previous_admin,
+            new_admin,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    
+        Ok(())
+    }
+    
+    /// Transfer hook, called by Token-2022 on every transfer of the mint
+    #[interface(spl_transfer_hook_interface::execute)]
+    pub fn execute(ctx: Context<Execute>, _amount: u64) -> Result<()> {
+        // Only Token-2022 sets the transferring flag, so this can't be
+        // called directly to fake a policy check
+        let source_info = ctx.accounts.source_token.to_account_info();
+        let source_data = source_info.try_borrow_data()?;
+        let source = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&source_data)?;
+        let transferring = source
+            .get_extension::<TransferHookAccount>()
+            .map(|extension| bool::from(extension.transferring))
+            .unwrap_or(false);
+        require!(transferring, HookError::NotTransferring);
+    
+        require!(
+            is_allowlisted(&ctx.accounts.source_entry),
+            HookError::SenderNotAllowlisted
+        );
+        require!(
+            is_allowlisted(&ctx.accounts.destination_entry),
+            HookError::RecipientNotAllowlisted
+        );
+    
+        Ok(())
+    }
+}
+
+/// Allowlist entries of the source and destination owners, read from the
+/// owner field of the token accounts at execute time
+pub fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    // Execute accounts: 0 source, 1 mint, 2 destination, 3 owner, 4 meta list
+    let entry = |token_account_index: u8| {
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal {
+                    bytes: ALLOWLIST_ENTRY_SEED.to_vec(),
+                },
+                Seed::AccountKey { index: 1 },
+                Seed::AccountData {
+                    account_index: token_account_index,
+                    data_index: TOKEN_ACCOUNT_OWNER_OFFSET,
+                    length: 32,
+                },
+            ],
+            false,
+            false,
+        )
+    };
+    Ok(vec![entry(0)?, entry(2)?])
+}
+
+/// Entries are closed on removal, so an existing entry at the derived
+/// address is enough
+fn is_allowlisted(entry: &AccountInfo) -> bool {
+    entry.owner == &crate::ID && !entry.data_is_empty()
+}
+
+// ============================================================================
+// Types
+// ============================================================================
+
+#[account]
+pub struct AllowlistConfig {
+    pub mint: Pubkey,
+    pub admin: Pubkey,
+    pub entry_count: u32,
+    pub bump: u8,
+}
+
+impl AllowlistConfig {
+    pub const LEN: usize = 8 + 32 + 32 + 4 + 1;
+}
+
+#[account]
+pub struct AllowlistEntry {
+    pub mint: Pubkey,
+    pub wallet: Pubkey,
+    pub added_by: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl AllowlistEntry {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1;
+}
+
+// ============================================================================
+// Account Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeAllowlist<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// TransferHook authority of the mint
+    pub authority: Signer<'info>,
+    
+    #[account(mint::token_program = anchor_spl::token_2022::ID)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: ExtraAccountMetaList of the mint, created and written in the
+    /// handler
+    #[account(
+        mut,
+        seeds = [EXTRA_ACCOUNT_METAS_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = AllowlistConfig::LEN,
+        seeds = [ALLOWLIST_CONFIG_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub config: Account<'info, AllowlistConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddToAllowlist<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [ALLOWLIST_CONFIG_SEED, config.mint.as_ref()],
+        bump = config.bump,
+        has_one = admin @ HookError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, AllowlistConfig>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = AllowlistEntry::LEN,
+        seeds = [ALLOWLIST_ENTRY_SEED, config.mint.as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub entry: Account<'info, AllowlistEntry>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RemoveFromAllowlist<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [ALLOWLIST_CONFIG_SEED, config.mint.as_ref()],
+        bump = config.bump,
+        has_one = admin @ HookError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, AllowlistConfig>,
+    
+    #[account(
+        mut,
+        close = admin,
+        seeds = [ALLOWLIST_ENTRY_SEED, config.mint.as_ref(), wallet.as_ref()],
+        bump = entry.bump
+    )]
+    pub entry: Account<'info, AllowlistEntry>,
+}
+
+#[derive(Accounts)]
+pub struct SetAllowlistAdmin<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [ALLOWLIST_CONFIG_SEED, config.mint.as_ref()],
+        bump = config.bump,
+        has_one = admin @ HookError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, AllowlistConfig>,
+}
+
+/// Account order is fixed by the transfer hook interface, followed by the
+/// extra accounts from the meta list
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    #[account(token::mint = mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Source owner or delegate, authorized by Token-2022
+    pub owner: UncheckedAccount<'info>,
+    
+    /// CHECK: ExtraAccountMetaList of the mint
+    #[account(
+        seeds = [EXTRA_ACCOUNT_METAS_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    
+    /// CHECK: Allowlist entry of the source owner, may not exist
+    #[account(
+        seeds = [ALLOWLIST_ENTRY_SEED, mint.key().as_ref(), source_token.owner.as_ref()],
+        bump
+    )]
+    pub source_entry: UncheckedAccount<'info>,
+    
+    /// CHECK: Allowlist entry of the destination owner, may not exist
+    #[account(
+        seeds = [ALLOWLIST_ENTRY_SEED, mint.key().as_ref(), destination_token.owner.as_ref()],
+        bump
+    )]
+    pub destination_entry: UncheckedAccount<'info>,
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[event]
+pub struct AllowlistEntryAdded {
+    pub mint: Pubkey,
+    pub wallet: Pubkey,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowlistEntryRemoved {
+    pub mint: Pubkey,
+    pub wallet: Pubkey,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowlistAdminChanged {
+    pub mint: Pubkey,
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[error_code]
+pub enum HookError {
+    #[msg("Mint has no transfer hook pointing at this program")]
+    TransferHookNotConfigured,
+    #[msg("Signer is not the allowlist admin for this mint")]
+    UnauthorizedAdmin,
+    #[msg("Execute can only be called by Token-2022 during a transfer")]
+    NotTransferring,
+    #[msg("Sender is not on the allowlist")]
+    SenderNotAllowlisted,
+    #[msg("Recipient is not on the allowlist")]
+    RecipientNotAllowlisted,
+    #[msg("Math operation overflow")]
+    MathOverflow,
+}
@@ -0,0 +1,336 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+// Constant Product AMM - local swap venue for copy-trading strategies
+// x * y = k pools over two SPL tokens. `swap` implements the vault's swap
+// interface (see the vault's `swap_interface` module), so its pools can be
+// allowlisted as strategy venues like any other program's. Liquidity is
+// contributed without LP tokens; this program is a stand-in for a real venue
+// on localnet and devnet.
+
+declare_id!("H8S1ptyQdodEcWnkp25V9B6LjarmE5sizy7CwmrMkYjN");
+
+pub const POOL_SEED: &[u8] = b"pool";
+pub const POOL_VAULT_SEED: &[u8] = b"pool_vault";
+
+const MAX_FEE_BPS: u16 = 1_000; // 10% maximum swap fee
+const BASIS_POINTS_DIVISOR: u128 = 10_000;
+
+#[program]
+pub mod constant_product_amm {
+    use super::*;
+
+    /// Create a pool for a mint pair. Mints must be passed in ascending key
+    /// order so each pair has a single pool
+    pub fn initialize_pool(ctx: Context<InitializePool>, fee_bps: u16) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, AmmError::FeeTooHigh);
+        require!(
+            ctx.accounts.mint_a.key() < ctx.accounts.mint_b.key(),
+            AmmError::MintsNotSorted
+        );
+
+        let pool = &mut ctx.accounts.pool;
+        pool.mint_a = ctx.accounts.mint_a.key();
+        pool.mint_b = ctx.accounts.mint_b.key();
+        pool.vault_a = ctx.accounts.vault_a.key();
+        pool.vault_b = ctx.accounts.vault_b.key();
+        pool.fee_bps = fee_bps;
+        pool.bump = ctx.bumps.pool;
+
+        emit!(PoolCreated {
+            pool: pool.key(),
+            mint_a: pool.mint_a,
+            mint_b: pool.mint_b,
+            fee_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit both tokens into the pool reserves
+    pub fn add_liquidity(ctx: Context<AddLiquidity>, amount_a: u64, amount_b: u64) -> Result<()> {
+        require!(amount_a > 0 && amount_b > 0, AmmError::ZeroAmount);
+
+        for (from, to, amount) in [
+            (&ctx.accounts.provider_a, &ctx.accounts.vault_a, amount_a),
+            (&ctx.accounts.provider_b, &ctx.accounts.vault_b, amount_b),
+        ] {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: from.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: ctx.accounts.provider.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+        }
+
+        emit!(LiquidityAdded {
+            pool: ctx.accounts.pool.key(),
+            provider: ctx.accounts.provider.key(),
+            amount_a,
+            amount_b,
+        });
+
+        Ok(())
+    }
+
+    /// Swap `amount_in` of the source mint for the other mint of the pool
+    pub fn swap(ctx: Context<Swap>, amount_in: u64, minimum_amount_out: u64) -> Result<()> {
+        require!(amount_in > 0, AmmError::ZeroAmount);
+
+        let pool = &ctx.accounts.pool;
+        let amount_out = swap_amount_out(
+            ctx.accounts.pool_source_vault.amount,
+            ctx.accounts.pool_destination_vault.amount,
+            amount_in,
+            pool.fee_bps,
+        )
+        .ok_or(AmmError::MathOverflow)?;
+        require!(amount_out > 0, AmmError::ZeroAmount);
+        require!(amount_out >= minimum_amount_out, AmmError::SlippageExceeded);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.pool_source_vault.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            amount_in,
+        )?;
+
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            POOL_SEED,
+            pool.mint_a.as_ref(),
+            pool.mint_b.as_ref(),
+            &[pool.bump],
+        ]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_destination_vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount_out,
+        )?;
+
+        emit!(Swapped {
+            pool: pool.key(),
+            source_mint: ctx.accounts.source.mint,
+            amount_in,
+            amount_out,
+        });
+
+        Ok(())
+    }
+}
+
+/// Output of swapping `amount_in` against the given reserves after the pool
+/// fee, rounded down: `reserve_out * in_after_fee / (reserve_in + in_after_fee)`
+pub fn swap_amount_out(reserve_in: u64, reserve_out: u64, amount_in: u64, fee_bps: u16) -> Option<u64> {
+    let in_after_fee = (amount_in as u128)
+        .checked_mul(BASIS_POINTS_DIVISOR - fee_bps as u128)?
+        / BASIS_POINTS_DIVISOR;
+    let denominator = (reserve_in as u128).checked_add(in_after_fee)?;
+    if denominator == 0 {
+        return None;
+    }
+    let amount_out = (reserve_out as u128).checked_mul(in_after_fee)? / denominator;
+    u64::try_from(amount_out).ok()
+}
+
+// ============================================================================
+// Types
+// ============================================================================
+
+#[account]
+pub struct Pool {
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub fee_bps: u16,
+    pub bump: u8,
+}
+
+impl Pool {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 2 + 1;
+}
+
+// ============================================================================
+// Account Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub mint_a: Account<'info, Mint>,
+
+    pub mint_b: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Pool::LEN,
+        seeds = [POOL_SEED, mint_a.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint_a,
+        token::authority = pool,
+        seeds = [POOL_VAULT_SEED, pool.key().as_ref(), mint_a.key().as_ref()],
+        bump
+    )]
+    pub vault_a: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint_b,
+        token::authority = pool,
+        seeds = [POOL_VAULT_SEED, pool.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    pub vault_b: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddLiquidity<'info> {
+    pub provider: Signer<'info>,
+
+    #[account(
+        seeds = [POOL_SEED, pool.mint_a.as_ref(), pool.mint_b.as_ref()],
+        bump = pool.bump,
+        has_one = vault_a,
+        has_one = vault_b
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut)]
+    pub vault_a: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub vault_b: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = pool.mint_a)]
+    pub provider_a: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = pool.mint_b)]
+    pub provider_b: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Account order is the vault's swap interface: source, destination, source
+/// authority, pool, pool source and destination vaults, token program
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(
+        mut,
+        constraint = source.mint == pool_source_vault.mint @ AmmError::MintMismatch
+    )]
+    pub source: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = destination.mint == pool_destination_vault.mint @ AmmError::MintMismatch
+    )]
+    pub destination: Account<'info, TokenAccount>,
+
+    /// Owner or delegate of `source`, checked by the token program
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [POOL_SEED, pool.mint_a.as_ref(), pool.mint_b.as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        constraint = pool_source_vault.key() == pool.vault_a
+            || pool_source_vault.key() == pool.vault_b @ AmmError::InvalidPoolVault
+    )]
+    pub pool_source_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = (pool_destination_vault.key() == pool.vault_a
+            || pool_destination_vault.key() == pool.vault_b)
+            && pool_destination_vault.key() != pool_source_vault.key() @ AmmError::InvalidPoolVault
+    )]
+    pub pool_destination_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[event]
+pub struct PoolCreated {
+    pub pool: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub fee_bps: u16,
+}
+
+#[event]
+pub struct LiquidityAdded {
+    pub pool: Pubkey,
+    pub provider: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct Swapped {
+    pub pool: Pubkey,
+    pub source_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+#[error_code]
+pub enum AmmError {
+    #[msg("Swap fee is too high (max 10%)")]
+    FeeTooHigh,
+
+    #[msg("Pool mints must be in ascending key order")]
+    MintsNotSorted,
+
+    #[msg("Amount must be greater than zero")]
+    ZeroAmount,
+
+    #[msg("Output is below the minimum amount out")]
+    SlippageExceeded,
+
+    #[msg("Token account mint does not match the pool vault")]
+    MintMismatch,
+
+    #[msg("Vault does not belong to this pool")]
+    InvalidPoolVault,
+
+    #[msg("Math operation overflow")]
+    MathOverflow,
+}
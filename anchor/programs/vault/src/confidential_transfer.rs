@@ -0,0 +1,786 @@
+//! CPI helpers for the Token-2022 Confidential Transfer and Confidential
+//! Transfer Fee extensions.
+//!
+//! anchor-spl 0.30 does not ship wrappers for this extension yet, so these
+//! follow the shape of its `token_2022_extensions` helpers. The one Transfer
+//! Fee instruction it leaves out, WithdrawWithheldTokensFromAccounts, is here
+//! too.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::sysvar::{self, instructions::get_instruction_relative};
+use spl_token_2022::extension::confidential_transfer::instruction::{
+    self as ct_instruction, ConfidentialTransferInstruction, ConfigureAccountInstructionData,
+    EmptyAccountInstructionData, Pod, WithdrawInstructionData,
+};
+use spl_token_2022::extension::confidential_transfer::ciphertext_extraction::SourceDecryptHandles;
+use spl_token_2022::extension::confidential_transfer::{
+    ConfidentialTransferAccount, ConfidentialTransferMint,
+};
+use spl_token_2022::extension::confidential_transfer_fee::instruction::{
+    self as ct_fee_instruction, ConfidentialTransferFeeInstruction,
+    WithdrawWithheldTokensFromMintData,
+};
+use spl_token_2022::extension::confidential_transfer_fee::ConfidentialTransferFeeConfig;
+use spl_token_2022::extension::transfer_fee::instruction as transfer_fee_instruction;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::transfer_hook::TransferHook;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use spl_token_2022::instruction::TokenInstruction;
+use spl_token_2022::proof::decode_proof_instruction_context;
+use spl_token_2022::solana_zk_token_sdk::instruction::ZkProofData;
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::pod::{AeCiphertext, ElGamalPubkey};
+use spl_token_2022::solana_zk_token_sdk::zk_token_proof_instruction::ProofInstruction;
+use spl_token_2022::solana_zk_token_sdk::zk_token_proof_program;
+use spl_token_2022::solana_zk_token_sdk::zk_token_proof_state::ProofContextState;
+
+use crate::privacy_payments::PrivacyError;
+
+/// Reads the context of a zero-knowledge proof the same way Token-2022 does:
+/// from a pre-verified context state account when `proof_instruction_offset`
+/// is 0, otherwise from the proof instruction at that offset relative to the
+/// current instruction, in which case `proof_account` is the instructions sysvar.
+pub fn proof_context<T, U>(
+    proof_instruction: ProofInstruction,
+    proof_account: &AccountInfo,
+    proof_instruction_offset: i8,
+) -> Result<U>
+where
+    T: Pod + ZkProofData<U>,
+    U: Pod,
+{
+    if proof_instruction_offset == 0 {
+        require_keys_eq!(
+            *proof_account.owner,
+            zk_token_proof_program::id(),
+            PrivacyError::InvalidProof
+        );
+        let data = proof_account.try_borrow_data()?;
+        let context_state = ProofContextState::<U>::try_from_bytes(&data)
+            .map_err(|_| error!(PrivacyError::InvalidProof))?;
+        require!(
+            context_state.proof_type == T::PROOF_TYPE.into(),
+            PrivacyError::InvalidProof
+        );
+        Ok(context_state.proof_context)
+    } else {
+        require_keys_eq!(
+            proof_account.key(),
+            sysvar::instructions::ID,
+            PrivacyError::InvalidProof
+        );
+        let zkp_instruction =
+            get_instruction_relative(proof_instruction_offset.into(), proof_account)?;
+        decode_proof_instruction_context::<T, U>(proof_instruction, &zkp_instruction)
+            .copied()
+            .map_err(|_| error!(PrivacyError::InvalidProof))
+    }
+}
+
+/// Copies the ConfidentialTransferAccount extension out of a token account.
+pub fn account_extension(token_account: &AccountInfo) -> Result<ConfidentialTransferAccount> {
+    let data = token_account.try_borrow_data()?;
+    let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    account
+        .get_extension::<ConfidentialTransferAccount>()
+        .copied()
+        .map_err(|_| error!(PrivacyError::AccountNotConfigured))
+}
+
+/// Copies the ConfidentialTransferMint extension out of a mint.
+pub fn mint_extension(mint: &AccountInfo) -> Result<ConfidentialTransferMint> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    mint.get_extension::<ConfidentialTransferMint>()
+        .copied()
+        .map_err(|_| error!(PrivacyError::MintNotConfigured))
+}
+
+/// Copies the TransferFeeConfig extension out of a mint, if it has one.
+pub fn transfer_fee_config(mint: &AccountInfo) -> Result<Option<TransferFeeConfig>> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(mint.get_extension::<TransferFeeConfig>().ok().copied())
+}
+
+/// Program the mint's TransferHook extension points at, if any.
+pub fn transfer_hook_program_id(mint: &AccountInfo) -> Result<Option<Pubkey>> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(mint
+        .get_extension::<TransferHook>()
+        .ok()
+        .and_then(|hook| Option::<Pubkey>::from(hook.program_id)))
+}
+
+/// Copies the ConfidentialTransferFeeConfig extension out of a mint.
+pub fn fee_extension(mint: &AccountInfo) -> Result<ConfidentialTransferFeeConfig> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    mint.get_extension::<ConfidentialTransferFeeConfig>()
+        .copied()
+        .map_err(|_| error!(PrivacyError::TransferFeesNotConfigured))
+}
+
+/// Same encoding as Token-2022's crate-private `encode_instruction`. Needed for
+/// instructions whose public constructors take off-chain proof data types.
+fn encode_instruction<T: Into<u8>, D: Pod>(
+    token_program_id: &Pubkey,
+    accounts: Vec<AccountMeta>,
+    token_instruction_type: TokenInstruction,
+    instruction_type: T,
+    instruction_data: &D,
+) -> Instruction {
+    let mut data = token_instruction_type.pack();
+    data.push(instruction_type.into());
+    data.extend_from_slice(bytemuck::bytes_of(instruction_data));
+    Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn initialize_mint<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, ConfidentialTransferInitializeMint<'info>>,
+    authority: Option<Pubkey>,
+    auto_approve_new_accounts: bool,
+    auditor_elgamal_pubkey: Option<[u8; 32]>,
+) -> Result<()> {
+    let ix = ct_instruction::initialize_mint(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.mint.key,
+        authority,
+        auto_approve_new_accounts,
+        auditor_elgamal_pubkey.map(ElGamalPubkey),
+    )?;
+    invoke_signed(
+        &ix,
+        &[ctx.accounts.token_program_id, ctx.accounts.mint],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct ConfidentialTransferInitializeMint<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+}
+
+pub fn update_mint<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, ConfidentialTransferUpdateMint<'info>>,
+    auto_approve_new_accounts: bool,
+    auditor_elgamal_pubkey: Option<[u8; 32]>,
+) -> Result<()> {
+    let ix = ct_instruction::update_mint(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.mint.key,
+        ctx.accounts.authority.key,
+        &[],
+        auto_approve_new_accounts,
+        auditor_elgamal_pubkey.map(ElGamalPubkey),
+    )?;
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.token_program_id,
+            ctx.accounts.mint,
+            ctx.accounts.authority,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct ConfidentialTransferUpdateMint<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+pub fn approve_account<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, ApproveAccount<'info>>,
+) -> Result<()> {
+    let ix = ct_instruction::approve_account(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.token_account.key,
+        ctx.accounts.mint.key,
+        ctx.accounts.authority.key,
+        &[],
+    )?;
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.token_program_id,
+            ctx.accounts.token_account,
+            ctx.accounts.mint,
+            ctx.accounts.authority,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct ApproveAccount<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub token_account: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+/// Grows a token account so it can hold the given extensions.
+pub fn reallocate<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Reallocate<'info>>,
+    extension_types: &[ExtensionType],
+) -> Result<()> {
+    let ix = spl_token_2022::instruction::reallocate(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.account.key,
+        ctx.accounts.payer.key,
+        ctx.accounts.owner.key,
+        &[],
+        extension_types,
+    )?;
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.token_program_id,
+            ctx.accounts.account,
+            ctx.accounts.payer,
+            ctx.accounts.system_program,
+            ctx.accounts.owner,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct Reallocate<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub account: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub owner: AccountInfo<'info>,
+}
+
+pub fn configure_account<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, ConfigureAccount<'info>>,
+    decryptable_zero_balance: [u8; 36],
+    maximum_pending_balance_credit_counter: u64,
+    proof_instruction_offset: i8,
+) -> Result<()> {
+    let ix = encode_instruction(
+        ctx.accounts.token_program_id.key,
+        vec![
+            AccountMeta::new(*ctx.accounts.token_account.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.mint.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.proof_account.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.authority.key, true),
+        ],
+        TokenInstruction::ConfidentialTransferExtension,
+        ConfidentialTransferInstruction::ConfigureAccount,
+        &ConfigureAccountInstructionData {
+            decryptable_zero_balance: AeCiphertext(decryptable_zero_balance),
+            maximum_pending_balance_credit_counter: maximum_pending_balance_credit_counter.into(),
+            proof_instruction_offset,
+        },
+    );
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.token_program_id,
+            ctx.accounts.token_account,
+            ctx.accounts.mint,
+            ctx.accounts.proof_account,
+            ctx.accounts.authority,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct ConfigureAccount<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub token_account: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    /// Instructions sysvar or pre-verified proof context state account
+    pub proof_account: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+pub fn deposit<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Deposit<'info>>,
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    let ix = ct_instruction::deposit(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.token_account.key,
+        ctx.accounts.mint.key,
+        amount,
+        decimals,
+        ctx.accounts.authority.key,
+        &[],
+    )?;
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.token_program_id,
+            ctx.accounts.token_account,
+            ctx.accounts.mint,
+            ctx.accounts.authority,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub token_account: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+pub fn apply_pending_balance<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, ApplyPendingBalance<'info>>,
+    expected_pending_balance_credit_counter: u64,
+    new_decryptable_available_balance: [u8; 36],
+) -> Result<()> {
+    let ix = ct_instruction::inner_apply_pending_balance(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.token_account.key,
+        expected_pending_balance_credit_counter,
+        AeCiphertext(new_decryptable_available_balance),
+        ctx.accounts.authority.key,
+        &[],
+    )?;
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.token_program_id,
+            ctx.accounts.token_account,
+            ctx.accounts.authority,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct ApplyPendingBalance<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub token_account: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+pub fn transfer_with_split_proofs<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferWithSplitProofs<'info>>,
+    new_source_decryptable_available_balance: [u8; 36],
+    source_decrypt_handles: &SourceDecryptHandles,
+) -> Result<()> {
+    let ix = ct_instruction::transfer_with_split_proofs(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.source.key,
+        ctx.accounts.mint.key,
+        ctx.accounts.destination.key,
+        AeCiphertext(new_source_decryptable_available_balance),
+        ctx.accounts.authority.key,
+        ct_instruction::TransferSplitContextStateAccounts {
+            equality_proof: ctx.accounts.equality_proof.key,
+            ciphertext_validity_proof: ctx.accounts.ciphertext_validity_proof.key,
+            range_proof: ctx.accounts.range_proof.key,
+            authority: ctx.accounts.authority.key,
+            no_op_on_uninitialized_split_context_state: false,
+            close_split_context_state_accounts: None,
+        },
+        source_decrypt_handles,
+    )?;
+    let mut account_infos = vec![
+        ctx.accounts.token_program_id,
+        ctx.accounts.source,
+        ctx.accounts.mint,
+        ctx.accounts.destination,
+        ctx.accounts.equality_proof,
+        ctx.accounts.ciphertext_validity_proof,
+        ctx.accounts.range_proof,
+        ctx.accounts.authority,
+    ];
+    invoke_with_hook_accounts(ix, &mut account_infos, ctx.remaining_accounts, ctx.signer_seeds)
+}
+
+#[derive(Accounts)]
+pub struct TransferWithSplitProofs<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub source: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub destination: AccountInfo<'info>,
+    pub equality_proof: AccountInfo<'info>,
+    pub ciphertext_validity_proof: AccountInfo<'info>,
+    pub range_proof: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+pub fn withdraw<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Withdraw<'info>>,
+    amount: u64,
+    decimals: u8,
+    new_decryptable_available_balance: [u8; 36],
+    proof_instruction_offset: i8,
+) -> Result<()> {
+    let ix = encode_instruction(
+        ctx.accounts.token_program_id.key,
+        vec![
+            AccountMeta::new(*ctx.accounts.token_account.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.mint.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.proof_account.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.authority.key, true),
+        ],
+        TokenInstruction::ConfidentialTransferExtension,
+        ConfidentialTransferInstruction::Withdraw,
+        &WithdrawInstructionData {
+            amount: amount.into(),
+            decimals,
+            new_decryptable_available_balance: AeCiphertext(new_decryptable_available_balance),
+            proof_instruction_offset,
+        },
+    );
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.token_program_id,
+            ctx.accounts.token_account,
+            ctx.accounts.mint,
+            ctx.accounts.proof_account,
+            ctx.accounts.authority,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub token_account: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    /// Instructions sysvar or pre-verified proof context state account
+    pub proof_account: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+pub fn empty_account<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, EmptyAccount<'info>>,
+    proof_instruction_offset: i8,
+) -> Result<()> {
+    let ix = encode_instruction(
+        ctx.accounts.token_program_id.key,
+        vec![
+            AccountMeta::new(*ctx.accounts.token_account.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.proof_account.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.authority.key, true),
+        ],
+        TokenInstruction::ConfidentialTransferExtension,
+        ConfidentialTransferInstruction::EmptyAccount,
+        &EmptyAccountInstructionData {
+            proof_instruction_offset,
+        },
+    );
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.token_program_id,
+            ctx.accounts.token_account,
+            ctx.accounts.proof_account,
+            ctx.accounts.authority,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct EmptyAccount<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub token_account: AccountInfo<'info>,
+    /// Instructions sysvar or pre-verified proof context state account
+    pub proof_account: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+pub fn transfer_with_fee_and_split_proofs<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferWithFeeAndSplitProofs<'info>>,
+    new_source_decryptable_available_balance: [u8; 36],
+    source_decrypt_handles: &SourceDecryptHandles,
+) -> Result<()> {
+    let ix = ct_instruction::transfer_with_fee_and_split_proofs(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.source.key,
+        ctx.accounts.mint.key,
+        ctx.accounts.destination.key,
+        AeCiphertext(new_source_decryptable_available_balance),
+        ctx.accounts.authority.key,
+        ct_instruction::TransferWithFeeSplitContextStateAccounts {
+            equality_proof: ctx.accounts.equality_proof.key,
+            transfer_amount_ciphertext_validity_proof: ctx
+                .accounts
+                .transfer_amount_ciphertext_validity_proof
+                .key,
+            fee_sigma_proof: ctx.accounts.fee_sigma_proof.key,
+            fee_ciphertext_validity_proof: ctx.accounts.fee_ciphertext_validity_proof.key,
+            range_proof: ctx.accounts.range_proof.key,
+            authority: ctx.accounts.authority.key,
+            no_op_on_uninitialized_split_context_state: false,
+            close_split_context_state_accounts: None,
+        },
+        source_decrypt_handles,
+    )?;
+    let mut account_infos = vec![
+        ctx.accounts.token_program_id,
+        ctx.accounts.source,
+        ctx.accounts.mint,
+        ctx.accounts.destination,
+        ctx.accounts.equality_proof,
+        ctx.accounts.transfer_amount_ciphertext_validity_proof,
+        ctx.accounts.fee_sigma_proof,
+        ctx.accounts.fee_ciphertext_validity_proof,
+        ctx.accounts.range_proof,
+        ctx.accounts.authority,
+    ];
+    invoke_with_hook_accounts(ix, &mut account_infos, ctx.remaining_accounts, ctx.signer_seeds)
+}
+
+/// Transfers on a mint with a transfer hook need the hook program, its
+/// ExtraAccountMetaList and the accounts it lists after the transfer accounts.
+/// Token-2022 forwards them to the hook's Execute.
+fn invoke_with_hook_accounts<'info>(
+    mut ix: Instruction,
+    account_infos: &mut Vec<AccountInfo<'info>>,
+    hook_accounts: Vec<AccountInfo<'info>>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    for account in hook_accounts {
+        ix.accounts.push(if account.is_writable {
+            AccountMeta::new(*account.key, false)
+        } else {
+            AccountMeta::new_readonly(*account.key, false)
+        });
+        account_infos.push(account);
+    }
+    invoke_signed(&ix, account_infos, signer_seeds).map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct TransferWithFeeAndSplitProofs<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub source: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub destination: AccountInfo<'info>,
+    pub equality_proof: AccountInfo<'info>,
+    pub transfer_amount_ciphertext_validity_proof: AccountInfo<'info>,
+    pub fee_sigma_proof: AccountInfo<'info>,
+    pub fee_ciphertext_validity_proof: AccountInfo<'info>,
+    pub range_proof: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+pub fn initialize_confidential_transfer_fee_config<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, InitializeConfidentialTransferFeeConfig<'info>>,
+    authority: Option<Pubkey>,
+    withdraw_withheld_authority_elgamal_pubkey: [u8; 32],
+) -> Result<()> {
+    let ix = ct_fee_instruction::initialize_confidential_transfer_fee_config(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.mint.key,
+        authority,
+        ElGamalPubkey(withdraw_withheld_authority_elgamal_pubkey),
+    )?;
+    invoke_signed(
+        &ix,
+        &[ctx.accounts.token_program_id, ctx.accounts.mint],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfidentialTransferFeeConfig<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+}
+
+pub fn harvest_withheld_tokens_to_mint<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, HarvestWithheldTokensToMint<'info>>,
+    sources: Vec<AccountInfo<'info>>,
+) -> Result<()> {
+    let ix = ct_fee_instruction::harvest_withheld_tokens_to_mint(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.mint.key,
+        sources.iter().map(|a| a.key).collect::<Vec<_>>().as_slice(),
+    )?;
+    
+    let mut account_infos = vec![ctx.accounts.token_program_id, ctx.accounts.mint];
+    account_infos.extend_from_slice(&sources);
+    
+    invoke_signed(&ix, &account_infos, ctx.signer_seeds).map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct HarvestWithheldTokensToMint<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+}
+
+pub fn withdraw_withheld_tokens_from_mint<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, WithdrawWithheldTokensFromMint<'info>>,
+    new_decryptable_available_balance: [u8; 36],
+    proof_instruction_offset: i8,
+) -> Result<()> {
+    let ix = encode_instruction(
+        ctx.accounts.token_program_id.key,
+        vec![
+            AccountMeta::new(*ctx.accounts.mint.key, false),
+            AccountMeta::new(*ctx.accounts.destination.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.proof_account.key, false),
+            AccountMeta::new_readonly(*ctx.accounts.authority.key, true),
+        ],
+        TokenInstruction::ConfidentialTransferFeeExtension,
+        ConfidentialTransferFeeInstruction::WithdrawWithheldTokensFromMint,
+        &WithdrawWithheldTokensFromMintData {
+            proof_instruction_offset,
+            new_decryptable_available_balance: AeCiphertext(new_decryptable_available_balance),
+        },
+    );
+    invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.token_program_id,
+            ctx.accounts.mint,
+            ctx.accounts.destination,
+            ctx.accounts.proof_account,
+            ctx.accounts.authority,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct WithdrawWithheldTokensFromMint<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub destination: AccountInfo<'info>,
+    /// Instructions sysvar or pre-verified proof context state account
+    pub proof_account: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+pub fn withdraw_withheld_tokens_from_accounts<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, WithdrawWithheldTokensFromAccounts<'info>>,
+    sources: Vec<AccountInfo<'info>>,
+) -> Result<()> {
+    let ix = transfer_fee_instruction::withdraw_withheld_tokens_from_accounts(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.mint.key,
+        ctx.accounts.destination.key,
+        ctx.accounts.authority.key,
+        &[],
+        sources.iter().map(|a| a.key).collect::<Vec<_>>().as_slice(),
+    )?;
+    
+    let mut account_infos = vec![
+        ctx.accounts.token_program_id,
+        ctx.accounts.mint,
+        ctx.accounts.destination,
+        ctx.accounts.authority,
+    ];
+    account_infos.extend_from_slice(&sources);
+    
+    invoke_signed(&ix, &account_infos, ctx.signer_seeds).map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct WithdrawWithheldTokensFromAccounts<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub destination: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
+
+pub fn enable_confidential_credits<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, UpdateCredits<'info>>,
+) -> Result<()> {
+    let ix = ct_instruction::enable_confidential_credits(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.token_account.key,
+        ctx.accounts.authority.key,
+        &[],
+    )?;
+    invoke_update_credits(&ix, ctx)
+}
+
+pub fn disable_confidential_credits<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, UpdateCredits<'info>>,
+) -> Result<()> {
+    let ix = ct_instruction::disable_confidential_credits(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.token_account.key,
+        ctx.accounts.authority.key,
+        &[],
+    )?;
+    invoke_update_credits(&ix, ctx)
+}
+
+pub fn enable_non_confidential_credits<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, UpdateCredits<'info>>,
+) -> Result<()> {
+    let ix = ct_instruction::enable_non_confidential_credits(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.token_account.key,
+        ctx.accounts.authority.key,
+        &[],
+    )?;
+    invoke_update_credits(&ix, ctx)
+}
+
+pub fn disable_non_confidential_credits<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, UpdateCredits<'info>>,
+) -> Result<()> {
+    let ix = ct_instruction::disable_non_confidential_credits(
+        ctx.accounts.token_program_id.key,
+        ctx.accounts.token_account.key,
+        ctx.accounts.authority.key,
+        &[],
+    )?;
+    invoke_update_credits(&ix, ctx)
+}
+
+fn invoke_update_credits<'info>(
+    ix: &Instruction,
+    ctx: CpiContext<'_, '_, '_, 'info, UpdateCredits<'info>>,
+) -> Result<()> {
+    invoke_signed(
+        ix,
+        &[
+            ctx.accounts.token_program_id,
+            ctx.accounts.token_account,
+            ctx.accounts.authority,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct UpdateCredits<'info> {
+    pub token_program_id: AccountInfo<'info>,
+    pub token_account: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+}
@@ -0,0 +1,354 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use spl_token_2022::extension::confidential_transfer::ciphertext_extraction::SourceDecryptHandles;
+use spl_token_2022::extension::confidential_transfer::instruction::{
+    ProofInstruction, ZeroBalanceProofContext, ZeroBalanceProofData,
+};
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::{ops, pod};
+
+use crate::confidential_transfer;
+use crate::privacy_payments::{
+    execute_transfer, ConfidentialMemo, PrivacyError, TransferLeg, MAX_MEMO_CIPHERTEXT_LEN,
+};
+
+// Invoices Module - private payment requests
+// The payee publishes the amount they ask for encrypted under the payer's
+// ElGamal key. The payer settles it with a confidential transfer and a
+// zero-balance proof that the invoice ciphertext minus the transferred
+// amount encrypts 0, so the program knows the exact amount was paid while
+// nobody else learns it.
+
+pub const INVOICE_SEED: &[u8] = b"invoice";
+
+/// Request a confidential payment from the owner of `payer_account`
+///
+/// `amount_ciphertext` is the amount encrypted under the ElGamal key of
+/// `payer_account`. The payer checks it decrypts to the amount agreed on
+/// before paying; the optional memo (e.g. the invoice details) is published
+/// in the InvoiceCreated event.
+pub fn create_invoice(
+    ctx: Context<CreateInvoice>,
+    invoice_id: u64,
+    amount_ciphertext: [u8; 64],
+    expires_at: i64,
+    memo: Option<ConfidentialMemo>,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(expires_at > now, PrivacyError::InvoiceExpired);
+    if let Some(memo) = &memo {
+        require!(
+            !memo.ciphertext.is_empty() && memo.ciphertext.len() <= MAX_MEMO_CIPHERTEXT_LEN,
+            PrivacyError::InvalidMemo
+        );
+    }
+    
+    // Both accounts must be able to take part in the transfer
+    confidential_transfer::account_extension(&ctx.accounts.payee_account.to_account_info())?;
+    confidential_transfer::account_extension(&ctx.accounts.payer_account.to_account_info())?;
+    
+    let invoice = &mut ctx.accounts.invoice;
+    invoice.payee = ctx.accounts.payee.key();
+    invoice.payee_account = ctx.accounts.payee_account.key();
+    invoice.payer_account = ctx.accounts.payer_account.key();
+    invoice.mint = ctx.accounts.mint.key();
+    invoice.invoice_id = invoice_id;
+    invoice.amount_ciphertext = amount_ciphertext;
+    invoice.created_at = now;
+    invoice.expires_at = expires_at;
+    invoice.bump = ctx.bumps.invoice;
+    
+    emit!(InvoiceCreated {
+        invoice: invoice.key(),
+        payee: invoice.payee,
+        payee_account: invoice.payee_account,
+        payer_account: invoice.payer_account,
+        mint: invoice.mint,
+        amount_ciphertext,
+        expires_at,
+        memo,
+        timestamp: now,
+    });
+    
+    msg!("🧾 Invoice {} created", invoice_id);
+    Ok(())
+}
+
+/// Settle an invoice with a confidential transfer of exactly the invoiced
+/// amount
+///
+/// Same accounts and proofs as transfer_confidential, plus a zero-balance
+/// proof (inline at `proof_instruction_offset`, or a context state account
+/// when it is 0) over the invoice ciphertext minus the transferred amount.
+/// The invoice is closed and its rent returned to the payee.
+pub fn pay_invoice<'info>(
+    ctx: Context<'_, '_, '_, 'info, PayInvoice<'info>>,
+    new_source_decryptable_available_balance: [u8; 36],
+    source_decrypt_handles: [u8; 64],
+    proof_instruction_offset: i8,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now <= ctx.accounts.invoice.expires_at,
+        PrivacyError::InvoiceExpired
+    );
+    
+    msg!("🧾 Paying invoice {}", ctx.accounts.invoice.invoice_id);
+    
+    let payer_pubkey =
+        confidential_transfer::account_extension(&ctx.accounts.sender_account.to_account_info())?
+            .elgamal_pubkey;
+    
+    let source_decrypt_handles: SourceDecryptHandles =
+        bytemuck::pod_read_unaligned(&source_decrypt_handles);
+    
+    let accounts = &ctx.accounts;
+    let paid = execute_transfer(
+        TransferLeg {
+            token_program: &accounts.token_program.to_account_info(),
+            sender: &accounts.sender.to_account_info(),
+            sender_account: &accounts.sender_account.to_account_info(),
+            recipient_account: &accounts.recipient_account.to_account_info(),
+            mint: &accounts.mint.to_account_info(),
+            equality_proof: &accounts.equality_proof.to_account_info(),
+            ciphertext_validity_proof: &accounts.ciphertext_validity_proof.to_account_info(),
+            fee_sigma_proof: accounts
+                .fee_sigma_proof
+                .as_ref()
+                .map(|proof| proof.to_account_info())
+                .as_ref(),
+            fee_ciphertext_validity_proof: accounts
+                .fee_ciphertext_validity_proof
+                .as_ref()
+                .map(|proof| proof.to_account_info())
+                .as_ref(),
+            range_proof: &accounts.range_proof.to_account_info(),
+            hook_accounts: ctx.remaining_accounts,
+            signer_seeds: &[],
+        },
+        new_source_decryptable_available_balance,
+        &source_decrypt_handles,
+    )?;
+    
+    // Both ciphertexts are under the payer's key, so their difference
+    // encrypts 0 exactly when the transfer amount is the invoiced one
+    let difference = ops::subtract(
+        &pod::ElGamalCiphertext(accounts.invoice.amount_ciphertext),
+        &paid.source_ciphertext()?,
+    )
+    .ok_or(PrivacyError::InvalidProof)?;
+    let proof = confidential_transfer::proof_context::<ZeroBalanceProofData, ZeroBalanceProofContext>(
+        ProofInstruction::VerifyZeroBalance,
+        &accounts.proof_account.to_account_info(),
+        proof_instruction_offset,
+    )?;
+    require!(
+        proof.pubkey == payer_pubkey && proof.ciphertext == difference,
+        PrivacyError::InvoiceAmountMismatch
+    );
+    
+    emit!(InvoicePaid {
+        invoice: accounts.invoice.key(),
+        payee: accounts.invoice.payee,
+        payer: accounts.sender.key(),
+        mint: accounts.invoice.mint,
+        timestamp: now,
+    });
+    
+    msg!("✅ Invoice paid - amount remains encrypted");
+    Ok(())
+}
+
+/// Close an invoice that expired unpaid and return its rent to the payee
+/// Anyone can crank this once the expiry has passed
+pub fn close_expired_invoice(ctx: Context<CloseExpiredInvoice>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now > ctx.accounts.invoice.expires_at,
+        PrivacyError::InvoiceNotExpired
+    );
+    
+    emit!(InvoiceClosed {
+        invoice: ctx.accounts.invoice.key(),
+        payee: ctx.accounts.invoice.payee,
+        timestamp: now,
+    });
+    
+    Ok(())
+}
+
+// ============================================================================
+// Types
+// ============================================================================
+
+#[account]
+pub struct Invoice {
+    pub payee: Pubkey,
+    /// Confidential account the payment goes to
+    pub payee_account: Pubkey,
+    /// Confidential account expected to pay, its key encrypts the amount
+    pub payer_account: Pubkey,
+    pub mint: Pubkey,
+    pub invoice_id: u64,
+    /// ElGamal ciphertext of the invoiced amount under the payer's key
+    pub amount_ciphertext: [u8; 64],
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl Invoice {
+    pub const LEN: usize = 8 + 32 * 4 + 8 + 64 + 8 + 8 + 1;
+}
+
+// ============================================================================
+// Account Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(invoice_id: u64)]
+pub struct CreateInvoice<'info> {
+    #[account(mut)]
+    pub payee: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = payee,
+        space = Invoice::LEN,
+        seeds = [INVOICE_SEED, payee.key().as_ref(), &invoice_id.to_le_bytes()],
+        bump
+    )]
+    pub invoice: Account<'info, Invoice>,
+    
+    /// Payee's confidential account
+    #[account(
+        token::authority = payee,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub payee_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Payer's confidential account
+    #[account(
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub payer_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PayInvoice<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// Sender's confidential account, the one the invoice is addressed to
+    #[account(
+        mut,
+        token::authority = sender,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub sender_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Payee's confidential account
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub recipient_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        close = payee,
+        has_one = payee,
+        has_one = mint,
+        constraint = invoice.payee_account == recipient_account.key() @ PrivacyError::InvalidRecipient,
+        constraint = invoice.payer_account == sender_account.key() @ PrivacyError::InvoicePayerMismatch,
+    )]
+    pub invoice: Account<'info, Invoice>,
+    
+    /// CHECK: Receives the invoice rent, checked against the invoice
+    #[account(mut)]
+    pub payee: UncheckedAccount<'info>,
+    
+    /// CHECK: CiphertextCommitmentEquality proof context state account,
+    /// validated in the handler
+    pub equality_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: BatchedGroupedCiphertext2HandlesValidity proof context state
+    /// account for the transfer amount, validated in the handler
+    pub ciphertext_validity_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: FeeSigma proof context state account, validated in the
+    /// handler. Only for mints with transfer fees
+    pub fee_sigma_proof: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: BatchedGroupedCiphertext2HandlesValidity proof context state
+    /// account for the fee, validated in the handler. Only for mints with
+    /// transfer fees
+    pub fee_ciphertext_validity_proof: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: BatchedRangeProofU128 (BatchedRangeProofU256 with fees) proof
+    /// context state account, validated in the handler
+    pub range_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: Instructions sysvar (inline proof) or ZeroBalance proof context
+    /// state account for the invoice amount, validated in the handler
+    pub proof_account: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct CloseExpiredInvoice<'info> {
+    #[account(
+        mut,
+        close = payee,
+        has_one = payee,
+    )]
+    pub invoice: Account<'info, Invoice>,
+    
+    /// CHECK: Receives the invoice rent, checked against the invoice
+    #[account(mut)]
+    pub payee: UncheckedAccount<'info>,
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[event]
+pub struct InvoiceCreated {
+    pub invoice: Pubkey,
+    pub payee: Pubkey,
+    pub payee_account: Pubkey,
+    pub payer_account: Pubkey,
+    pub mint: Pubkey,
+    pub amount_ciphertext: [u8; 64],
+    pub expires_at: i64,
+    pub memo: Option<ConfidentialMemo>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InvoicePaid {
+    pub invoice: Pubkey,
+    pub payee: Pubkey,
+    pub payer: Pubkey,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InvoiceClosed {
+    pub invoice: Pubkey,
+    pub payee: Pubkey,
+    pub timestamp: i64,
+}
@@ -1,21 +1,72 @@
 use anchor_lang::prelude::*;
-use anchor_lang::system_program::{transfer, Transfer};
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
 
 // Privacy Payments Module - Token-2022 Confidential Transfers
+mod confidential_transfer;
 mod privacy_payments;
 use privacy_payments::*;
+pub use privacy_payments::{
+    batch_transfer_compute_units, BatchTransferEntry, ConfidentialMemo, PrivacyError,
+    TransferFeeParams, APPROVAL_REQUEST_SEED, AUDIT_CONFIG_SEED, CONFIDENTIAL_AUTHORITY_SEED,
+    CREDIT_POLICY_SEED, FREEZE_AUTHORITY_SEED, HOOK_AUTHORITY_SEED, MAX_BATCH_TRANSFERS,
+    MAX_MEMO_CIPHERTEXT_LEN, MINT_AUTHORITY_SEED,
+};
+
+// Wrapper Module - 1:1 backed confidential tokens
+mod wrapper;
+use wrapper::*;
+pub use wrapper::{RESERVE_SEED, WRAPPER_SEED};
+
+// Transfer Fees Module - protocol revenue from confidential transfers
+mod transfer_fees;
+use transfer_fees::*;
+
+// Invoices Module - private payment requests
+mod invoices;
+use invoices::*;
+pub use invoices::{Invoice, INVOICE_SEED};
+
+// Streams Module - confidential vesting payments
+mod streams;
+use streams::*;
+pub use streams::{
+    stream_allowance_ciphertext, Stream, StreamSchedule, STREAM_ALLOWANCE_BIT_LENGTH,
+    STREAM_ESCROW_SEED, STREAM_SEED,
+};
+
+// Swap Interface - program-agnostic CPI into allowlisted swap venues
+pub mod swap_interface;
 
 #[cfg(test)]
 mod tests;
 
 declare_id!("75GwXPYmQSpfSWBg6awrancWsejB19o1AfiGerdbrbtS");
 
+/// Signer seeds of a strategy PDA, `[b"strategy", trader, index, bump]`.
+/// A macro rather than a `Strategy` method so the index bytes it borrows
+/// live as long as the caller's `let`.
+macro_rules! strategy_signer_seeds {
+    ($strategy:expr) => {
+        &[&[
+            b"strategy",
+            $strategy.trader.as_ref(),
+            &$strategy.index.to_le_bytes(),
+            &[$strategy.bump],
+        ]]
+    };
+}
+
 // Constants for validation
 const MAX_NAME_LENGTH: usize = 50;
 const MAX_DESCRIPTION_LENGTH: usize = 500;
 const MAX_FEE_BPS: u16 = 5000; // 50% maximum fee
-const MIN_STAKE_AMOUNT: u64 = 1_000_000_000; // 1 SOL minimum stake
 const BASIS_POINTS_DIVISOR: u64 = 10_000;
+const MAX_PRICE_DEVIATION_BPS: u16 = 1_000; // 10% widest slippage bound a venue may allow
+const MAX_REFERENCE_PRICE_AGE: i64 = 60 * 60; // 1 hour before a reference price goes stale
+
+pub const TRADING_CONFIG_SEED: &[u8] = b"trading_config";
+pub const SWAP_VENUE_SEED: &[u8] = b"swap_venue";
 
 #[program]
 pub mod vault {
@@ -25,18 +76,446 @@ pub mod vault {
     // PRIVACY PAYMENT INSTRUCTIONS (Token-2022 Confidential Transfers)
     // ========================================================================
     
-    pub use crate::privacy_payments::{
-        initialize_confidential_mint,
-        initialize_user_account,
-        deposit_confidential,
-        transfer_confidential,
-        withdraw_confidential,
-    };
+    pub fn initialize_confidential_mint(
+        ctx: Context<InitializeMint>,
+        decimals: u8,
+        auto_approve_new_accounts: bool,
+        auditor_elgamal_pubkey: Option<[u8; 32]>,
+        transfer_fee: Option<TransferFeeParams>,
+        transfer_hook_program_id: Option<Pubkey>,
+    ) -> Result<()> {
+        privacy_payments::initialize_confidential_mint(
+            ctx,
+            decimals,
+            auto_approve_new_accounts,
+            auditor_elgamal_pubkey,
+            transfer_fee,
+            transfer_hook_program_id,
+        )
+    }
+
+    pub fn update_confidential_mint(
+        ctx: Context<UpdateConfidentialMint>,
+        auto_approve_new_accounts: bool,
+        auditor_elgamal_pubkey: Option<[u8; 32]>,
+    ) -> Result<()> {
+        privacy_payments::update_confidential_mint(
+            ctx,
+            auto_approve_new_accounts,
+            auditor_elgamal_pubkey,
+        )
+    }
+
+    pub fn set_audit_authority(ctx: Context<ManageAuditConfig>, new_authority: Pubkey) -> Result<()> {
+        privacy_payments::set_audit_authority(ctx, new_authority)
+    }
+
+    pub fn set_kyc_authority(ctx: Context<ManageAuditConfig>, new_kyc_authority: Pubkey) -> Result<()> {
+        privacy_payments::set_kyc_authority(ctx, new_kyc_authority)
+    }
+
+    pub fn set_compliance_authority(
+        ctx: Context<ManageAuditConfig>,
+        new_compliance_authority: Pubkey,
+    ) -> Result<()> {
+        privacy_payments::set_compliance_authority(ctx, new_compliance_authority)
+    }
+
+    pub fn update_transfer_hook(
+        ctx: Context<ManageTransferHook>,
+        transfer_hook_program_id: Option<Pubkey>,
+    ) -> Result<()> {
+        privacy_payments::update_transfer_hook(ctx, transfer_hook_program_id)
+    }
+
+    pub fn initialize_transfer_hook_allowlist(
+        ctx: Context<InitializeTransferHookAllowlist>,
+    ) -> Result<()> {
+        privacy_payments::initialize_transfer_hook_allowlist(ctx)
+    }
+
+    pub fn request_account_approval(ctx: Context<RequestAccountApproval>) -> Result<()> {
+        privacy_payments::request_account_approval(ctx)
+    }
+
+    pub fn approve_confidential_account(ctx: Context<ReviewAccountApproval>) -> Result<()> {
+        privacy_payments::approve_confidential_account(ctx)
+    }
+
+    pub fn reject_confidential_account(
+        ctx: Context<ReviewAccountApproval>,
+        reason_code: u16,
+    ) -> Result<()> {
+        privacy_payments::reject_confidential_account(ctx, reason_code)
+    }
+
+    pub fn freeze_confidential_account(
+        ctx: Context<ComplianceAction>,
+        reason_code: u16,
+    ) -> Result<()> {
+        privacy_payments::freeze_confidential_account(ctx, reason_code)
+    }
+
+    pub fn thaw_confidential_account(
+        ctx: Context<ComplianceAction>,
+        reason_code: u16,
+    ) -> Result<()> {
+        privacy_payments::thaw_confidential_account(ctx, reason_code)
+    }
+
+    pub fn initialize_user_account(
+        ctx: Context<InitializeUserAccount>,
+        elgamal_pubkey: [u8; 32],
+        decryptable_zero_balance: [u8; 36],
+        proof_instruction_offset: i8,
+    ) -> Result<()> {
+        privacy_payments::initialize_user_account(
+            ctx,
+            elgamal_pubkey,
+            decryptable_zero_balance,
+            proof_instruction_offset,
+        )
+    }
+
+    pub fn deposit_confidential(ctx: Context<DepositConfidential>, amount: u64) -> Result<()> {
+        privacy_payments::deposit_confidential(ctx, amount)
+    }
+
+    pub fn apply_pending_balance(
+        ctx: Context<ApplyPendingBalance>,
+        expected_pending_balance_credit_counter: u64,
+        new_decryptable_available_balance: [u8; 36],
+    ) -> Result<()> {
+        privacy_payments::apply_pending_balance(
+            ctx,
+            expected_pending_balance_credit_counter,
+            new_decryptable_available_balance,
+        )
+    }
+
+    pub fn transfer_confidential<'info>(
+        ctx: Context<'_, '_, '_, 'info, TransferConfidential<'info>>,
+        new_source_decryptable_available_balance: [u8; 36],
+        source_decrypt_handles: [u8; 64],
+        memo: Option<ConfidentialMemo>,
+    ) -> Result<()> {
+        privacy_payments::transfer_confidential(
+            ctx,
+            new_source_decryptable_available_balance,
+            source_decrypt_handles,
+            memo,
+        )
+    }
+
+    pub fn batch_transfer_confidential<'info>(
+        ctx: Context<'_, '_, '_, 'info, BatchTransferConfidential<'info>>,
+        transfers: Vec<BatchTransferEntry>,
+    ) -> Result<()> {
+        privacy_payments::batch_transfer_confidential(ctx, transfers)
+    }
+
+    pub fn withdraw_confidential(
+        ctx: Context<WithdrawConfidential>,
+        amount: u64,
+        new_decryptable_available_balance: [u8; 36],
+        proof_instruction_offset: i8,
+    ) -> Result<()> {
+        privacy_payments::withdraw_confidential(
+            ctx,
+            amount,
+            new_decryptable_available_balance,
+            proof_instruction_offset,
+        )
+    }
+
+    pub fn enable_confidential_credits(ctx: Context<ConfigureCredits>) -> Result<()> {
+        privacy_payments::enable_confidential_credits(ctx)
+    }
+
+    pub fn disable_confidential_credits(ctx: Context<ConfigureCredits>) -> Result<()> {
+        privacy_payments::disable_confidential_credits(ctx)
+    }
+
+    pub fn enable_non_confidential_credits(ctx: Context<ConfigureCredits>) -> Result<()> {
+        privacy_payments::enable_non_confidential_credits(ctx)
+    }
+
+    pub fn disable_non_confidential_credits(ctx: Context<ConfigureCredits>) -> Result<()> {
+        privacy_payments::disable_non_confidential_credits(ctx)
+    }
+
+    pub fn enforce_confidential_only(ctx: Context<EnforceConfidentialOnly>) -> Result<()> {
+        privacy_payments::enforce_confidential_only(ctx)
+    }
+
+    pub fn close_confidential_account(
+        ctx: Context<CloseConfidentialAccount>,
+        proof_instruction_offset: i8,
+    ) -> Result<()> {
+        privacy_payments::close_confidential_account(ctx, proof_instruction_offset)
+    }
+
+    // ========================================================================
+    // TRANSFER FEE INSTRUCTIONS (withheld confidential fees -> treasury)
+    // ========================================================================
+
+    pub fn set_fee_treasury(ctx: Context<SetFeeTreasury>) -> Result<()> {
+        transfer_fees::set_fee_treasury(ctx)
+    }
+
+    pub fn set_transfer_fee(
+        ctx: Context<SetTransferFee>,
+        transfer_fee_basis_points: u16,
+        maximum_fee: u64,
+    ) -> Result<()> {
+        transfer_fees::set_transfer_fee(ctx, transfer_fee_basis_points, maximum_fee)
+    }
+
+    pub fn harvest_confidential_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, HarvestConfidentialFees<'info>>,
+    ) -> Result<()> {
+        transfer_fees::harvest_confidential_fees(ctx)
+    }
+
+    pub fn withdraw_confidential_fees(
+        ctx: Context<WithdrawConfidentialFees>,
+        new_decryptable_available_balance: [u8; 36],
+        proof_instruction_offset: i8,
+    ) -> Result<()> {
+        transfer_fees::withdraw_confidential_fees(
+            ctx,
+            new_decryptable_available_balance,
+            proof_instruction_offset,
+        )
+    }
+
+    pub fn withdraw_withheld_fees_from_mint(ctx: Context<WithdrawWithheldFees>) -> Result<()> {
+        transfer_fees::withdraw_withheld_fees_from_mint(ctx)
+    }
+
+    pub fn withdraw_withheld_fees_from_accounts<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawWithheldFees<'info>>,
+    ) -> Result<()> {
+        transfer_fees::withdraw_withheld_fees_from_accounts(ctx)
+    }
+
+    // ========================================================================
+    // WRAPPER INSTRUCTIONS (underlying token <-> confidential token, 1:1)
+    // ========================================================================
+
+    pub fn initialize_wrapper(ctx: Context<InitializeWrapper>) -> Result<()> {
+        wrapper::initialize_wrapper(ctx)
+    }
+
+    pub fn wrap(ctx: Context<Wrap>, amount: u64) -> Result<()> {
+        wrapper::wrap(ctx, amount)
+    }
+
+    pub fn unwrap(ctx: Context<Unwrap>, amount: u64) -> Result<()> {
+        wrapper::unwrap(ctx, amount)
+    }
+
+    // ========================================================================
+    // INVOICE INSTRUCTIONS (private payment requests)
+    // ========================================================================
+
+    pub fn create_invoice(
+        ctx: Context<CreateInvoice>,
+        invoice_id: u64,
+        amount_ciphertext: [u8; 64],
+        expires_at: i64,
+        memo: Option<ConfidentialMemo>,
+    ) -> Result<()> {
+        invoices::create_invoice(ctx, invoice_id, amount_ciphertext, expires_at, memo)
+    }
+
+    pub fn pay_invoice<'info>(
+        ctx: Context<'_, '_, '_, 'info, PayInvoice<'info>>,
+        new_source_decryptable_available_balance: [u8; 36],
+        source_decrypt_handles: [u8; 64],
+        proof_instruction_offset: i8,
+    ) -> Result<()> {
+        invoices::pay_invoice(
+            ctx,
+            new_source_decryptable_available_balance,
+            source_decrypt_handles,
+            proof_instruction_offset,
+        )
+    }
+
+    pub fn close_expired_invoice(ctx: Context<CloseExpiredInvoice>) -> Result<()> {
+        invoices::close_expired_invoice(ctx)
+    }
+
+    // ========================================================================
+    // STREAM INSTRUCTIONS (confidential vesting payments)
+    // ========================================================================
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_stream(
+        ctx: Context<CreateStream>,
+        schedule: StreamSchedule,
+        escrow_elgamal_pubkey: [u8; 32],
+        escrow_decryptable_zero_balance: [u8; 36],
+        escrow_proof_instruction_offset: i8,
+        new_source_decryptable_available_balance: [u8; 36],
+        source_decrypt_handles: [u8; 64],
+        escrow_decryptable_available_balance: [u8; 36],
+        stream_secret: ConfidentialMemo,
+    ) -> Result<()> {
+        streams::create_stream(
+            ctx,
+            schedule,
+            escrow_elgamal_pubkey,
+            escrow_decryptable_zero_balance,
+            escrow_proof_instruction_offset,
+            new_source_decryptable_available_balance,
+            source_decrypt_handles,
+            escrow_decryptable_available_balance,
+            stream_secret,
+        )
+    }
+
+    pub fn withdraw_from_stream(
+        ctx: Context<WithdrawFromStream>,
+        new_escrow_decryptable_available_balance: [u8; 36],
+        source_decrypt_handles: [u8; 64],
+        new_decryptable_withdrawn_amount: [u8; 36],
+    ) -> Result<()> {
+        streams::withdraw_from_stream(
+            ctx,
+            new_escrow_decryptable_available_balance,
+            source_decrypt_handles,
+            new_decryptable_withdrawn_amount,
+        )
+    }
+
+    pub fn cancel_stream(
+        ctx: Context<CancelStream>,
+        new_escrow_decryptable_available_balance: [u8; 36],
+        source_decrypt_handles: [u8; 64],
+    ) -> Result<()> {
+        streams::cancel_stream(
+            ctx,
+            new_escrow_decryptable_available_balance,
+            source_decrypt_handles,
+        )
+    }
+
+    pub fn close_stream(ctx: Context<CloseStream>, proof_instruction_offset: i8) -> Result<()> {
+        streams::close_stream(ctx, proof_instruction_offset)
+    }
 
     // ========================================================================
     // COPY TRADING INSTRUCTIONS (Optional - for full Spectre Protocol)
     // ========================================================================
 
+    /// Name the admin who allowlists swap venues. Only the program's upgrade
+    /// authority can create the config
+    pub fn initialize_trading_config(
+        ctx: Context<InitializeTradingConfig>,
+        admin: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.trading_config;
+        config.admin = admin;
+        config.bump = ctx.bumps.trading_config;
+
+        Ok(())
+    }
+
+    /// Allowlist a pool as the venue for a base/asset pair (admin only)
+    /// The admin's reference price, not the pool's, bounds every trade
+    /// through it, so a trader can't move the price a strategy trades at.
+    pub fn add_swap_venue(
+        ctx: Context<AddSwapVenue>,
+        reference_base: u64,
+        reference_asset: u64,
+        max_deviation_bps: u16,
+    ) -> Result<()> {
+        require!(
+            reference_base > 0 && reference_asset > 0,
+            VaultError::InvalidReferencePrice
+        );
+        require!(
+            max_deviation_bps <= MAX_PRICE_DEVIATION_BPS,
+            VaultError::DeviationTooHigh
+        );
+
+        let venue = &mut ctx.accounts.venue;
+        venue.base_mint = ctx.accounts.base_mint.key();
+        venue.asset_mint = ctx.accounts.asset_mint.key();
+        venue.swap_program = ctx.accounts.swap_program.key();
+        venue.pool = ctx.accounts.pool.key();
+        venue.pool_base_vault = ctx.accounts.pool_base_vault.key();
+        venue.pool_asset_vault = ctx.accounts.pool_asset_vault.key();
+        venue.reference_base = reference_base;
+        venue.reference_asset = reference_asset;
+        venue.max_deviation_bps = max_deviation_bps;
+        venue.price_updated_at = Clock::get()?.unix_timestamp;
+        venue.bump = ctx.bumps.venue;
+
+        emit!(SwapVenueAdded {
+            venue: venue.key(),
+            base_mint: venue.base_mint,
+            asset_mint: venue.asset_mint,
+            swap_program: venue.swap_program,
+            pool: venue.pool,
+            max_deviation_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Record a new reference price for a venue (admin only)
+    /// `reference_base` base units trade for `reference_asset` asset units.
+    pub fn set_reference_price(
+        ctx: Context<ManageSwapVenue>,
+        reference_base: u64,
+        reference_asset: u64,
+    ) -> Result<()> {
+        require!(
+            reference_base > 0 && reference_asset > 0,
+            VaultError::InvalidReferencePrice
+        );
+
+        let venue = &mut ctx.accounts.venue;
+        venue.reference_base = reference_base;
+        venue.reference_asset = reference_asset;
+        venue.price_updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(ReferencePriceUpdated {
+            venue: venue.key(),
+            reference_base,
+            reference_asset,
+            timestamp: venue.price_updated_at,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke a venue (admin only), returning its rent to the admin
+    /// Strategies on the pair can no longer trade; subscribers can still
+    /// unsubscribe.
+    pub fn remove_swap_venue(ctx: Context<RemoveSwapVenue>) -> Result<()> {
+        emit!(SwapVenueRemoved {
+            venue: ctx.accounts.venue.key(),
+            base_mint: ctx.accounts.venue.base_mint,
+            asset_mint: ctx.accounts.venue.asset_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Create the per-trader profile that numbers the trader's strategies
+    pub fn initialize_trader_profile(ctx: Context<InitializeTraderProfile>) -> Result<()> {
+        let profile = &mut ctx.accounts.trader_profile;
+        profile.trader = ctx.accounts.trader.key();
+        profile.strategy_count = 0;
+        profile.bump = ctx.bumps.trader_profile;
+
+        Ok(())
+    }
+
     pub fn initialize_strategy(
         ctx: Context<InitializeStrategy>,
         name: String,
@@ -57,6 +536,11 @@ pub mod vault {
             VaultError::FeeTooHigh
         );
 
+        let profile = &mut ctx.accounts.trader_profile;
+        let index = profile.strategy_count;
+        profile.strategy_count = profile.strategy_count.checked_add(1)
+            .ok_or(VaultError::MathOverflow)?;
+
         let strategy = &mut ctx.accounts.strategy;
         let clock = Clock::get()?;
 
@@ -69,11 +553,23 @@ pub mod vault {
         strategy.total_fees_earned = 0;
         strategy.is_active = true;
         strategy.created_at = clock.unix_timestamp;
+        strategy.index = index;
+        strategy.migrated_from = None;
+        strategy.base_mint = Pubkey::default();
+        strategy.asset_mint = Pubkey::default();
+        strategy.base_vault = Pubkey::default();
+        strategy.asset_vault = Pubkey::default();
+        strategy.swap_program = Pubkey::default();
+        strategy.min_deposit = 0;
+        strategy.total_shares = 0;
+        strategy.base_balance = 0;
+        strategy.asset_balance = 0;
         strategy.bump = ctx.bumps.strategy;
 
         emit!(StrategyCreated {
             strategy: strategy.key(),
             trader: strategy.trader,
+            index,
             name: strategy.name.clone(),
             performance_fee_bps,
             timestamp: clock.unix_timestamp,
@@ -120,6 +616,37 @@ pub mod vault {
         Ok(())
     }
 
+    /// Create the strategy-owned token vaults on a mint pair with an
+    /// allowlisted swap venue, and pin the venue's swap program. Subscriber
+    /// deposits are held in the base vault; the asset vault holds whatever
+    /// the trader buys with them.
+    pub fn initialize_strategy_vaults(ctx: Context<InitializeStrategyVaults>) -> Result<()> {
+        let strategy = &mut ctx.accounts.strategy;
+        strategy.base_mint = ctx.accounts.base_mint.key();
+        strategy.asset_mint = ctx.accounts.asset_mint.key();
+        strategy.base_vault = ctx.accounts.base_vault.key();
+        strategy.asset_vault = ctx.accounts.asset_vault.key();
+        strategy.swap_program = ctx.accounts.venue.swap_program;
+        // One whole base token, whatever the mint's decimals
+        strategy.min_deposit = 10u64
+            .checked_pow(u32::from(ctx.accounts.base_mint.decimals))
+            .ok_or(VaultError::MathOverflow)?;
+
+        emit!(StrategyVaultsInitialized {
+            strategy: strategy.key(),
+            base_mint: strategy.base_mint,
+            asset_mint: strategy.asset_mint,
+            swap_program: strategy.swap_program,
+            min_deposit: strategy.min_deposit,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit base tokens into the strategy pool for shares at current NAV
+    /// NAV is the base balance plus the asset balance valued at the venue's
+    /// reference price, so a trader can't move it through the pool
     pub fn subscribe_to_strategy(
         ctx: Context<SubscribeToStrategy>,
         initial_deposit: u64,
@@ -129,18 +656,23 @@ pub mod vault {
             VaultError::StrategyInactive
         );
         require!(
-            initial_deposit >= MIN_STAKE_AMOUNT,
+            initial_deposit >= ctx.accounts.strategy.min_deposit,
             VaultError::InsufficientDeposit
         );
 
         let clock = Clock::get()?;
+        let nav = ctx.accounts.strategy.nav(&ctx.accounts.venue, clock.unix_timestamp)?;
+        let shares = ctx.accounts.strategy.shares_for_deposit(initial_deposit, nav)?;
+        require!(shares > 0, VaultError::InsufficientDeposit);
 
-        transfer(
+        // Deposit into the strategy's base vault
+        token::transfer(
             CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.user.to_account_info(),
-                    to: ctx.accounts.position.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.user_base_account.to_account_info(),
+                    to: ctx.accounts.base_vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
                 },
             ),
             initial_deposit,
@@ -150,16 +682,20 @@ pub mod vault {
         let position = &mut ctx.accounts.position;
         position.user = ctx.accounts.user.key();
         position.strategy = ctx.accounts.strategy.key();
+        position.shares = shares;
         position.initial_balance = initial_deposit;
-        position.current_balance = initial_deposit;
         position.total_fees_paid = 0;
         position.last_fee_settlement = clock.unix_timestamp;
         position.subscribed_at = clock.unix_timestamp;
         position.is_active = true;
         position.bump = ctx.bumps.position;
 
-        // Update strategy stats
+        // Update pool and strategy stats
         let strategy = &mut ctx.accounts.strategy;
+        strategy.total_shares = strategy.total_shares.checked_add(shares)
+            .ok_or(VaultError::MathOverflow)?;
+        strategy.base_balance = strategy.base_balance.checked_add(initial_deposit)
+            .ok_or(VaultError::MathOverflow)?;
         strategy.total_subscribers = strategy.total_subscribers.checked_add(1)
             .ok_or(VaultError::MathOverflow)?;
 
@@ -167,45 +703,126 @@ pub mod vault {
             user: position.user,
             strategy: position.strategy,
             initial_deposit,
+            shares,
             timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
 
-    /// Execute trade and update position
-    /// Called by trader to record trade execution
-    pub fn execute_trade(
-        ctx: Context<ExecuteTrade>,
-        amount: u64,
-        profit_or_loss: i64,
+    /// Trade the strategy pool through the pair's allowlisted swap venue
+    /// Buys spend base tokens for the asset, sells do the reverse. Pool
+    /// balances are updated once from the vault balance changes, and every
+    /// subscriber's share of the result follows from their shares.
+    /// The minimum output is derived from the venue's reference price, which
+    /// only the admin sets, so moving the pool's price can't make the
+    /// strategy trade at a bad one.
+    pub fn execute_trade<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteTrade<'info>>,
+        side: TradeSide,
+        amount_in: u64,
     ) -> Result<()> {
-        let position = &mut ctx.accounts.position;
-        require!(position.is_active, VaultError::PositionInactive);
-
-        // Update position balance
-        if profit_or_loss >= 0 {
-            position.current_balance = position.current_balance
-                .checked_add(profit_or_loss as u64)
-                .ok_or(VaultError::MathOverflow)?;
-        } else {
-            position.current_balance = position.current_balance
-                .checked_sub(profit_or_loss.abs() as u64)
-                .ok_or(VaultError::InsufficientBalance)?;
-        }
+        require!(amount_in > 0, VaultError::InvalidTradeAmount);
+
+        let available = match side {
+            TradeSide::Buy => ctx.accounts.strategy.base_balance,
+            TradeSide::Sell => ctx.accounts.strategy.asset_balance,
+        };
+        require!(amount_in <= available, VaultError::InsufficientBalance);
+
+        let (source, destination, pool_source_vault, pool_destination_vault) = match side {
+            TradeSide::Buy => (
+                &ctx.accounts.base_vault,
+                &ctx.accounts.asset_vault,
+                &ctx.accounts.pool_base_vault,
+                &ctx.accounts.pool_asset_vault,
+            ),
+            TradeSide::Sell => (
+                &ctx.accounts.asset_vault,
+                &ctx.accounts.base_vault,
+                &ctx.accounts.pool_asset_vault,
+                &ctx.accounts.pool_base_vault,
+            ),
+        };
+        let source_before = source.amount;
+        let destination_before = destination.amount;
+        let minimum_amount_out = ctx.accounts.venue.minimum_output(
+            side,
+            amount_in,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let strategy = &ctx.accounts.strategy;
+        let signer_seeds: &[&[&[u8]]] = strategy_signer_seeds!(strategy);
+        swap_interface::swap(
+            ctx.accounts.swap_program.to_account_info(),
+            swap_interface::SwapAccounts {
+                source: source.to_account_info(),
+                destination: destination.to_account_info(),
+                authority: strategy.to_account_info(),
+                pool: ctx.accounts.pool.to_account_info(),
+                pool_source_vault: pool_source_vault.to_account_info(),
+                pool_destination_vault: pool_destination_vault.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            amount_in,
+            minimum_amount_out,
+            signer_seeds,
+        )?;
+
+        ctx.accounts.base_vault.reload()?;
+        ctx.accounts.asset_vault.reload()?;
+        let (source, destination) = match side {
+            TradeSide::Buy => (&ctx.accounts.base_vault, &ctx.accounts.asset_vault),
+            TradeSide::Sell => (&ctx.accounts.asset_vault, &ctx.accounts.base_vault),
+        };
+
+        // Never trust the swap program's accounting: it may not take more
+        // than `amount_in` or return less than the on-chain minimum
+        let spent = source_before
+            .checked_sub(source.amount)
+            .ok_or(VaultError::InvalidSwapResult)?;
+        let received = destination.amount
+            .checked_sub(destination_before)
+            .ok_or(VaultError::InvalidSwapResult)?;
+        require!(spent <= amount_in, VaultError::InvalidSwapResult);
+        require!(received >= minimum_amount_out, VaultError::SlippageExceeded);
 
-        // Update strategy volume
         let strategy = &mut ctx.accounts.strategy;
+        let base_volume = match side {
+            TradeSide::Buy => {
+                strategy.base_balance = strategy.base_balance
+                    .checked_sub(spent)
+                    .ok_or(VaultError::InsufficientBalance)?;
+                strategy.asset_balance = strategy.asset_balance
+                    .checked_add(received)
+                    .ok_or(VaultError::MathOverflow)?;
+                spent
+            }
+            TradeSide::Sell => {
+                strategy.asset_balance = strategy.asset_balance
+                    .checked_sub(spent)
+                    .ok_or(VaultError::InsufficientBalance)?;
+                strategy.base_balance = strategy.base_balance
+                    .checked_add(received)
+                    .ok_or(VaultError::MathOverflow)?;
+                received
+            }
+        };
+
+        // Volume is measured in base tokens
         strategy.total_volume_traded = strategy.total_volume_traded
-            .checked_add(amount)
+            .checked_add(base_volume)
             .ok_or(VaultError::MathOverflow)?;
 
         emit!(TradeExecuted {
             strategy: strategy.key(),
-            user: position.user,
-            amount,
-            profit_or_loss,
-            new_balance: position.current_balance,
+            side,
+            amount_in: spent,
+            amount_out: received,
+            base_balance: strategy.base_balance,
+            asset_balance: strategy.asset_balance,
             timestamp: Clock::get()?.unix_timestamp,
         });
 
@@ -213,23 +830,17 @@ pub mod vault {
     }
 
     /// Settle performance fees
-    /// Calculate and transfer fees based on profit
+    /// The fee is charged on the growth of the position's value at NAV since
+    /// the last settlement, paid in base tokens and burned from the
+    /// position's shares
     pub fn settle_fees(ctx: Context<SettleFees>) -> Result<()> {
         require!(ctx.accounts.position.is_active, VaultError::PositionInactive);
 
-        // Get user's wallet balance for sanity check
-        let user_balance = ctx.accounts.user.lamports();
-
-        // Calculate profit (both values should be in lamports)
-        let current = ctx.accounts.position.current_balance;
+        // Calculate profit (both values are in base tokens)
+        let nav = ctx.accounts.strategy.nav(&ctx.accounts.venue, Clock::get()?.unix_timestamp)?;
+        let current = ctx.accounts.strategy.value_of_shares(ctx.accounts.position.shares, nav)?;
         let initial = ctx.accounts.position.initial_balance;
-        
-        // Sanity check: current_balance shouldn't be astronomically large
-        require!(
-            current < 1_000_000 * 1_000_000_000, // Max 1M SOL in lamports
-            VaultError::MathOverflow
-        );
-        
+
         let profit = current
             .checked_sub(initial)
             .ok_or(VaultError::NoProfitToSettle)?;
@@ -248,21 +859,31 @@ pub mod vault {
         // Cap fee at 10% of initial balance to prevent absurd fees from calculation errors
         let max_fee = initial / 10; // Max 10% of initial deposit
         let capped_fee = fee_amount.min(max_fee);
-        
-        // Ensure user has enough balance to pay the fee
+
+        // Round the burned shares up so the fee never dilutes other subscribers
+        let fee_shares = ctx.accounts.strategy.shares_for_withdrawal(capped_fee, nav)?;
         require!(
-            user_balance >= capped_fee,
+            fee_shares <= ctx.accounts.position.shares,
             VaultError::InsufficientBalance
         );
 
-        // Transfer fee from user to trader (user pays the fee, not from position account)
-        transfer(
-            CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.user.to_account_info(),
-                    to: ctx.accounts.trader.to_account_info(),
+        // Pay the fee out of the pool's base vault; a pool holding mostly
+        // the asset settles once the trader sells enough of it back
+        require!(
+            capped_fee <= ctx.accounts.strategy.base_balance,
+            VaultError::InsufficientBalance
+        );
+        let strategy = &ctx.accounts.strategy;
+        let signer_seeds: &[&[&[u8]]] = strategy_signer_seeds!(strategy);
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.base_vault.to_account_info(),
+                    to: ctx.accounts.trader_base_account.to_account_info(),
+                    authority: strategy.to_account_info(),
                 },
+                signer_seeds,
             ),
             capped_fee,
         )?;
@@ -274,17 +895,25 @@ pub mod vault {
 
         // Update position
         let position = &mut ctx.accounts.position;
-        position.current_balance = position.current_balance
-            .checked_sub(capped_fee)
+        position.shares = position.shares
+            .checked_sub(fee_shares)
             .ok_or(VaultError::InsufficientBalance)?;
-        position.initial_balance = position.current_balance; // Reset baseline
+        position.initial_balance = current
+            .checked_sub(capped_fee)
+            .ok_or(VaultError::InsufficientBalance)?; // Reset baseline
         position.total_fees_paid = position.total_fees_paid
             .checked_add(capped_fee)
             .ok_or(VaultError::MathOverflow)?;
         position.last_fee_settlement = Clock::get()?.unix_timestamp;
 
-        // Update strategy stats
+        // Update pool and strategy stats
         let strategy = &mut ctx.accounts.strategy;
+        strategy.total_shares = strategy.total_shares
+            .checked_sub(fee_shares)
+            .ok_or(VaultError::MathOverflow)?;
+        strategy.base_balance = strategy.base_balance
+            .checked_sub(capped_fee)
+            .ok_or(VaultError::InsufficientBalance)?;
         strategy.total_fees_earned = strategy.total_fees_earned
             .checked_add(capped_fee)
             .ok_or(VaultError::MathOverflow)?;
@@ -294,7 +923,8 @@ pub mod vault {
             strategy: strategy_key,
             trader: trader_key,
             fee_amount: capped_fee,
-            remaining_balance: position.current_balance,
+            shares_burned: fee_shares,
+            remaining_balance: position.initial_balance,
             timestamp: Clock::get()?.unix_timestamp,
         });
 
@@ -302,27 +932,52 @@ pub mod vault {
     }
 
     /// Unsubscribe from strategy and withdraw funds
+    /// Burns all of the position's shares for their pro-rata part of both
+    /// pool balances, so exits never depend on an asset price
     pub fn unsubscribe(ctx: Context<Unsubscribe>) -> Result<()> {
         require!(ctx.accounts.position.is_active, VaultError::PositionInactive);
 
-        let withdraw_amount = ctx.accounts.position.current_balance;
-        let position_lamports = ctx.accounts.position.to_account_info().lamports();
-        
-        // Only withdraw if there are sufficient lamports in the account
-        // The position account needs to maintain rent-exempt balance until it's closed
-        if withdraw_amount > 0 && position_lamports > withdraw_amount {
-            // Transfer user's balance back
-            **ctx.accounts.position.to_account_info().try_borrow_mut_lamports()? -= withdraw_amount;
-            **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += withdraw_amount;
+        let shares = ctx.accounts.position.shares;
+        let base_amount = ctx.accounts.strategy.base_for_shares(shares)?;
+        let asset_amount = ctx.accounts.strategy.asset_for_shares(shares)?;
+
+        let strategy = &ctx.accounts.strategy;
+        let signer_seeds: &[&[&[u8]]] = strategy_signer_seeds!(strategy);
+        for (from, to, amount) in [
+            (&ctx.accounts.base_vault, &ctx.accounts.user_base_account, base_amount),
+            (&ctx.accounts.asset_vault, &ctx.accounts.user_asset_account, asset_amount),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: from.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: strategy.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?;
         }
-        
-        // Prepare seeds for transfer (not used anymore but keeping structure)
+
         let user_key = ctx.accounts.position.user;
         let strategy_key = ctx.accounts.position.strategy;
-        let bump = ctx.accounts.position.bump;
 
-        // Update strategy subscriber count before closing
+        // Update pool and subscriber count before closing
         let strategy = &mut ctx.accounts.strategy;
+        strategy.total_shares = strategy.total_shares
+            .checked_sub(shares)
+            .ok_or(VaultError::MathOverflow)?;
+        strategy.base_balance = strategy.base_balance
+            .checked_sub(base_amount)
+            .ok_or(VaultError::MathOverflow)?;
+        strategy.asset_balance = strategy.asset_balance
+            .checked_sub(asset_amount)
+            .ok_or(VaultError::MathOverflow)?;
         strategy.total_subscribers = strategy.total_subscribers
             .checked_sub(1)
             .ok_or(VaultError::MathOverflow)?;
@@ -330,13 +985,135 @@ pub mod vault {
         emit!(UserUnsubscribed {
             user: user_key,
             strategy: strategy_key,
-            withdrawn_amount: withdraw_amount,
+            shares_burned: shares,
+            withdrawn_amount: base_amount,
+            withdrawn_asset_amount: asset_amount,
             timestamp: Clock::get()?.unix_timestamp,
         });
 
         // Account will be closed automatically by Anchor's close constraint
         Ok(())
     }
+
+    /// Move a strategy created under the old single-strategy seeds
+    /// (`[b"strategy", trader]`) to the trader's next indexed address.
+    /// The legacy account is closed; subscribers then call `migrate_position`
+    /// to get their legacy deposits back.
+    pub fn migrate_strategy(ctx: Context<MigrateStrategy>) -> Result<()> {
+        let legacy_info = ctx.accounts.legacy_strategy.to_account_info();
+        require_keys_eq!(*legacy_info.owner, crate::ID, VaultError::InvalidLegacyStrategy);
+
+        let legacy = {
+            let data = legacy_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == Strategy::DISCRIMINATOR,
+                VaultError::InvalidLegacyStrategy
+            );
+            LegacyStrategy::deserialize(&mut &data[8..])
+                .map_err(|_| error!(VaultError::InvalidLegacyStrategy))?
+        };
+        require_keys_eq!(legacy.trader, ctx.accounts.trader.key(), VaultError::InvalidLegacyStrategy);
+
+        let profile = &mut ctx.accounts.trader_profile;
+        let index = profile.strategy_count;
+        profile.strategy_count = profile.strategy_count.checked_add(1)
+            .ok_or(VaultError::MathOverflow)?;
+
+        let strategy = &mut ctx.accounts.strategy;
+        strategy.trader = legacy.trader;
+        strategy.name = legacy.name;
+        strategy.description = legacy.description;
+        strategy.performance_fee_bps = legacy.performance_fee_bps;
+        // Legacy positions are refunded, not moved; subscribers are counted
+        // again as they subscribe to the indexed strategy
+        strategy.total_subscribers = 0;
+        strategy.total_volume_traded = legacy.total_volume_traded;
+        strategy.total_fees_earned = legacy.total_fees_earned;
+        strategy.is_active = legacy.is_active;
+        strategy.created_at = legacy.created_at;
+        strategy.index = index;
+        strategy.migrated_from = Some(legacy_info.key());
+        strategy.base_mint = Pubkey::default();
+        strategy.asset_mint = Pubkey::default();
+        strategy.base_vault = Pubkey::default();
+        strategy.asset_vault = Pubkey::default();
+        strategy.swap_program = Pubkey::default();
+        strategy.min_deposit = 0;
+        strategy.total_shares = 0;
+        strategy.base_balance = 0;
+        strategy.asset_balance = 0;
+        strategy.bump = ctx.bumps.strategy;
+
+        // Close the legacy account back to the trader
+        let trader_info = ctx.accounts.trader.to_account_info();
+        let legacy_lamports = legacy_info.lamports();
+        **legacy_info.try_borrow_mut_lamports()? = 0;
+        **trader_info.try_borrow_mut_lamports()? = trader_info.lamports()
+            .checked_add(legacy_lamports)
+            .ok_or(VaultError::MathOverflow)?;
+        legacy_info.assign(&System::id());
+        legacy_info.realloc(0, false)?;
+
+        emit!(StrategyMigrated {
+            legacy_strategy: legacy_info.key(),
+            strategy: strategy.key(),
+            trader: strategy.trader,
+            index,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close a subscriber's position on a migrated strategy's legacy
+    /// address. Legacy deposits were held as lamports on the position, so
+    /// they go back to the user with the rent; no position is created on the
+    /// indexed strategy, where the user subscribes like anyone else.
+    pub fn migrate_position(ctx: Context<MigratePosition>) -> Result<()> {
+        let legacy_info = ctx.accounts.legacy_position.to_account_info();
+        require_keys_eq!(*legacy_info.owner, crate::ID, VaultError::InvalidLegacyPosition);
+
+        let legacy = {
+            let data = legacy_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == UserPosition::DISCRIMINATOR,
+                VaultError::InvalidLegacyPosition
+            );
+            LegacyPosition::deserialize(&mut &data[8..])
+                .map_err(|_| error!(VaultError::InvalidLegacyPosition))?
+        };
+        require_keys_eq!(legacy.user, ctx.accounts.user.key(), VaultError::InvalidLegacyPosition);
+        require!(
+            ctx.accounts.strategy.migrated_from == Some(legacy.strategy),
+            VaultError::StrategyNotMigrated
+        );
+        let expected = Pubkey::create_program_address(
+            &[b"position", legacy.user.as_ref(), legacy.strategy.as_ref(), &[legacy.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| error!(VaultError::InvalidLegacyPosition))?;
+        require_keys_eq!(expected, legacy_info.key(), VaultError::InvalidLegacyPosition);
+
+        // Close the legacy account, deposit and rent, back to the user
+        let user_info = ctx.accounts.user.to_account_info();
+        let legacy_lamports = legacy_info.lamports();
+        **legacy_info.try_borrow_mut_lamports()? = 0;
+        **user_info.try_borrow_mut_lamports()? = user_info.lamports()
+            .checked_add(legacy_lamports)
+            .ok_or(VaultError::MathOverflow)?;
+        legacy_info.assign(&System::id());
+        legacy_info.realloc(0, false)?;
+
+        emit!(PositionMigrated {
+            user: legacy.user,
+            legacy_position: legacy_info.key(),
+            strategy: ctx.accounts.strategy.key(),
+            refunded_lamports: legacy_lamports,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -344,55 +1121,382 @@ pub mod vault {
 // ============================================================================
 
 #[account]
-pub struct Strategy {
-    pub trader: Pubkey,                // 32
-    pub name: String,                  // 4 + 50
-    pub description: String,           // 4 + 500
-    pub performance_fee_bps: u16,      // 2
-    pub total_subscribers: u32,        // 4
-    pub total_volume_traded: u64,      // 8
-    pub total_fees_earned: u64,        // 8
-    pub is_active: bool,               // 1
-    pub created_at: i64,               // 8
+pub struct TradingConfig {
+    pub admin: Pubkey,                 // 32
     pub bump: u8,                      // 1
 }
 
-impl Strategy {
-    pub const LEN: usize = 8 + 32 + 54 + 504 + 2 + 4 + 8 + 8 + 1 + 8 + 1;
+impl TradingConfig {
+    pub const LEN: usize = 8 + 32 + 1;
 }
 
+/// A pool allowlisted for strategies on one base/asset pair, with the
+/// admin's reference price that bounds trades through it
 #[account]
-pub struct UserPosition {
-    pub user: Pubkey,                  // 32
-    pub strategy: Pubkey,              // 32
-    pub initial_balance: u64,          // 8
-    pub current_balance: u64,          // 8
-    pub total_fees_paid: u64,          // 8
-    pub last_fee_settlement: i64,      // 8
-    pub subscribed_at: i64,            // 8
-    pub is_active: bool,               // 1
+pub struct SwapVenue {
+    pub base_mint: Pubkey,             // 32
+    pub asset_mint: Pubkey,            // 32
+    pub swap_program: Pubkey,          // 32
+    pub pool: Pubkey,                  // 32
+    pub pool_base_vault: Pubkey,       // 32
+    pub pool_asset_vault: Pubkey,      // 32
+    pub reference_base: u64,           // 8  base units worth `reference_asset`
+    pub reference_asset: u64,          // 8  asset units worth `reference_base`
+    pub max_deviation_bps: u16,        // 2
+    pub price_updated_at: i64,         // 8
     pub bump: u8,                      // 1
 }
 
-impl UserPosition {
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1;
-}
+impl SwapVenue {
+    pub const LEN: usize = 8 + 32 * 6 + 8 + 8 + 2 + 8 + 1;
+
+    /// Lowest acceptable output for trading `amount_in`: the reference
+    /// price applied to it, less `max_deviation_bps`. Fails once the
+    /// reference price is older than `MAX_REFERENCE_PRICE_AGE`.
+    pub fn minimum_output(&self, side: TradeSide, amount_in: u64, now: i64) -> Result<u64> {
+        self.require_fresh_price(now)?;
+        let reference_out = match side {
+            TradeSide::Buy => mul_div(amount_in, self.reference_asset, self.reference_base, false)?,
+            TradeSide::Sell => mul_div(amount_in, self.reference_base, self.reference_asset, false)?,
+        };
+        let minimum = mul_div(
+            reference_out,
+            BASIS_POINTS_DIVISOR - u64::from(self.max_deviation_bps),
+            BASIS_POINTS_DIVISOR,
+            false,
+        )?;
+        require!(minimum > 0, VaultError::InvalidTradeAmount);
+        Ok(minimum)
+    }
+
+    /// `asset_amount` valued in base tokens at the reference price, rounded
+    /// down. Fails once the reference price is stale.
+    pub fn asset_value(&self, asset_amount: u64, now: i64) -> Result<u64> {
+        self.require_fresh_price(now)?;
+        mul_div(asset_amount, self.reference_base, self.reference_asset, false)
+    }
+
+    fn require_fresh_price(&self, now: i64) -> Result<()> {
+        require!(
+            now.saturating_sub(self.price_updated_at) <= MAX_REFERENCE_PRICE_AGE,
+            VaultError::StaleReferencePrice
+        );
+        Ok(())
+    }
+}
+
+#[account]
+pub struct TraderProfile {
+    pub trader: Pubkey,                // 32
+    pub strategy_count: u32,           // 4
+    pub bump: u8,                      // 1
+}
+
+impl TraderProfile {
+    pub const LEN: usize = 8 + 32 + 4 + 1;
+}
+
+#[account]
+pub struct Strategy {
+    pub trader: Pubkey,                // 32
+    pub name: String,                  // 4 + 50
+    pub description: String,           // 4 + 500
+    pub performance_fee_bps: u16,      // 2
+    pub total_subscribers: u32,        // 4
+    pub total_volume_traded: u64,      // 8
+    pub total_fees_earned: u64,        // 8
+    pub is_active: bool,               // 1
+    pub created_at: i64,               // 8
+    pub index: u32,                    // 4
+    pub migrated_from: Option<Pubkey>, // 1 + 32
+    pub base_mint: Pubkey,             // 32
+    pub asset_mint: Pubkey,            // 32
+    pub base_vault: Pubkey,            // 32
+    pub asset_vault: Pubkey,           // 32
+    pub swap_program: Pubkey,          // 32
+    pub min_deposit: u64,              // 8  base units, set with the vaults
+    pub total_shares: u64,             // 8
+    pub base_balance: u64,             // 8  base tokens owned by the pool
+    pub asset_balance: u64,            // 8  asset tokens owned by the pool
+    pub bump: u8,                      // 1
+}
+
+impl Strategy {
+    pub const LEN: usize = 8 + 32 + 54 + 504 + 2 + 4 + 8 + 8 + 1 + 8 + 4 + 33 + 32 * 5 + 8 * 4 + 1;
+
+    /// Pool value in base tokens: the base balance plus the asset balance at
+    /// the venue's reference price. A flat pool needs no price.
+    pub fn nav(&self, venue: &SwapVenue, now: i64) -> Result<u64> {
+        if self.asset_balance == 0 {
+            return Ok(self.base_balance);
+        }
+        self.base_balance
+            .checked_add(venue.asset_value(self.asset_balance, now)?)
+            .ok_or_else(|| error!(VaultError::MathOverflow))
+    }
+
+    /// Shares minted for a base deposit at `nav`, rounded down.
+    /// The first deposit mints shares 1:1.
+    pub fn shares_for_deposit(&self, amount: u64, nav: u64) -> Result<u64> {
+        if self.total_shares == 0 {
+            return Ok(amount);
+        }
+        require!(nav > 0, VaultError::InvalidNav);
+        mul_div(amount, self.total_shares, nav, false)
+    }
+
+    /// Shares worth `amount` base tokens at `nav`, rounded up
+    pub fn shares_for_withdrawal(&self, amount: u64, nav: u64) -> Result<u64> {
+        require!(nav > 0, VaultError::InvalidNav);
+        mul_div(amount, self.total_shares, nav, true)
+    }
+
+    /// Base tokens `shares` are worth at `nav`, rounded down
+    pub fn value_of_shares(&self, shares: u64, nav: u64) -> Result<u64> {
+        if self.total_shares == 0 {
+            return Ok(0);
+        }
+        mul_div(shares, nav, self.total_shares, false)
+    }
+
+    /// Pro-rata base tokens behind `shares`, rounded down
+    pub fn base_for_shares(&self, shares: u64) -> Result<u64> {
+        if self.total_shares == 0 {
+            return Ok(0);
+        }
+        mul_div(shares, self.base_balance, self.total_shares, false)
+    }
+
+    /// Pro-rata asset tokens behind `shares`, rounded down
+    pub fn asset_for_shares(&self, shares: u64) -> Result<u64> {
+        if self.total_shares == 0 {
+            return Ok(0);
+        }
+        mul_div(shares, self.asset_balance, self.total_shares, false)
+    }
+}
+
+/// `value * numerator / denominator` in u128, rounded down or up
+fn mul_div(value: u64, numerator: u64, denominator: u64, round_up: bool) -> Result<u64> {
+    let product = (value as u128)
+        .checked_mul(numerator as u128)
+        .ok_or(VaultError::MathOverflow)?;
+    let denominator = denominator as u128;
+    let mut quotient = product
+        .checked_div(denominator)
+        .ok_or(VaultError::MathOverflow)?;
+    if round_up && product % denominator != 0 {
+        quotient += 1;
+    }
+    u64::try_from(quotient).map_err(|_| error!(VaultError::MathOverflow))
+}
+
+/// Layout of strategies created before indexed strategy PDAs, read only by
+/// `migrate_strategy`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyStrategy {
+    pub trader: Pubkey,
+    pub name: String,
+    pub description: String,
+    pub performance_fee_bps: u16,
+    pub total_subscribers: u32,
+    pub total_volume_traded: u64,
+    pub total_fees_earned: u64,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct UserPosition {
+    pub user: Pubkey,                  // 32
+    pub strategy: Pubkey,              // 32
+    pub shares: u64,                   // 8
+    pub initial_balance: u64,          // 8  base value at the last fee settlement
+    pub total_fees_paid: u64,          // 8
+    pub last_fee_settlement: i64,      // 8
+    pub subscribed_at: i64,            // 8
+    pub is_active: bool,               // 1
+    pub bump: u8,                      // 1
+}
+
+impl UserPosition {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1;
+}
+
+/// Layout of positions created before token custody, read only by
+/// `migrate_position`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyPosition {
+    pub user: Pubkey,
+    pub strategy: Pubkey,
+    pub initial_balance: u64,
+    pub current_balance: u64,
+    pub total_fees_paid: u64,
+    pub last_fee_settlement: i64,
+    pub subscribed_at: i64,
+    pub is_active: bool,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TradeSide {
+    /// Spend base tokens on the asset
+    Buy,
+    /// Sell the asset back into base tokens
+    Sell,
+}
 
 // ============================================================================
 // Context Structures
 // ============================================================================
 
+#[derive(Accounts)]
+pub struct InitializeTradingConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = TradingConfig::LEN,
+        seeds = [TRADING_CONFIG_SEED],
+        bump
+    )]
+    pub trading_config: Account<'info, TradingConfig>,
+    
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ VaultError::UnauthorizedAdmin
+    )]
+    pub program: Program<'info, crate::program::Vault>,
+    
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ VaultError::UnauthorizedAdmin
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddSwapVenue<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [TRADING_CONFIG_SEED],
+        bump = trading_config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub trading_config: Account<'info, TradingConfig>,
+    
+    pub base_mint: Account<'info, Mint>,
+    
+    #[account(constraint = asset_mint.key() != base_mint.key() @ VaultError::InvalidAssetMint)]
+    pub asset_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = SwapVenue::LEN,
+        seeds = [SWAP_VENUE_SEED, base_mint.key().as_ref(), asset_mint.key().as_ref()],
+        bump
+    )]
+    pub venue: Account<'info, SwapVenue>,
+    
+    /// CHECK: Any program implementing the swap interface, vetted by the admin
+    #[account(executable)]
+    pub swap_program: UncheckedAccount<'info>,
+    
+    /// CHECK: Pool state, laid out by the swap program
+    #[account(owner = swap_program.key() @ VaultError::InvalidSwapPool)]
+    pub pool: UncheckedAccount<'info>,
+    
+    #[account(token::mint = base_mint)]
+    pub pool_base_vault: Account<'info, TokenAccount>,
+    
+    #[account(token::mint = asset_mint)]
+    pub pool_asset_vault: Account<'info, TokenAccount>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageSwapVenue<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [TRADING_CONFIG_SEED],
+        bump = trading_config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub trading_config: Account<'info, TradingConfig>,
+    
+    #[account(
+        mut,
+        seeds = [SWAP_VENUE_SEED, venue.base_mint.as_ref(), venue.asset_mint.as_ref()],
+        bump = venue.bump
+    )]
+    pub venue: Account<'info, SwapVenue>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveSwapVenue<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [TRADING_CONFIG_SEED],
+        bump = trading_config.bump,
+        has_one = admin @ VaultError::UnauthorizedAdmin
+    )]
+    pub trading_config: Account<'info, TradingConfig>,
+    
+    #[account(
+        mut,
+        close = admin,
+        seeds = [SWAP_VENUE_SEED, venue.base_mint.as_ref(), venue.asset_mint.as_ref()],
+        bump = venue.bump
+    )]
+    pub venue: Account<'info, SwapVenue>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTraderProfile<'info> {
+    #[account(mut)]
+    pub trader: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = trader,
+        space = TraderProfile::LEN,
+        seeds = [b"trader_profile", trader.key().as_ref()],
+        bump
+    )]
+    pub trader_profile: Account<'info, TraderProfile>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 #[instruction(name: String)]
 pub struct InitializeStrategy<'info> {
     #[account(mut)]
     pub trader: Signer<'info>,
     
+    #[account(
+        mut,
+        seeds = [b"trader_profile", trader.key().as_ref()],
+        bump = trader_profile.bump,
+        has_one = trader
+    )]
+    pub trader_profile: Account<'info, TraderProfile>,
+    
     #[account(
         init,
         payer = trader,
         space = Strategy::LEN,
-        seeds = [b"strategy", trader.key().as_ref()],
+        seeds = [b"strategy", trader.key().as_ref(), &trader_profile.strategy_count.to_le_bytes()],
         bump
     )]
     pub strategy: Account<'info, Strategy>,
@@ -407,13 +1511,63 @@ pub struct UpdateStrategy<'info> {
     
     #[account(
         mut,
-        seeds = [b"strategy", trader.key().as_ref()],
+        seeds = [b"strategy", trader.key().as_ref(), &strategy.index.to_le_bytes()],
         bump = strategy.bump,
         has_one = trader
     )]
     pub strategy: Account<'info, Strategy>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeStrategyVaults<'info> {
+    #[account(mut)]
+    pub trader: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"strategy", trader.key().as_ref(), &strategy.index.to_le_bytes()],
+        bump = strategy.bump,
+        has_one = trader,
+        constraint = strategy.base_vault == Pubkey::default() @ VaultError::VaultsAlreadyInitialized
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    pub base_mint: Account<'info, Mint>,
+    
+    #[account(constraint = asset_mint.key() != base_mint.key() @ VaultError::InvalidAssetMint)]
+    pub asset_mint: Account<'info, Mint>,
+    
+    /// Only pairs with an allowlisted venue can be traded
+    #[account(
+        seeds = [SWAP_VENUE_SEED, base_mint.key().as_ref(), asset_mint.key().as_ref()],
+        bump = venue.bump
+    )]
+    pub venue: Account<'info, SwapVenue>,
+    
+    #[account(
+        init,
+        payer = trader,
+        token::mint = base_mint,
+        token::authority = strategy,
+        seeds = [b"strategy_vault", strategy.key().as_ref(), base_mint.key().as_ref()],
+        bump
+    )]
+    pub base_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = trader,
+        token::mint = asset_mint,
+        token::authority = strategy,
+        seeds = [b"strategy_vault", strategy.key().as_ref(), asset_mint.key().as_ref()],
+        bump
+    )]
+    pub asset_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct SubscribeToStrategy<'info> {
     #[account(mut)]
@@ -421,11 +1575,18 @@ pub struct SubscribeToStrategy<'info> {
     
     #[account(
         mut,
-        seeds = [b"strategy", strategy.trader.as_ref()],
-        bump = strategy.bump
+        seeds = [b"strategy", strategy.trader.as_ref(), &strategy.index.to_le_bytes()],
+        bump = strategy.bump,
+        has_one = base_vault
     )]
     pub strategy: Account<'info, Strategy>,
     
+    #[account(
+        seeds = [SWAP_VENUE_SEED, strategy.base_mint.as_ref(), strategy.asset_mint.as_ref()],
+        bump = venue.bump
+    )]
+    pub venue: Account<'info, SwapVenue>,
+    
     #[account(
         init,
         payer = user,
@@ -435,6 +1596,13 @@ pub struct SubscribeToStrategy<'info> {
     )]
     pub position: Account<'info, UserPosition>,
     
+    #[account(mut)]
+    pub base_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = strategy.base_mint, token::authority = user)]
+    pub user_base_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
@@ -445,19 +1613,46 @@ pub struct ExecuteTrade<'info> {
     
     #[account(
         mut,
-        seeds = [b"strategy", trader.key().as_ref()],
+        seeds = [b"strategy", trader.key().as_ref(), &strategy.index.to_le_bytes()],
         bump = strategy.bump,
-        has_one = trader
+        has_one = trader,
+        has_one = base_vault,
+        has_one = asset_vault,
+        has_one = swap_program
     )]
     pub strategy: Account<'info, Strategy>,
     
+    #[account(mut)]
+    pub base_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub asset_vault: Account<'info, TokenAccount>,
+    
     #[account(
-        mut,
-        seeds = [b"position", position.user.as_ref(), strategy.key().as_ref()],
-        bump = position.bump,
-        has_one = strategy
+        seeds = [SWAP_VENUE_SEED, strategy.base_mint.as_ref(), strategy.asset_mint.as_ref()],
+        bump = venue.bump,
+        has_one = swap_program @ VaultError::InvalidSwapPool,
+        has_one = pool @ VaultError::InvalidSwapPool,
+        has_one = pool_base_vault @ VaultError::InvalidSwapPool,
+        has_one = pool_asset_vault @ VaultError::InvalidSwapPool
     )]
-    pub position: Account<'info, UserPosition>,
+    pub venue: Account<'info, SwapVenue>,
+    
+    /// CHECK: Allowlisted by the venue, laid out by the swap program
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub pool_base_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub pool_asset_vault: Account<'info, TokenAccount>,
+    
+    /// CHECK: Allowlisted by the venue and pinned by the strategy
+    #[account(executable)]
+    pub swap_program: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
@@ -471,12 +1666,19 @@ pub struct SettleFees<'info> {
     
     #[account(
         mut,
-        seeds = [b"strategy", strategy.trader.as_ref()],
+        seeds = [b"strategy", strategy.trader.as_ref(), &strategy.index.to_le_bytes()],
         bump = strategy.bump,
-        has_one = trader
+        has_one = trader,
+        has_one = base_vault
     )]
     pub strategy: Account<'info, Strategy>,
     
+    #[account(
+        seeds = [SWAP_VENUE_SEED, strategy.base_mint.as_ref(), strategy.asset_mint.as_ref()],
+        bump = venue.bump
+    )]
+    pub venue: Account<'info, SwapVenue>,
+    
     #[account(
         mut,
         seeds = [b"position", user.key().as_ref(), strategy.key().as_ref()],
@@ -486,7 +1688,13 @@ pub struct SettleFees<'info> {
     )]
     pub position: Account<'info, UserPosition>,
     
-    pub system_program: Program<'info, System>,
+    #[account(mut)]
+    pub base_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = strategy.base_mint, token::authority = trader)]
+    pub trader_base_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
@@ -496,8 +1704,10 @@ pub struct Unsubscribe<'info> {
     
     #[account(
         mut,
-        seeds = [b"strategy", strategy.trader.as_ref()],
-        bump = strategy.bump
+        seeds = [b"strategy", strategy.trader.as_ref(), &strategy.index.to_le_bytes()],
+        bump = strategy.bump,
+        has_one = base_vault,
+        has_one = asset_vault
     )]
     pub strategy: Account<'info, Strategy>,
     
@@ -511,17 +1721,105 @@ pub struct Unsubscribe<'info> {
     )]
     pub position: Account<'info, UserPosition>,
     
+    #[account(mut)]
+    pub base_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = strategy.base_mint, token::authority = user)]
+    pub user_base_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub asset_vault: Account<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = strategy.asset_mint, token::authority = user)]
+    pub user_asset_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateStrategy<'info> {
+    #[account(mut)]
+    pub trader: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"trader_profile", trader.key().as_ref()],
+        bump = trader_profile.bump,
+        has_one = trader
+    )]
+    pub trader_profile: Account<'info, TraderProfile>,
+    
+    /// CHECK: Old-layout strategy at the single-strategy seeds, validated in the handler
+    #[account(
+        mut,
+        seeds = [b"strategy", trader.key().as_ref()],
+        bump
+    )]
+    pub legacy_strategy: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = trader,
+        space = Strategy::LEN,
+        seeds = [b"strategy", trader.key().as_ref(), &trader_profile.strategy_count.to_le_bytes()],
+        bump
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePosition<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [b"strategy", strategy.trader.as_ref(), &strategy.index.to_le_bytes()],
+        bump = strategy.bump
+    )]
+    pub strategy: Account<'info, Strategy>,
+    
+    /// CHECK: Old-layout position on the strategy's legacy address, validated in the handler
+    #[account(mut)]
+    pub legacy_position: UncheckedAccount<'info>,
+}
+
 // ============================================================================
 // Events
 // ============================================================================
 
+#[event]
+pub struct SwapVenueAdded {
+    pub venue: Pubkey,
+    pub base_mint: Pubkey,
+    pub asset_mint: Pubkey,
+    pub swap_program: Pubkey,
+    pub pool: Pubkey,
+    pub max_deviation_bps: u16,
+}
+
+#[event]
+pub struct ReferencePriceUpdated {
+    pub venue: Pubkey,
+    pub reference_base: u64,
+    pub reference_asset: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SwapVenueRemoved {
+    pub venue: Pubkey,
+    pub base_mint: Pubkey,
+    pub asset_mint: Pubkey,
+}
+
 #[event]
 pub struct StrategyCreated {
     pub strategy: Pubkey,
     pub trader: Pubkey,
+    pub index: u32,
     pub name: String,
     pub performance_fee_bps: u16,
     pub timestamp: i64,
@@ -534,21 +1832,33 @@ pub struct StrategyUpdated {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct StrategyVaultsInitialized {
+    pub strategy: Pubkey,
+    pub base_mint: Pubkey,
+    pub asset_mint: Pubkey,
+    pub swap_program: Pubkey,
+    pub min_deposit: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct UserSubscribed {
     pub user: Pubkey,
     pub strategy: Pubkey,
     pub initial_deposit: u64,
+    pub shares: u64,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct TradeExecuted {
     pub strategy: Pubkey,
-    pub user: Pubkey,
-    pub amount: u64,
-    pub profit_or_loss: i64,
-    pub new_balance: u64,
+    pub side: TradeSide,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub base_balance: u64,
+    pub asset_balance: u64,
     pub timestamp: i64,
 }
 
@@ -558,6 +1868,7 @@ pub struct FeesSettled {
     pub strategy: Pubkey,
     pub trader: Pubkey,
     pub fee_amount: u64,
+    pub shares_burned: u64,
     pub remaining_balance: u64,
     pub timestamp: i64,
 }
@@ -566,7 +1877,27 @@ pub struct FeesSettled {
 pub struct UserUnsubscribed {
     pub user: Pubkey,
     pub strategy: Pubkey,
+    pub shares_burned: u64,
     pub withdrawn_amount: u64,
+    pub withdrawn_asset_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyMigrated {
+    pub legacy_strategy: Pubkey,
+    pub strategy: Pubkey,
+    pub trader: Pubkey,
+    pub index: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionMigrated {
+    pub user: Pubkey,
+    pub legacy_position: Pubkey,
+    pub strategy: Pubkey,
+    pub refunded_lamports: u64,
     pub timestamp: i64,
 }
 
@@ -588,7 +1919,7 @@ pub enum VaultError {
     #[msg("Strategy is not active")]
     StrategyInactive,
     
-    #[msg("Insufficient deposit amount (minimum 1 SOL)")]
+    #[msg("Deposit is below the strategy's minimum of one base token")]
     InsufficientDeposit,
     
     #[msg("Position is not active")]
@@ -605,4 +1936,46 @@ pub enum VaultError {
     
     #[msg("Math operation overflow")]
     MathOverflow,
+    
+    #[msg("Account is not a legacy single-strategy account for this trader")]
+    InvalidLegacyStrategy,
+    
+    #[msg("Strategy was not migrated from this position's legacy strategy")]
+    StrategyNotMigrated,
+    
+    #[msg("Account is not a legacy position for this user")]
+    InvalidLegacyPosition,
+    
+    #[msg("Strategy vaults are already initialized")]
+    VaultsAlreadyInitialized,
+    
+    #[msg("Asset mint must differ from the base mint")]
+    InvalidAssetMint,
+    
+    #[msg("Trade amount must be greater than zero")]
+    InvalidTradeAmount,
+    
+    #[msg("Swap moved more than the trade amount out of the vault")]
+    InvalidSwapResult,
+    
+    #[msg("Swap returned less than the minimum amount out")]
+    SlippageExceeded,
+    
+    #[msg("Swap program or pool accounts are not the pair's allowlisted venue")]
+    InvalidSwapPool,
+    
+    #[msg("Strategy has shares outstanding but no value")]
+    InvalidNav,
+    
+    #[msg("Only the trading admin can manage swap venues")]
+    UnauthorizedAdmin,
+    
+    #[msg("Reference price amounts must be greater than zero")]
+    InvalidReferencePrice,
+    
+    #[msg("Venue slippage bound is too wide (max 10%)")]
+    DeviationTooHigh,
+    
+    #[msg("Venue reference price is older than one hour")]
+    StaleReferencePrice,
 }
@@ -1,13 +1,104 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_lang::system_program::{create_account, CreateAccount};
+use anchor_spl::token_2022::{initialize_mint2, InitializeMint2, Token2022};
+use anchor_spl::token_2022_extensions::transfer_fee::{
+    transfer_fee_initialize, TransferFeeInitialize,
+};
+use anchor_spl::token_2022_extensions::transfer_hook::{
+    transfer_hook_initialize, transfer_hook_update, TransferHookInitialize, TransferHookUpdate,
+};
+use anchor_spl::token_interface::{
+    close_account, freeze_account, thaw_account, CloseAccount, FreezeAccount, Mint, ThawAccount,
+    TokenAccount,
+};
+use bytemuck::Zeroable;
+use spl_token_2022::extension::confidential_transfer::ciphertext_extraction::{
+    SourceDecryptHandles, TransferProofContextInfo, TransferWithFeeProofContextInfo,
+};
+use spl_token_2022::extension::confidential_transfer::instruction::{
+    BatchedGroupedCiphertext2HandlesValidityProofContext,
+    BatchedGroupedCiphertext2HandlesValidityProofData, BatchedRangeProofContext,
+    BatchedRangeProofU128Data, BatchedRangeProofU256Data, CiphertextCommitmentEqualityProofContext,
+    CiphertextCommitmentEqualityProofData, FeeSigmaProofContext, FeeSigmaProofData,
+    ProofInstruction, PubkeyValidityData, PubkeyValidityProofContext, WithdrawData,
+    WithdrawProofContext, ZeroBalanceProofContext, ZeroBalanceProofData,
+};
+use spl_token_2022::extension::confidential_transfer::EncryptedBalance;
+use spl_token_2022::extension::transfer_fee::{TransferFee, MAX_FEE_BASIS_POINTS};
+use spl_token_2022::extension::{ExtensionType, StateWithExtensions};
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::ops;
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::pod::{
+    ElGamalCiphertext, ElGamalPubkey, TransferAmountCiphertext,
+};
+
+use allowlist_hook::program::AllowlistHook;
+
+use crate::confidential_transfer;
+
+pub use spl_token_2022::extension::confidential_transfer::MAXIMUM_DEPOSIT_TRANSFER_AMOUNT;
+
+/// Deposits and incoming transfers an account can take before the owner has to
+/// run ApplyPendingBalance (same default as the spl-token CLI)
+pub const MAXIMUM_PENDING_BALANCE_CREDIT_COUNTER: u64 = 65_536;
+
+/// Number of past auditor keys kept in the audit config
+pub const MAX_AUDITOR_HISTORY: usize = 10;
+
+/// Most transfers in one batch_transfer_confidential. The per-transfer
+/// instruction data (100 bytes) and accounts (4, or 6 with fees) keep larger
+/// batches from fitting in a transaction even with an address lookup table.
+pub const MAX_BATCH_TRANSFERS: usize = 8;
+
+/// Remaining accounts per batch transfer: recipient, equality, ciphertext
+/// validity and range proofs, plus the two fee proofs on fee mints
+pub const BATCH_ACCOUNTS_PER_TRANSFER: usize = 4;
+pub const BATCH_ACCOUNTS_PER_TRANSFER_WITH_FEE: usize = 6;
+
+/// Compute budget of a batch. These are unmeasured estimates: they're counted
+/// from the code paths, with the curve25519 syscalls at their runtime cost
+/// (multiply 2 208, add/subtract ~520), and rounded up by about half:
+/// - base, ~12k: dispatch, the sender account and mint unpacked with their
+///   extensions, the entries deserialized and the batch event
+/// - per transfer, ~32k: the recipient and proof contexts checked by the
+///   vault and again by Token-2022, the CPI, and Token-2022's lo/hi
+///   ciphertext arithmetic on the source and destination balances
+/// - per transfer with fee, ~70k: on top of that, both programs check the fee
+///   proofs against the fee parameters (~14k of curve ops each) and Token-2022
+///   moves the fee out of the destination into its withheld amount
+///
+/// The `*_fits_its_compute_budget` tests in tests/batch_transfer.rs measure a
+/// full batch, but skip unless the BPF builds of the vault and Token-2022 are
+/// there, so nothing has checked these numbers yet.
+pub const BATCH_TRANSFER_BASE_COMPUTE_UNITS: u32 = 20_000;
+pub const BATCH_TRANSFER_COMPUTE_UNITS_PER_TRANSFER: u32 = 50_000;
+pub const BATCH_TRANSFER_COMPUTE_UNITS_PER_TRANSFER_WITH_FEE: u32 = 110_000;
+
+/// Longest memo ciphertext accepted on a transfer, authentication tag
+/// included
+pub const MAX_MEMO_CIPHERTEXT_LEN: usize = 256;
+
+pub const CONFIDENTIAL_AUTHORITY_SEED: &[u8] = b"confidential_authority";
+pub const AUDIT_CONFIG_SEED: &[u8] = b"audit_config";
+pub const APPROVAL_REQUEST_SEED: &[u8] = b"approval";
+pub const MINT_AUTHORITY_SEED: &[u8] = b"mint_authority";
+pub const CREDIT_POLICY_SEED: &[u8] = b"credit_policy";
+pub const FREEZE_AUTHORITY_SEED: &[u8] = b"freeze_authority";
+pub const HOOK_AUTHORITY_SEED: &[u8] = b"hook_authority";
 
 /// Initialize a confidential transfer enabled mint for private payments
 /// This creates a USDC-like token with encrypted balances
+///
+/// With `transfer_hook_program_id` (e.g. the allowlist-hook program) every
+/// transfer of the mint is checked by that program. A PDA is the hook
+/// authority, so only the compliance authority can repoint the hook or set
+/// up the allowlist.
 pub fn initialize_confidential_mint(
     ctx: Context<InitializeMint>,
     decimals: u8,
     auto_approve_new_accounts: bool,
     auditor_elgamal_pubkey: Option<[u8; 32]>,
+    transfer_fee: Option<TransferFeeParams>,
+    transfer_hook_program_id: Option<Pubkey>,
 ) -> Result<()> {
     msg!("🔐 Initializing confidential mint with Token-2022");
     msg!("Decimals: {}", decimals);
@@ -17,139 +108,1728 @@ pub fn initialize_confidential_mint(
         msg!("✅ Auditor key configured for compliance: {:?}", &auditor_key[0..8]);
     }
     
-    // Mint is created with confidential transfer extension
-    // Extension initialization happens via CPI to Token-2022 program
+    let mut extensions = vec![ExtensionType::ConfidentialTransferMint];
+    if let Some(fee) = &transfer_fee {
+        require!(
+            fee.transfer_fee_basis_points <= MAX_FEE_BASIS_POINTS,
+            PrivacyError::InvalidTransferFee
+        );
+        msg!(
+            "💸 Transfer fee: {} bps, max {}",
+            fee.transfer_fee_basis_points,
+            fee.maximum_fee
+        );
+        extensions.push(ExtensionType::TransferFeeConfig);
+        extensions.push(ExtensionType::ConfidentialTransferFeeConfig);
+    }
+    if let Some(program_id) = &transfer_hook_program_id {
+        msg!("🪝 Transfer hook: {}", program_id);
+        extensions.push(ExtensionType::TransferHook);
+    }
+    
+    // Allocate the mint with room for the confidential transfer (and fee)
+    // extensions. Extensions must be initialized before the base mint, so we
+    // can't use Anchor's `init` constraint here.
+    let mint_len =
+        ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extensions)?;
+    let lamports = Rent::get()?.minimum_balance(mint_len);
+    
+    create_account(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            CreateAccount {
+                from: ctx.accounts.payer.to_account_info(),
+                to: ctx.accounts.mint.to_account_info(),
+            },
+        ),
+        lamports,
+        mint_len as u64,
+        &ctx.accounts.token_program.key(),
+    )?;
+    
+    // A program PDA administers the confidential transfer config (approving
+    // accounts, rotating the auditor key) so those actions go through the
+    // checks in this program
+    confidential_transfer::initialize_mint(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            confidential_transfer::ConfidentialTransferInitializeMint {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+            },
+        ),
+        Some(ctx.accounts.confidential_authority.key()),
+        auto_approve_new_accounts,
+        auditor_elgamal_pubkey,
+    )?;
+    
+    // Fee settings and withheld fee withdrawals also go through the PDA, so
+    // the treasury payout is gated by the audit authority in this program
+    if let Some(fee) = transfer_fee {
+        let confidential_authority = ctx.accounts.confidential_authority.key();
+        transfer_fee_initialize(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferFeeInitialize {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                },
+            ),
+            Some(&confidential_authority),
+            Some(&confidential_authority),
+            fee.transfer_fee_basis_points,
+            fee.maximum_fee,
+        )?;
+        
+        confidential_transfer::initialize_confidential_transfer_fee_config(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                confidential_transfer::InitializeConfidentialTransferFeeConfig {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                },
+            ),
+            Some(confidential_authority),
+            fee.withdraw_withheld_authority_elgamal_pubkey,
+        )?;
+    }
+    
+    // Repointing the hook changes the transfer policy, so it goes through
+    // a PDA gated by the compliance authority like freezing does
+    if let Some(program_id) = transfer_hook_program_id {
+        transfer_hook_initialize(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferHookInitialize {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                },
+            ),
+            Some(ctx.accounts.hook_authority.key()),
+            Some(program_id),
+        )?;
+    }
+    
+    // Freezing goes through a PDA too, so only the compliance authority
+    // recorded in the audit config can freeze or thaw accounts
+    initialize_mint2(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            InitializeMint2 {
+                mint: ctx.accounts.mint.to_account_info(),
+            },
+        ),
+        decimals,
+        &ctx.accounts.mint_authority.key(),
+        Some(&ctx.accounts.freeze_authority.key()),
+    )?;
+    
+    let audit_config = &mut ctx.accounts.audit_config;
+    audit_config.mint = ctx.accounts.mint.key();
+    audit_config.authority = ctx.accounts.payer.key();
+    audit_config.kyc_authority = ctx.accounts.payer.key();
+    audit_config.compliance_authority = ctx.accounts.payer.key();
+    audit_config.fee_treasury = Pubkey::default();
+    audit_config.auditor_elgamal_pubkey = auditor_elgamal_pubkey.unwrap_or_default();
+    audit_config.auto_approve_new_accounts = auto_approve_new_accounts;
+    audit_config.rotation_count = 0;
+    audit_config.last_rotated_at = Clock::get()?.unix_timestamp;
+    audit_config.history = Vec::new();
+    audit_config.confidential_authority_bump = ctx.bumps.confidential_authority;
+    audit_config.freeze_authority_bump = ctx.bumps.freeze_authority;
+    audit_config.hook_authority_bump = ctx.bumps.hook_authority;
+    audit_config.bump = ctx.bumps.audit_config;
+    
+    msg!("✅ Confidential mint initialized: {}", ctx.accounts.mint.key());
+    Ok(())
+}
+
+/// Rotate the auditor ElGamal key and/or toggle auto-approval of new accounts
+/// Only the audit authority recorded for the mint can call this
+pub fn update_confidential_mint(
+    ctx: Context<UpdateConfidentialMint>,
+    auto_approve_new_accounts: bool,
+    auditor_elgamal_pubkey: Option<[u8; 32]>,
+) -> Result<()> {
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        CONFIDENTIAL_AUTHORITY_SEED,
+        mint_key.as_ref(),
+        &[ctx.accounts.audit_config.confidential_authority_bump],
+    ]];
+    
+    confidential_transfer::update_mint(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            confidential_transfer::ConfidentialTransferUpdateMint {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.confidential_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        auto_approve_new_accounts,
+        auditor_elgamal_pubkey,
+    )?;
+    
+    let clock = Clock::get()?;
+    let audit_config = &mut ctx.accounts.audit_config;
+    let new_auditor = auditor_elgamal_pubkey.unwrap_or_default();
+    
+    let previous_auditor = audit_config.auditor_elgamal_pubkey;
+    
+    if new_auditor != previous_auditor {
+        if audit_config.history.len() == MAX_AUDITOR_HISTORY {
+            audit_config.history.remove(0);
+        }
+        audit_config.history.push(AuditorRotation {
+            previous_auditor,
+            new_auditor,
+            rotated_by: ctx.accounts.authority.key(),
+            rotated_at: clock.unix_timestamp,
+        });
+        audit_config.auditor_elgamal_pubkey = new_auditor;
+        audit_config.rotation_count = audit_config.rotation_count
+            .checked_add(1)
+            .ok_or(PrivacyError::MathOverflow)?;
+        audit_config.last_rotated_at = clock.unix_timestamp;
+        
+        emit!(AuditorRotated {
+            mint: mint_key,
+            previous_auditor,
+            new_auditor,
+            rotation_count: audit_config.rotation_count,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+    audit_config.auto_approve_new_accounts = auto_approve_new_accounts;
+    
+    msg!("✅ Confidential mint config updated");
+    Ok(())
+}
+
+/// Hand the audit authority for a mint to a new key
+pub fn set_audit_authority(
+    ctx: Context<ManageAuditConfig>,
+    new_authority: Pubkey,
+) -> Result<()> {
+    let audit_config = &mut ctx.accounts.audit_config;
+    let previous_authority = audit_config.authority;
+    audit_config.authority = new_authority;
+    
+    emit!(AuditAuthorityChanged {
+        mint: audit_config.mint,
+        previous_authority,
+        new_authority,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Hand the KYC authority (approves new confidential accounts) to a new key
+pub fn set_kyc_authority(
+    ctx: Context<ManageAuditConfig>,
+    new_kyc_authority: Pubkey,
+) -> Result<()> {
+    let audit_config = &mut ctx.accounts.audit_config;
+    let previous_authority = audit_config.kyc_authority;
+    audit_config.kyc_authority = new_kyc_authority;
+    
+    emit!(KycAuthorityChanged {
+        mint: audit_config.mint,
+        previous_authority,
+        new_authority: new_kyc_authority,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Hand the compliance authority (freezes and thaws accounts) to a new key
+pub fn set_compliance_authority(
+    ctx: Context<ManageAuditConfig>,
+    new_compliance_authority: Pubkey,
+) -> Result<()> {
+    let audit_config = &mut ctx.accounts.audit_config;
+    let previous_authority = audit_config.compliance_authority;
+    audit_config.compliance_authority = new_compliance_authority;
+    
+    emit!(ComplianceAuthorityChanged {
+        mint: audit_config.mint,
+        previous_authority,
+        new_authority: new_compliance_authority,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Queue a configured confidential account for KYC approval
+/// Only needed on mints created with auto_approve_new_accounts = false.
+/// Each request is its own PDA, paid for by the owner and refunded to them
+/// when the KYC authority approves or rejects it.
+pub fn request_account_approval(ctx: Context<RequestAccountApproval>) -> Result<()> {
+    let mint = confidential_transfer::mint_extension(&ctx.accounts.mint.to_account_info())?;
+    require!(
+        !bool::from(mint.auto_approve_new_accounts),
+        PrivacyError::ApprovalNotRequired
+    );
+    let extension = confidential_transfer::account_extension(
+        &ctx.accounts.confidential_account.to_account_info(),
+    )?;
+    require!(
+        !bool::from(extension.approved),
+        PrivacyError::ApprovalNotRequired
+    );
+    
+    let clock = Clock::get()?;
+    let request = &mut ctx.accounts.approval_request;
+    request.mint = ctx.accounts.mint.key();
+    request.token_account = ctx.accounts.confidential_account.key();
+    request.owner = ctx.accounts.owner.key();
+    request.requested_at = clock.unix_timestamp;
+    request.bump = ctx.bumps.approval_request;
+    
+    emit!(AccountApprovalRequested {
+        mint: request.mint,
+        token_account: request.token_account,
+        owner: request.owner,
+        timestamp: clock.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Approve a pending confidential account (KYC authority only)
+pub fn approve_confidential_account(ctx: Context<ReviewAccountApproval>) -> Result<()> {
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        CONFIDENTIAL_AUTHORITY_SEED,
+        mint_key.as_ref(),
+        &[ctx.accounts.audit_config.confidential_authority_bump],
+    ]];
+    
+    confidential_transfer::approve_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        confidential_transfer::ApproveAccount {
+            token_program_id: ctx.accounts.token_program.to_account_info(),
+            token_account: ctx.accounts.confidential_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            authority: ctx.accounts.confidential_authority.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+    
+    emit!(AccountApproved {
+        mint: mint_key,
+        token_account: ctx.accounts.confidential_account.key(),
+        owner: ctx.accounts.approval_request.owner,
+        kyc_authority: ctx.accounts.kyc_authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    // The request account is closed back to its owner by the context
+    Ok(())
+}
+
+/// Reject a pending confidential account (KYC authority only)
+/// The account stays unapproved and its request is closed
+pub fn reject_confidential_account(
+    ctx: Context<ReviewAccountApproval>,
+    reason_code: u16,
+) -> Result<()> {
+    emit!(AccountRejected {
+        mint: ctx.accounts.mint.key(),
+        token_account: ctx.accounts.confidential_account.key(),
+        owner: ctx.accounts.approval_request.owner,
+        kyc_authority: ctx.accounts.kyc_authority.key(),
+        reason_code,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Freeze a confidential account (compliance authority only)
+/// Nothing moves in or out of the account, encrypted or public, until it is
+/// thawed. `reason_code` is recorded in the AccountFrozen event.
+pub fn freeze_confidential_account(
+    ctx: Context<ComplianceAction>,
+    reason_code: u16,
+) -> Result<()> {
+    require!(
+        !ctx.accounts.confidential_account.is_frozen(),
+        PrivacyError::AccountAlreadyFrozen
+    );
+    
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        FREEZE_AUTHORITY_SEED,
+        mint_key.as_ref(),
+        &[ctx.accounts.audit_config.freeze_authority_bump],
+    ]];
+    
+    freeze_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        FreezeAccount {
+            account: ctx.accounts.confidential_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            authority: ctx.accounts.freeze_authority.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+    
+    emit!(AccountFrozen {
+        mint: mint_key,
+        token_account: ctx.accounts.confidential_account.key(),
+        owner: ctx.accounts.confidential_account.owner,
+        compliance_authority: ctx.accounts.compliance_authority.key(),
+        reason_code,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("🧊 Confidential account frozen");
+    Ok(())
+}
+
+/// Thaw a frozen confidential account (compliance authority only)
+pub fn thaw_confidential_account(
+    ctx: Context<ComplianceAction>,
+    reason_code: u16,
+) -> Result<()> {
+    require!(
+        ctx.accounts.confidential_account.is_frozen(),
+        PrivacyError::AccountNotFrozen
+    );
+    
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        FREEZE_AUTHORITY_SEED,
+        mint_key.as_ref(),
+        &[ctx.accounts.audit_config.freeze_authority_bump],
+    ]];
+    
+    thaw_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        ThawAccount {
+            account: ctx.accounts.confidential_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            authority: ctx.accounts.freeze_authority.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+    
+    emit!(AccountThawed {
+        mint: mint_key,
+        token_account: ctx.accounts.confidential_account.key(),
+        owner: ctx.accounts.confidential_account.owner,
+        compliance_authority: ctx.accounts.compliance_authority.key(),
+        reason_code,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("✅ Confidential account thawed");
+    Ok(())
+}
+
+/// Point the mint's transfer hook at another program, or at none
+/// (compliance authority only)
+pub fn update_transfer_hook(
+    ctx: Context<ManageTransferHook>,
+    transfer_hook_program_id: Option<Pubkey>,
+) -> Result<()> {
+    let previous_program_id =
+        confidential_transfer::transfer_hook_program_id(&ctx.accounts.mint.to_account_info())?;
+    
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        HOOK_AUTHORITY_SEED,
+        mint_key.as_ref(),
+        &[ctx.accounts.audit_config.hook_authority_bump],
+    ]];
+    
+    transfer_hook_update(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferHookUpdate {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.hook_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        transfer_hook_program_id,
+    )?;
+    
+    emit!(TransferHookUpdated {
+        mint: mint_key,
+        previous_program_id,
+        new_program_id: transfer_hook_program_id,
+        compliance_authority: ctx.accounts.compliance_authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("🪝 Transfer hook updated");
+    Ok(())
+}
+
+/// Set up the allowlist-hook program for a mint hooked to it, with the
+/// compliance authority as the allowlist admin (compliance authority only)
+pub fn initialize_transfer_hook_allowlist(ctx: Context<InitializeTransferHookAllowlist>) -> Result<()> {
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        HOOK_AUTHORITY_SEED,
+        mint_key.as_ref(),
+        &[ctx.accounts.audit_config.hook_authority_bump],
+    ]];
+    
+    allowlist_hook::cpi::initialize_allowlist(
+        CpiContext::new_with_signer(
+            ctx.accounts.allowlist_program.to_account_info(),
+            allowlist_hook::cpi::accounts::InitializeAllowlist {
+                payer: ctx.accounts.compliance_authority.to_account_info(),
+                authority: ctx.accounts.hook_authority.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                extra_account_meta_list: ctx.accounts.extra_account_meta_list.to_account_info(),
+                config: ctx.accounts.allowlist_config.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        ctx.accounts.compliance_authority.key(),
+    )
+}
+
+/// Initialize user's confidential token account
+/// Enables them to receive and send private payments
+pub fn initialize_user_account(
+    ctx: Context<InitializeUserAccount>,
+    elgamal_pubkey: [u8; 32],
+    decryptable_zero_balance: [u8; 36],
+    proof_instruction_offset: i8,
+) -> Result<()> {
+    msg!("🔐 Initializing confidential account for user");
+    msg!("ElGamal public key: {:?}", &elgamal_pubkey[0..8]);
+    
+    // The PubkeyValidityProof shows the user knows the secret key for the
+    // ElGamal pubkey being registered. Token-2022 reads the pubkey from the
+    // proof, so make sure it's the one the user asked us to configure.
+    let proof = confidential_transfer::proof_context::<PubkeyValidityData, PubkeyValidityProofContext>(
+        ProofInstruction::VerifyPubkeyValidity,
+        &ctx.accounts.proof_account.to_account_info(),
+        proof_instruction_offset,
+    )?;
+    require!(
+        proof.pubkey == ElGamalPubkey(elgamal_pubkey),
+        PrivacyError::InvalidProof
+    );
+    
+    // On fee mints ConfigureAccount also sets up the encrypted withheld fee
+    // balance, which needs its own space
+    let mut extensions = vec![ExtensionType::ConfidentialTransferAccount];
+    if confidential_transfer::transfer_fee_config(&ctx.accounts.mint.to_account_info())?.is_some() {
+        extensions.push(ExtensionType::ConfidentialTransferFeeAmount);
+    }
+    
+    confidential_transfer::reallocate(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            confidential_transfer::Reallocate {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                account: ctx.accounts.confidential_account.to_account_info(),
+                payer: ctx.accounts.user.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                owner: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        &extensions,
+    )?;
+    
+    // User generates ElGamal and AE keys client-side
+    // Only they can decrypt their balance
+    confidential_transfer::configure_account(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            confidential_transfer::ConfigureAccount {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                token_account: ctx.accounts.confidential_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                proof_account: ctx.accounts.proof_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        decryptable_zero_balance,
+        MAXIMUM_PENDING_BALANCE_CREDIT_COUNTER,
+        proof_instruction_offset,
+    )?;
+    
+    msg!("✅ Confidential account configured");
+    Ok(())
+}
+
+/// Deposit tokens into confidential account
+/// Converts public balance to encrypted balance
+pub fn deposit_confidential(
+    ctx: Context<DepositConfidential>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, PrivacyError::InvalidAmount);
+    require!(
+        amount <= MAXIMUM_DEPOSIT_TRANSFER_AMOUNT,
+        PrivacyError::InvalidAmount
+    );
+    require!(
+        ctx.accounts.confidential_account.amount >= amount,
+        PrivacyError::InsufficientBalance
+    );
+    
+    // The deposited amount is public, so it's a public credit as far as a
+    // confidential-only account is concerned
+    require!(
+        !CreditPolicy::is_confidential_only(&ctx.accounts.credit_policy)?,
+        PrivacyError::ConfidentialOnlyAccount
+    );
+    
+    msg!("💰 Depositing {} tokens into confidential account", amount);
+    msg!("Balance will be encrypted on-chain");
+    
+    // Moves the public balance of the account into its pending encrypted
+    // balance. The owner rolls it into the available balance with
+    // ApplyPendingBalance once they've decrypted the new total client-side.
+    confidential_transfer::deposit(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            confidential_transfer::Deposit {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                token_account: ctx.accounts.confidential_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+    )?;
+    
+    msg!("✅ Deposit successful - balance now encrypted");
+    Ok(())
+}
+
+/// Roll pending balance (deposits and incoming transfers) into the
+/// available balance so it can be spent
+pub fn apply_pending_balance(
+    ctx: Context<ApplyPendingBalance>,
+    expected_pending_balance_credit_counter: u64,
+    new_decryptable_available_balance: [u8; 36],
+) -> Result<()> {
+    CreditPolicy::check_public_credits_disabled(
+        &ctx.accounts.credit_policy,
+        &ctx.accounts.confidential_account.to_account_info(),
+    )?;
+    
+    let extension = confidential_transfer::account_extension(
+        &ctx.accounts.confidential_account.to_account_info(),
+    )?;
+    
+    // The client computes the new AE balance from the pending credits it has
+    // seen. If more landed since then, the AE balance would drift from the
+    // ElGamal one, so make them decrypt again instead.
+    let pending_balance_credit_counter = u64::from(extension.pending_balance_credit_counter);
+    require!(
+        expected_pending_balance_credit_counter == pending_balance_credit_counter,
+        PrivacyError::StalePendingBalanceCounter
+    );
+    
+    msg!("📥 Applying {} pending credits", pending_balance_credit_counter);
+    
+    confidential_transfer::apply_pending_balance(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            confidential_transfer::ApplyPendingBalance {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                token_account: ctx.accounts.confidential_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        expected_pending_balance_credit_counter,
+        new_decryptable_available_balance,
+    )?;
+    
+    msg!("✅ Pending balance applied");
+    Ok(())
+}
+
+/// Send private payment to another user
+/// Amount is encrypted, only sender and recipient can see it
+/// On fee mints the protocol fee is withheld, encrypted, in the recipient account
+/// An optional encrypted memo is published in a ConfidentialTransferMemo event
+pub fn transfer_confidential<'info>(
+    ctx: Context<'_, '_, '_, 'info, TransferConfidential<'info>>,
+    new_source_decryptable_available_balance: [u8; 36],
+    source_decrypt_handles: [u8; 64],
+    memo: Option<ConfidentialMemo>,
+) -> Result<()> {
+    msg!("🔒 Executing confidential transfer");
+    msg!("Amount: ████████ (encrypted on-chain)");
+    
+    if let Some(memo) = &memo {
+        require!(
+            !memo.ciphertext.is_empty() && memo.ciphertext.len() <= MAX_MEMO_CIPHERTEXT_LEN,
+            PrivacyError::InvalidMemo
+        );
+    }
+    
+    CreditPolicy::check_public_credits_disabled(
+        &ctx.accounts.credit_policy,
+        &ctx.accounts.sender_account.to_account_info(),
+    )?;
+    
+    let source_decrypt_handles: SourceDecryptHandles =
+        bytemuck::pod_read_unaligned(&source_decrypt_handles);
+    
+    let accounts = &ctx.accounts;
+    execute_transfer(
+        TransferLeg {
+            token_program: &accounts.token_program.to_account_info(),
+            sender: &accounts.sender.to_account_info(),
+            sender_account: &accounts.sender_account.to_account_info(),
+            recipient_account: &accounts.recipient_account.to_account_info(),
+            mint: &accounts.mint.to_account_info(),
+            equality_proof: &accounts.equality_proof.to_account_info(),
+            ciphertext_validity_proof: &accounts.ciphertext_validity_proof.to_account_info(),
+            fee_sigma_proof: accounts
+                .fee_sigma_proof
+                .as_ref()
+                .map(|proof| proof.to_account_info())
+                .as_ref(),
+            fee_ciphertext_validity_proof: accounts
+                .fee_ciphertext_validity_proof
+                .as_ref()
+                .map(|proof| proof.to_account_info())
+                .as_ref(),
+            range_proof: &accounts.range_proof.to_account_info(),
+            hook_accounts: ctx.remaining_accounts,
+            signer_seeds: &[],
+        },
+        new_source_decryptable_available_balance,
+        &source_decrypt_handles,
+    )?;
+    
+    if let Some(memo) = memo {
+        emit!(ConfidentialTransferMemo {
+            mint: ctx.accounts.mint.key(),
+            sender_account: ctx.accounts.sender_account.key(),
+            recipient_account: ctx.accounts.recipient_account.key(),
+            memo,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+    
+    msg!("✅ Confidential transfer complete - amounts remain encrypted");
+    Ok(())
+}
+
+/// Pay several recipients from one confidential account in one instruction
+///
+/// Each transfer takes its accounts from `remaining_accounts`, in order:
+/// recipient account, equality proof, ciphertext validity proof, range
+/// proof, and on fee mints the fee sigma and fee ciphertext validity proofs
+/// after those. Every entry carries the sender's decryptable balance after
+/// that transfer. All transfers land or none do; a failing entry is reported
+/// as `transfers[i]` in the error origin.
+pub fn batch_transfer_confidential<'info>(
+    ctx: Context<'_, '_, '_, 'info, BatchTransferConfidential<'info>>,
+    transfers: Vec<BatchTransferEntry>,
+) -> Result<()> {
+    msg!("🔒 Executing {} confidential transfers", transfers.len());
+    
+    require!(
+        !transfers.is_empty() && transfers.len() <= MAX_BATCH_TRANSFERS,
+        PrivacyError::InvalidBatchSize
+    );
+    
+    let mint_info = ctx.accounts.mint.to_account_info();
+    // Remaining accounts hold the batch, there's no room for hook accounts
+    require!(
+        confidential_transfer::transfer_hook_program_id(&mint_info)?.is_none(),
+        PrivacyError::TransferHookNotSupported
+    );
+    let accounts_per_transfer = if confidential_transfer::transfer_fee_config(&mint_info)?.is_some() {
+        BATCH_ACCOUNTS_PER_TRANSFER_WITH_FEE
+    } else {
+        BATCH_ACCOUNTS_PER_TRANSFER
+    };
+    require!(
+        ctx.remaining_accounts.len() == transfers.len() * accounts_per_transfer,
+        PrivacyError::InvalidBatchAccounts
+    );
+    
+    let token_program = ctx.accounts.token_program.to_account_info();
+    let sender = ctx.accounts.sender.to_account_info();
+    let sender_account = ctx.accounts.sender_account.to_account_info();
+    CreditPolicy::check_public_credits_disabled(&ctx.accounts.credit_policy, &sender_account)?;
+    
+    for (index, (entry, accounts)) in transfers
+        .iter()
+        .zip(ctx.remaining_accounts.chunks(accounts_per_transfer))
+        .enumerate()
+    {
+        let recipient_account = &accounts[0];
+        let source_decrypt_handles: SourceDecryptHandles =
+            bytemuck::pod_read_unaligned(&entry.source_decrypt_handles);
+        
+        check_batch_recipient(recipient_account, &token_program, &mint_info)
+            .and_then(|()| {
+                execute_transfer(
+                    TransferLeg {
+                        token_program: &token_program,
+                        sender: &sender,
+                        sender_account: &sender_account,
+                        recipient_account,
+                        mint: &mint_info,
+                        equality_proof: &accounts[1],
+                        ciphertext_validity_proof: &accounts[2],
+                        range_proof: &accounts[3],
+                        fee_sigma_proof: accounts.get(4),
+                        fee_ciphertext_validity_proof: accounts.get(5),
+                        hook_accounts: &[],
+                        signer_seeds: &[],
+                    },
+                    entry.new_source_decryptable_available_balance,
+                    &source_decrypt_handles,
+                )
+            })
+            .map_err(|e| e.with_account_name(format!("transfers[{index}]")))?;
+    }
+    
+    emit!(ConfidentialBatchTransferred {
+        mint: ctx.accounts.mint.key(),
+        sender_account: ctx.accounts.sender_account.key(),
+        transfer_count: transfers.len() as u8,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("✅ Batch transfer complete - amounts remain encrypted");
+    Ok(())
+}
+
+/// Compute units to request for a batch of `transfer_count` transfers,
+/// `with_fee` on mints with a transfer fee
+pub fn batch_transfer_compute_units(transfer_count: usize, with_fee: bool) -> u32 {
+    let units_per_transfer = if with_fee {
+        BATCH_TRANSFER_COMPUTE_UNITS_PER_TRANSFER_WITH_FEE
+    } else {
+        BATCH_TRANSFER_COMPUTE_UNITS_PER_TRANSFER
+    };
+    let per_transfer = units_per_transfer.saturating_mul(transfer_count as u32);
+    BATCH_TRANSFER_BASE_COMPUTE_UNITS.saturating_add(per_transfer)
+}
+
+/// Recipients of a batch come in unchecked, so hold them to the same rules
+/// the TransferConfidential constraints enforce
+fn check_batch_recipient(
+    recipient_account: &AccountInfo,
+    token_program: &AccountInfo,
+    mint: &AccountInfo,
+) -> Result<()> {
+    require!(recipient_account.is_writable, PrivacyError::InvalidRecipient);
+    require_keys_eq!(
+        *recipient_account.owner,
+        token_program.key(),
+        PrivacyError::InvalidRecipient
+    );
+    let data = recipient_account.try_borrow_data()?;
+    let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)
+        .map_err(|_| error!(PrivacyError::InvalidRecipient))?;
+    require_keys_eq!(account.base.mint, mint.key(), PrivacyError::InvalidRecipient);
+    Ok(())
+}
+
+/// Accounts of a single confidential transfer. `signer_seeds` are set when
+/// the sending account belongs to a program PDA.
+pub(crate) struct TransferLeg<'a, 'info> {
+    pub token_program: &'a AccountInfo<'info>,
+    pub sender: &'a AccountInfo<'info>,
+    pub sender_account: &'a AccountInfo<'info>,
+    pub recipient_account: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    pub equality_proof: &'a AccountInfo<'info>,
+    pub ciphertext_validity_proof: &'a AccountInfo<'info>,
+    pub fee_sigma_proof: Option<&'a AccountInfo<'info>>,
+    pub fee_ciphertext_validity_proof: Option<&'a AccountInfo<'info>>,
+    pub range_proof: &'a AccountInfo<'info>,
+    /// Transfer hook program, its ExtraAccountMetaList and extra accounts,
+    /// passed through to Token-2022 on mints with a transfer hook
+    pub hook_accounts: &'a [AccountInfo<'info>],
+    pub signer_seeds: &'a [&'a [&'a [u8]]],
+}
+
+/// Encrypted amount of an executed transfer, as Token-2022 splits it: the
+/// low 16 and high 32 bits, each under the source, destination and auditor keys
+pub(crate) struct TransferredAmount {
+    pub lo: TransferAmountCiphertext,
+    pub hi: TransferAmountCiphertext,
+}
+
+impl TransferredAmount {
+    /// The whole amount under the sender's key
+    pub fn source_ciphertext(&self) -> Result<ElGamalCiphertext> {
+        self.combined(0)
+    }
+    
+    /// The whole amount under the recipient's key
+    pub fn destination_ciphertext(&self) -> Result<ElGamalCiphertext> {
+        self.combined(1)
+    }
+    
+    /// Recombine the low and high parts under the key whose decrypt handle
+    /// is at `handle`, the same way Token-2022 credits and debits balances
+    fn combined(&self, handle: usize) -> Result<ElGamalCiphertext> {
+        let part = |ciphertext: &TransferAmountCiphertext| {
+            let bytes = bytemuck::bytes_of(ciphertext);
+            let offset = 32 * (handle + 1);
+            let mut part = [0u8; 64];
+            part[..32].copy_from_slice(&bytes[..32]);
+            part[32..].copy_from_slice(&bytes[offset..offset + 32]);
+            ElGamalCiphertext(part)
+        };
+        ops::add_with_lo_hi(&ElGamalCiphertext::zeroed(), &part(&self.lo), &part(&self.hi))
+            .ok_or_else(|| error!(PrivacyError::InvalidProof))
+    }
+}
+
+/// Check the proofs of one transfer against the sender, recipient and mint
+/// and run it through Token-2022
+pub(crate) fn execute_transfer(
+    leg: TransferLeg,
+    new_source_decryptable_available_balance: [u8; 36],
+    source_decrypt_handles: &SourceDecryptHandles,
+) -> Result<TransferredAmount> {
+    // Token-2022 repeats the key checks below, but we want a clear
+    // InvalidProof instead of a generic token program failure
+    let sender = confidential_transfer::account_extension(leg.sender_account)?;
+    let recipient = confidential_transfer::account_extension(leg.recipient_account)?;
+    let mint = confidential_transfer::mint_extension(leg.mint)?;
+    let auditor = Option::<ElGamalPubkey>::from(mint.auditor_elgamal_pubkey);
+    
+    // The proofs are verified ahead of time into context state accounts by
+    // the ZK ElGamal proof program. Together they ensure:
+    // 1. Sender has sufficient balance (proven without revealing balance)
+    // 2. Amount is valid and positive (proven without revealing amount)
+    // 3. No overflow/underflow (cryptographically verified)
+    // 4. On fee mints, the withheld fee matches the mint's fee parameters
+    if let Some(transfer_fee_config) = confidential_transfer::transfer_fee_config(leg.mint)? {
+        let fee_config = confidential_transfer::fee_extension(leg.mint)?;
+        let (Some(fee_sigma_proof), Some(fee_ciphertext_validity_proof)) =
+            (leg.fee_sigma_proof, leg.fee_ciphertext_validity_proof)
+        else {
+            return err!(PrivacyError::MissingFeeProofs);
+        };
+        
+        let fee_parameters = transfer_fee_config.get_epoch_fee(Clock::get()?.epoch);
+        let proof = verify_transfer_with_fee_proofs(
+            &leg,
+            fee_sigma_proof,
+            fee_ciphertext_validity_proof,
+            source_decrypt_handles,
+            fee_parameters,
+        )?;
+        let pubkeys = &proof.transfer_with_fee_pubkeys;
+        require!(
+            pubkeys.source == sender.elgamal_pubkey
+                && pubkeys.destination == recipient.elgamal_pubkey
+                && pubkeys.withdraw_withheld_authority
+                    == fee_config.withdraw_withheld_authority_elgamal_pubkey,
+            PrivacyError::InvalidProof
+        );
+        if let Some(auditor) = auditor {
+            require!(pubkeys.auditor == auditor, PrivacyError::InvalidProof);
+        }
+        
+        confidential_transfer::transfer_with_fee_and_split_proofs(
+            CpiContext::new_with_signer(
+                leg.token_program.clone(),
+                confidential_transfer::TransferWithFeeAndSplitProofs {
+                    token_program_id: leg.token_program.clone(),
+                    source: leg.sender_account.clone(),
+                    mint: leg.mint.clone(),
+                    destination: leg.recipient_account.clone(),
+                    equality_proof: leg.equality_proof.clone(),
+                    transfer_amount_ciphertext_validity_proof: leg.ciphertext_validity_proof.clone(),
+                    fee_sigma_proof: fee_sigma_proof.clone(),
+                    fee_ciphertext_validity_proof: fee_ciphertext_validity_proof.clone(),
+                    range_proof: leg.range_proof.clone(),
+                    authority: leg.sender.clone(),
+                },
+                leg.signer_seeds,
+            )
+            .with_remaining_accounts(leg.hook_accounts.to_vec()),
+            new_source_decryptable_available_balance,
+            source_decrypt_handles,
+        )?;
+        
+        Ok(TransferredAmount {
+            lo: proof.ciphertext_lo,
+            hi: proof.ciphertext_hi,
+        })
+    } else {
+        let proof = verify_transfer_proofs(&leg, source_decrypt_handles)?;
+        require!(
+            proof.transfer_pubkeys.source == sender.elgamal_pubkey
+                && proof.transfer_pubkeys.destination == recipient.elgamal_pubkey,
+            PrivacyError::InvalidProof
+        );
+        if let Some(auditor) = auditor {
+            require!(
+                proof.transfer_pubkeys.auditor == auditor,
+                PrivacyError::InvalidProof
+            );
+        }
+        
+        // Token-2022 confidential transfer extension handles:
+        // - Homomorphic subtraction from sender's encrypted balance
+        // - Homomorphic addition to recipient's pending balance
+        confidential_transfer::transfer_with_split_proofs(
+            CpiContext::new_with_signer(
+                leg.token_program.clone(),
+                confidential_transfer::TransferWithSplitProofs {
+                    token_program_id: leg.token_program.clone(),
+                    source: leg.sender_account.clone(),
+                    mint: leg.mint.clone(),
+                    destination: leg.recipient_account.clone(),
+                    equality_proof: leg.equality_proof.clone(),
+                    ciphertext_validity_proof: leg.ciphertext_validity_proof.clone(),
+                    range_proof: leg.range_proof.clone(),
+                    authority: leg.sender.clone(),
+                },
+                leg.signer_seeds,
+            )
+            .with_remaining_accounts(leg.hook_accounts.to_vec()),
+            new_source_decryptable_available_balance,
+            source_decrypt_handles,
+        )?;
+        
+        Ok(TransferredAmount {
+            lo: proof.ciphertext_lo,
+            hi: proof.ciphertext_hi,
+        })
+    }
+}
+
+/// Load the three split proof contexts of a transfer and check they describe
+/// the same transfer
+fn verify_transfer_proofs(
+    leg: &TransferLeg,
+    source_decrypt_handles: &SourceDecryptHandles,
+) -> Result<TransferProofContextInfo> {
+    let equality = confidential_transfer::proof_context::<
+        CiphertextCommitmentEqualityProofData,
+        CiphertextCommitmentEqualityProofContext,
+    >(
+        ProofInstruction::VerifyCiphertextCommitmentEquality,
+        leg.equality_proof,
+        0,
+    )?;
+    let ciphertext_validity = confidential_transfer::proof_context::<
+        BatchedGroupedCiphertext2HandlesValidityProofData,
+        BatchedGroupedCiphertext2HandlesValidityProofContext,
+    >(
+        ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity,
+        leg.ciphertext_validity_proof,
+        0,
+    )?;
+    let range = confidential_transfer::proof_context::<
+        BatchedRangeProofU128Data,
+        BatchedRangeProofContext,
+    >(
+        ProofInstruction::VerifyBatchedRangeProofU128,
+        leg.range_proof,
+        0,
+    )?;
+    
+    TransferProofContextInfo::verify_and_extract(
+        &equality,
+        &ciphertext_validity,
+        &range,
+        source_decrypt_handles,
+    )
+    .map_err(|_| error!(PrivacyError::InvalidProof))
+}
+
+/// Load the five split proof contexts of a transfer with fee and check they
+/// describe the same transfer under the mint's current fee parameters
+fn verify_transfer_with_fee_proofs(
+    leg: &TransferLeg,
+    fee_sigma_proof: &AccountInfo,
+    fee_ciphertext_validity_proof: &AccountInfo,
+    source_decrypt_handles: &SourceDecryptHandles,
+    fee_parameters: &TransferFee,
+) -> Result<TransferWithFeeProofContextInfo> {
+    let equality = confidential_transfer::proof_context::<
+        CiphertextCommitmentEqualityProofData,
+        CiphertextCommitmentEqualityProofContext,
+    >(
+        ProofInstruction::VerifyCiphertextCommitmentEquality,
+        leg.equality_proof,
+        0,
+    )?;
+    let transfer_amount_ciphertext_validity = confidential_transfer::proof_context::<
+        BatchedGroupedCiphertext2HandlesValidityProofData,
+        BatchedGroupedCiphertext2HandlesValidityProofContext,
+    >(
+        ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity,
+        leg.ciphertext_validity_proof,
+        0,
+    )?;
+    let fee_sigma = confidential_transfer::proof_context::<FeeSigmaProofData, FeeSigmaProofContext>(
+        ProofInstruction::VerifyFeeSigma,
+        fee_sigma_proof,
+        0,
+    )?;
+    let fee_ciphertext_validity = confidential_transfer::proof_context::<
+        BatchedGroupedCiphertext2HandlesValidityProofData,
+        BatchedGroupedCiphertext2HandlesValidityProofContext,
+    >(
+        ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity,
+        fee_ciphertext_validity_proof,
+        0,
+    )?;
+    let range = confidential_transfer::proof_context::<
+        BatchedRangeProofU256Data,
+        BatchedRangeProofContext,
+    >(
+        ProofInstruction::VerifyBatchedRangeProofU256,
+        leg.range_proof,
+        0,
+    )?;
+    
+    TransferWithFeeProofContextInfo::verify_and_extract(
+        &equality,
+        &transfer_amount_ciphertext_validity,
+        &fee_sigma,
+        &fee_ciphertext_validity,
+        &range,
+        source_decrypt_handles,
+        fee_parameters,
+    )
+    .map_err(|_| error!(PrivacyError::InvalidProof))
+}
+
+/// Withdraw from confidential balance back to public balance
+/// User decrypts balance client-side and proves the withdrawal
+pub fn withdraw_confidential(
+    ctx: Context<WithdrawConfidential>,
+    amount: u64,
+    new_decryptable_available_balance: [u8; 36],
+    proof_instruction_offset: i8,
+) -> Result<()> {
+    require!(amount > 0, PrivacyError::InvalidAmount);
+    
+    msg!("💸 Withdrawing {} tokens from confidential account", amount);
+    msg!("User decrypts balance client-side before withdrawal");
+    
+    // The withdraw proof bundles a CiphertextCommitmentEquality proof (the
+    // remaining balance ciphertext matches a commitment) with a 64-bit range
+    // proof on that commitment, so the withdrawal can't overdraw the account
+    let proof = confidential_transfer::proof_context::<WithdrawData, WithdrawProofContext>(
+        ProofInstruction::VerifyWithdraw,
+        &ctx.accounts.proof_account.to_account_info(),
+        proof_instruction_offset,
+    )?;
+    let extension = confidential_transfer::account_extension(
+        &ctx.accounts.confidential_account.to_account_info(),
+    )?;
+    require!(
+        proof.pubkey == extension.elgamal_pubkey,
+        PrivacyError::InvalidProof
+    );
+    
+    confidential_transfer::withdraw(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            confidential_transfer::Withdraw {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                token_account: ctx.accounts.confidential_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                proof_account: ctx.accounts.proof_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+        new_decryptable_available_balance,
+        proof_instruction_offset,
+    )?;
+    
+    msg!("✅ Withdrawal successful");
+    Ok(())
+}
+
+/// Accept incoming confidential transfers
+pub fn enable_confidential_credits(ctx: Context<ConfigureCredits>) -> Result<()> {
+    confidential_transfer::enable_confidential_credits(credits_cpi(
+        &ctx.accounts.token_program,
+        &ctx.accounts.confidential_account,
+        &ctx.accounts.owner,
+    ))?;
+    emit_credit_settings(&ctx.accounts.confidential_account, &ctx.accounts.owner)
+}
+
+/// Reject incoming confidential transfers
+pub fn disable_confidential_credits(ctx: Context<ConfigureCredits>) -> Result<()> {
+    confidential_transfer::disable_confidential_credits(credits_cpi(
+        &ctx.accounts.token_program,
+        &ctx.accounts.confidential_account,
+        &ctx.accounts.owner,
+    ))?;
+    emit_credit_settings(&ctx.accounts.confidential_account, &ctx.accounts.owner)
+}
+
+/// Accept incoming public (non-confidential) transfers
+/// Not allowed once the account is locked to confidential-only
+pub fn enable_non_confidential_credits(ctx: Context<ConfigureCredits>) -> Result<()> {
+    require!(
+        !CreditPolicy::is_confidential_only(&ctx.accounts.credit_policy)?,
+        PrivacyError::ConfidentialOnlyAccount
+    );
+    
+    confidential_transfer::enable_non_confidential_credits(credits_cpi(
+        &ctx.accounts.token_program,
+        &ctx.accounts.confidential_account,
+        &ctx.accounts.owner,
+    ))?;
+    emit_credit_settings(&ctx.accounts.confidential_account, &ctx.accounts.owner)
+}
+
+/// Reject incoming public (non-confidential) transfers
+pub fn disable_non_confidential_credits(ctx: Context<ConfigureCredits>) -> Result<()> {
+    confidential_transfer::disable_non_confidential_credits(credits_cpi(
+        &ctx.accounts.token_program,
+        &ctx.accounts.confidential_account,
+        &ctx.accounts.owner,
+    ))?;
+    emit_credit_settings(&ctx.accounts.confidential_account, &ctx.accounts.owner)
+}
+
+/// Lock a wallet-owned account to confidential credits only (compliance
+/// authority only, the owner co-signs the Token-2022 change)
+///
+/// Program-owned escrows need no policy: their PDA disables public credits
+/// when the escrow is created and nothing can sign to turn them back on.
+/// Once locked, wrap and deposit_confidential refuse to credit the account
+/// public amounts. The owner can still turn public credits back on by
+/// calling Token-2022 directly, but then apply_pending_balance and the
+/// confidential transfers refuse the account until they're off again, and
+/// the compliance authority can freeze it.
+pub fn enforce_confidential_only(ctx: Context<EnforceConfidentialOnly>) -> Result<()> {
+    confidential_transfer::enable_confidential_credits(credits_cpi(
+        &ctx.accounts.token_program,
+        &ctx.accounts.confidential_account,
+        &ctx.accounts.owner,
+    ))?;
+    confidential_transfer::disable_non_confidential_credits(credits_cpi(
+        &ctx.accounts.token_program,
+        &ctx.accounts.confidential_account,
+        &ctx.accounts.owner,
+    ))?;
+    
+    let credit_policy = &mut ctx.accounts.credit_policy;
+    credit_policy.token_account = ctx.accounts.confidential_account.key();
+    credit_policy.confidential_only = true;
+    credit_policy.enforced_by = ctx.accounts.compliance_authority.key();
+    credit_policy.enforced_at = Clock::get()?.unix_timestamp;
+    credit_policy.bump = ctx.bumps.credit_policy;
+    
+    msg!("🔒 Account locked to confidential credits only");
+    emit_credit_settings(&ctx.accounts.confidential_account, &ctx.accounts.owner)
+}
+
+fn credits_cpi<'info>(
+    token_program: &Program<'info, Token2022>,
+    confidential_account: &InterfaceAccount<'info, TokenAccount>,
+    owner: &Signer<'info>,
+) -> CpiContext<'static, 'static, 'static, 'info, confidential_transfer::UpdateCredits<'info>> {
+    CpiContext::new(
+        token_program.to_account_info(),
+        confidential_transfer::UpdateCredits {
+            token_program_id: token_program.to_account_info(),
+            token_account: confidential_account.to_account_info(),
+            authority: owner.to_account_info(),
+        },
+    )
+}
+
+fn emit_credit_settings(
+    confidential_account: &InterfaceAccount<TokenAccount>,
+    owner: &Signer,
+) -> Result<()> {
+    let extension = confidential_transfer::account_extension(&confidential_account.to_account_info())?;
+    emit!(CreditSettingsChanged {
+        token_account: confidential_account.key(),
+        owner: owner.key(),
+        allow_confidential_credits: extension.allow_confidential_credits.into(),
+        allow_non_confidential_credits: extension.allow_non_confidential_credits.into(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Close a confidential account and return its rent to the owner
+/// Pending balance must be applied and public balance withdrawn first
+pub fn close_confidential_account(
+    ctx: Context<CloseConfidentialAccount>,
+    proof_instruction_offset: i8,
+) -> Result<()> {
+    require!(
+        ctx.accounts.confidential_account.amount == 0,
+        PrivacyError::PublicBalanceNotEmpty
+    );
+    
+    let extension = confidential_transfer::account_extension(
+        &ctx.accounts.confidential_account.to_account_info(),
+    )?;
+    require!(
+        extension.pending_balance_lo == EncryptedBalance::zeroed()
+            && extension.pending_balance_hi == EncryptedBalance::zeroed(),
+        PrivacyError::PendingBalanceNotEmpty
+    );
+    
+    // The zero-balance proof shows the available balance ciphertext
+    // decrypts to 0 without revealing anything else
+    let proof = confidential_transfer::proof_context::<ZeroBalanceProofData, ZeroBalanceProofContext>(
+        ProofInstruction::VerifyZeroBalance,
+        &ctx.accounts.proof_account.to_account_info(),
+        proof_instruction_offset,
+    )?;
+    require!(
+        proof.pubkey == extension.elgamal_pubkey
+            && proof.ciphertext == extension.available_balance,
+        PrivacyError::InvalidProof
+    );
+    
+    msg!("🧹 Emptying confidential account");
+    
+    confidential_transfer::empty_account(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            confidential_transfer::EmptyAccount {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                token_account: ctx.accounts.confidential_account.to_account_info(),
+                proof_account: ctx.accounts.proof_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        proof_instruction_offset,
+    )?;
+    
+    close_account(CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.confidential_account.to_account_info(),
+            destination: ctx.accounts.user.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        },
+    ))?;
+    
+    msg!("✅ Confidential account closed, rent returned");
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+/// Protocol fee taken on every confidential transfer of a mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct TransferFeeParams {
+    pub transfer_fee_basis_points: u16,
+    pub maximum_fee: u64,
+    /// Withheld fees are encrypted under this key, the treasury operator
+    /// holds the secret key
+    pub withdraw_withheld_authority_elgamal_pubkey: [u8; 32],
+}
+
+/// Memo attached to a confidential transfer, e.g. an invoice id
+///
+/// The ciphertext is encrypted under a one-time key that only the recipient
+/// (and the auditor, if a handle for it is given) can recover from their
+/// ElGamal secret key and the matching decrypt handle. The program does not
+/// look inside, it only bounds the size and publishes it in an event.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConfidentialMemo {
+    pub recipient_handle: [u8; 32],
+    pub auditor_handle: Option<[u8; 32]>,
+    pub ciphertext: Vec<u8>,
+}
+
+/// One transfer of a batch_transfer_confidential
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct BatchTransferEntry {
+    /// Sender's decryptable available balance after this transfer
+    pub new_source_decryptable_available_balance: [u8; 36],
+    pub source_decrypt_handles: [u8; 64],
+}
+
+/// Auditor configuration of a confidential mint, mirrored from the
+/// ConfidentialTransferMint extension together with its rotation history
+#[account]
+pub struct AuditConfig {
+    pub mint: Pubkey,                              // 32
+    pub authority: Pubkey,                         // 32
+    pub kyc_authority: Pubkey,                     // 32
+    pub compliance_authority: Pubkey,              // 32
+    pub fee_treasury: Pubkey,                      // 32 (default = not set yet)
+    pub auditor_elgamal_pubkey: [u8; 32],          // 32 (zeroed = no auditor)
+    pub auto_approve_new_accounts: bool,           // 1
+    pub rotation_count: u32,                       // 4
+    pub last_rotated_at: i64,                      // 8
+    pub history: Vec<AuditorRotation>,             // 4 + 10 * 104
+    pub confidential_authority_bump: u8,           // 1
+    pub freeze_authority_bump: u8,                 // 1
+    pub hook_authority_bump: u8,                   // 1
+    pub bump: u8,                                  // 1
+}
+
+impl AuditConfig {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 32 + 32 + 1 + 4 + 8
+        + 4 + MAX_AUDITOR_HISTORY * AuditorRotation::LEN + 1 + 1 + 1 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct AuditorRotation {
+    pub previous_auditor: [u8; 32],                // 32
+    pub new_auditor: [u8; 32],                     // 32
+    pub rotated_by: Pubkey,                        // 32
+    pub rotated_at: i64,                           // 8
+}
+
+impl AuditorRotation {
+    pub const LEN: usize = 32 + 32 + 32 + 8;
+}
+
+/// A confidential account waiting for KYC approval, one PDA per account
+#[account]
+pub struct ApprovalRequest {
+    pub mint: Pubkey,                              // 32
+    pub token_account: Pubkey,                     // 32
+    pub owner: Pubkey,                             // 32
+    pub requested_at: i64,                         // 8
+    pub bump: u8,                                  // 1
+}
+
+impl ApprovalRequest {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1;
+}
+
+/// Credit restrictions the program enforces on top of Token-2022's own flags
+#[account]
+pub struct CreditPolicy {
+    pub token_account: Pubkey,                     // 32
+    pub confidential_only: bool,                   // 1
+    pub enforced_by: Pubkey,                       // 32
+    pub enforced_at: i64,                          // 8
+    pub bump: u8,                                  // 1
+}
+
+impl CreditPolicy {
+    pub const LEN: usize = 8 + 32 + 1 + 32 + 8 + 1;
+    
+    /// The policy PDA only exists for locked accounts, so a missing account
+    /// means no restriction
+    pub fn is_confidential_only(credit_policy: &AccountInfo) -> Result<bool> {
+        if credit_policy.owner != &crate::ID || credit_policy.data_is_empty() {
+            return Ok(false);
+        }
+        let data = credit_policy.try_borrow_data()?;
+        let policy = CreditPolicy::try_deserialize(&mut &data[..])?;
+        Ok(policy.confidential_only)
+    }
+    
+    /// The owner of a locked account can still turn public credits back on
+    /// by calling Token-2022 directly. Until they turn them off again, the
+    /// program won't apply or spend the account's balance.
+    pub fn check_public_credits_disabled(
+        credit_policy: &AccountInfo,
+        token_account: &AccountInfo,
+    ) -> Result<()> {
+        if !Self::is_confidential_only(credit_policy)? {
+            return Ok(());
+        }
+        let extension = confidential_transfer::account_extension(token_account)?;
+        require!(
+            !bool::from(extension.allow_non_confidential_credits),
+            PrivacyError::PublicCreditsEnabled
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeMint<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// The confidential transfer enabled mint
+    /// Allocated and initialized by the instruction so the
+    /// ConfidentialTransferMint extension is set up before the base mint
+    #[account(mut)]
+    pub mint: Signer<'info>,
+    
+    /// CHECK: PDA mint authority - supply only changes through wrap/unwrap
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA set as the confidential transfer authority of the mint
+    #[account(
+        seeds = [CONFIDENTIAL_AUTHORITY_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub confidential_authority: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA freeze authority of the mint
+    #[account(
+        seeds = [FREEZE_AUTHORITY_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub freeze_authority: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA TransferHook authority of the mint
+    #[account(
+        seeds = [HOOK_AUTHORITY_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub hook_authority: UncheckedAccount<'info>,
+    
+    /// Auditor key and rotation history, administered by the payer
+    #[account(
+        init,
+        payer = payer,
+        space = AuditConfig::LEN,
+        seeds = [AUDIT_CONFIG_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub audit_config: Account<'info, AuditConfig>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfidentialMint<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [AUDIT_CONFIG_SEED, mint.key().as_ref()],
+        bump = audit_config.bump,
+        has_one = mint,
+        has_one = authority @ PrivacyError::UnauthorizedAuditor
+    )]
+    pub audit_config: Account<'info, AuditConfig>,
+    
+    /// CHECK: PDA confidential transfer authority, signs the update
+    #[account(
+        seeds = [CONFIDENTIAL_AUTHORITY_SEED, mint.key().as_ref()],
+        bump = audit_config.confidential_authority_bump
+    )]
+    pub confidential_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct ManageAuditConfig<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [AUDIT_CONFIG_SEED, audit_config.mint.as_ref()],
+        bump = audit_config.bump,
+        has_one = authority @ PrivacyError::UnauthorizedAuditor
+    )]
+    pub audit_config: Account<'info, AuditConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RequestAccountApproval<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// Configured confidential account waiting for approval
+    #[account(
+        token::authority = owner,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub confidential_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = ApprovalRequest::LEN,
+        seeds = [APPROVAL_REQUEST_SEED, mint.key().as_ref(), confidential_account.key().as_ref()],
+        bump
+    )]
+    pub approval_request: Account<'info, ApprovalRequest>,
     
-    Ok(())
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
 }
 
-/// Initialize user's confidential token account
-/// Enables them to receive and send private payments
-pub fn initialize_user_account(
-    ctx: Context<InitializeUserAccount>,
-    elgamal_pubkey: [u8; 32],
-) -> Result<()> {
-    msg!("🔐 Initializing confidential account for user");
-    msg!("ElGamal public key: {:?}", &elgamal_pubkey[0..8]);
+#[derive(Accounts)]
+pub struct ReviewAccountApproval<'info> {
+    pub kyc_authority: Signer<'info>,
     
-    // Account created with confidential transfer extension
-    // User generates ElGamal keypair client-side
-    // Only they can decrypt their balance
+    /// Confidential account under review
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub confidential_account: InterfaceAccount<'info, TokenAccount>,
     
-    Ok(())
-}
-
-/// Deposit tokens into confidential account
-/// Converts public balance to encrypted balance
-pub fn deposit_confidential(
-    ctx: Context<DepositConfidential>,
-    amount: u64,
-) -> Result<()> {
-    require!(amount > 0, PrivacyError::InvalidAmount);
+    pub mint: InterfaceAccount<'info, Mint>,
     
-    msg!("💰 Depositing {} tokens into confidential account", amount);
-    msg!("Balance will be encrypted on-chain");
+    #[account(
+        seeds = [AUDIT_CONFIG_SEED, mint.key().as_ref()],
+        bump = audit_config.bump,
+        has_one = mint,
+        has_one = kyc_authority @ PrivacyError::UnauthorizedKycAuthority
+    )]
+    pub audit_config: Account<'info, AuditConfig>,
     
-    // Transfer tokens from user's regular account to confidential account
-    // The balance will be encrypted using the user's ElGamal public key
-    anchor_spl::token_interface::transfer_checked(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            anchor_spl::token_interface::TransferChecked {
-                from: ctx.accounts.source_account.to_account_info(),
-                mint: ctx.accounts.mint.to_account_info(),
-                to: ctx.accounts.confidential_account.to_account_info(),
-                authority: ctx.accounts.authority.to_account_info(),
-            },
-        ),
-        amount,
-        ctx.accounts.mint.decimals,
-    )?;
+    /// Only accounts with a pending request can be reviewed
+    #[account(
+        mut,
+        close = owner,
+        seeds = [APPROVAL_REQUEST_SEED, mint.key().as_ref(), confidential_account.key().as_ref()],
+        bump = approval_request.bump,
+        has_one = owner
+    )]
+    pub approval_request: Account<'info, ApprovalRequest>,
     
-    msg!("✅ Deposit successful - balance now encrypted");
-    Ok(())
+    /// CHECK: Requester, refunded the request rent
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA confidential transfer authority, signs the approval
+    #[account(
+        seeds = [CONFIDENTIAL_AUTHORITY_SEED, mint.key().as_ref()],
+        bump = audit_config.confidential_authority_bump
+    )]
+    pub confidential_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
 }
 
-/// Send private payment to another user
-/// Amount is encrypted, only sender and recipient can see it
-pub fn transfer_confidential(
-    ctx: Context<TransferConfidential>,
-    encrypted_amount: [u8; 64], // ElGamal ciphertext
-    proof_data: Vec<u8>,         // Zero-knowledge range proof
-) -> Result<()> {
-    msg!("🔒 Executing confidential transfer");
-    msg!("Amount: ████████ (encrypted on-chain)");
-    msg!("Proof size: {} bytes", proof_data.len());
+#[derive(Accounts)]
+pub struct ComplianceAction<'info> {
+    pub compliance_authority: Signer<'info>,
     
-    // The encrypted_amount and proof_data ensure:
-    // 1. Sender has sufficient balance (proven without revealing balance)
-    // 2. Amount is valid and positive (proven without revealing amount)
-    // 3. No overflow/underflow (cryptographically verified)
+    /// Confidential account being frozen or thawed
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub confidential_account: InterfaceAccount<'info, TokenAccount>,
     
-    // Token-2022 confidential transfer extension handles:
-    // - Homomorphic subtraction from sender's encrypted balance
-    // - Homomorphic addition to recipient's encrypted balance
-    // - Zero-knowledge proof verification
+    pub mint: InterfaceAccount<'info, Mint>,
     
-    msg!("✅ Confidential transfer complete - amounts remain encrypted");
-    Ok(())
+    #[account(
+        seeds = [AUDIT_CONFIG_SEED, mint.key().as_ref()],
+        bump = audit_config.bump,
+        has_one = mint,
+        has_one = compliance_authority @ PrivacyError::UnauthorizedComplianceAuthority
+    )]
+    pub audit_config: Account<'info, AuditConfig>,
+    
+    /// CHECK: PDA freeze authority of the mint, signs the freeze or thaw
+    #[account(
+        seeds = [FREEZE_AUTHORITY_SEED, mint.key().as_ref()],
+        bump = audit_config.freeze_authority_bump
+    )]
+    pub freeze_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
 }
 
-/// Withdraw from confidential account to regular account
-/// Decrypts balance and transfers to user's wallet
-pub fn withdraw_confidential(
-    ctx: Context<WithdrawConfidential>,
-    amount: u64,
-) -> Result<()> {
-    require!(amount > 0, PrivacyError::InvalidAmount);
+#[derive(Accounts)]
+pub struct ManageTransferHook<'info> {
+    pub compliance_authority: Signer<'info>,
     
-    msg!("💸 Withdrawing {} tokens from confidential account", amount);
-    msg!("User decrypts balance client-side before withdrawal");
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
     
-    // Transfer from confidential account back to regular account
-    // User must prove they have sufficient balance via ZK proof
-    anchor_spl::token_interface::transfer_checked(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            anchor_spl::token_interface::TransferChecked {
-                from: ctx.accounts.confidential_account.to_account_info(),
-                mint: ctx.accounts.mint.to_account_info(),
-                to: ctx.accounts.destination_account.to_account_info(),
-                authority: ctx.accounts.authority.to_account_info(),
-            },
-        ),
-        amount,
-        ctx.accounts.mint.decimals,
-    )?;
+    #[account(
+        seeds = [AUDIT_CONFIG_SEED, mint.key().as_ref()],
+        bump = audit_config.bump,
+        has_one = mint,
+        has_one = compliance_authority @ PrivacyError::UnauthorizedComplianceAuthority
+    )]
+    pub audit_config: Account<'info, AuditConfig>,
     
-    msg!("✅ Withdrawal successful");
-    Ok(())
+    /// CHECK: PDA TransferHook authority of the mint, signs the update
+    #[account(
+        seeds = [HOOK_AUTHORITY_SEED, mint.key().as_ref()],
+        bump = audit_config.hook_authority_bump
+    )]
+    pub hook_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
 }
 
-// ============================================================================
-// Account Contexts
-// ============================================================================
-
 #[derive(Accounts)]
-pub struct InitializeMint<'info> {
+pub struct InitializeTransferHookAllowlist<'info> {
+    /// Pays for the allowlist accounts and becomes the allowlist admin
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub compliance_authority: Signer<'info>,
+    
+    #[account(mint::token_program = anchor_spl::token_2022::ID)]
+    pub mint: InterfaceAccount<'info, Mint>,
     
-    /// The confidential transfer enabled mint
-    /// Must be created with Token-2022 program
     #[account(
-        init,
-        payer = payer,
-        mint::decimals = 6,
-        mint::authority = mint_authority,
-        mint::token_program = token_program,
+        seeds = [AUDIT_CONFIG_SEED, mint.key().as_ref()],
+        bump = audit_config.bump,
+        has_one = mint,
+        has_one = compliance_authority @ PrivacyError::UnauthorizedComplianceAuthority
     )]
-    pub mint: InterfaceAccount<'info, Mint>,
+    pub audit_config: Account<'info, AuditConfig>,
     
-    /// CHECK: Mint authority - can be program PDA or dedicated authority
-    pub mint_authority: UncheckedAccount<'info>,
+    /// CHECK: PDA TransferHook authority of the mint, signs for the allowlist
+    #[account(
+        seeds = [HOOK_AUTHORITY_SEED, mint.key().as_ref()],
+        bump = audit_config.hook_authority_bump
+    )]
+    pub hook_authority: UncheckedAccount<'info>,
+    
+    /// CHECK: ExtraAccountMetaList of the mint, validated by the allowlist program
+    #[account(mut)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    
+    /// CHECK: AllowlistConfig of the mint, validated by the allowlist program
+    #[account(mut)]
+    pub allowlist_config: UncheckedAccount<'info>,
     
-    pub token_program: Interface<'info, TokenInterface>,
+    pub allowlist_program: Program<'info, AllowlistHook>,
     pub system_program: Program<'info, System>,
 }
 
@@ -169,7 +1849,12 @@ pub struct InitializeUserAccount<'info> {
     pub confidential_account: InterfaceAccount<'info, TokenAccount>,
     
     pub mint: InterfaceAccount<'info, Mint>,
-    pub token_program: Interface<'info, TokenInterface>,
+    
+    /// CHECK: Instructions sysvar (inline proof) or PubkeyValidity proof
+    /// context state account, validated in the handler
+    pub proof_account: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
     pub system_program: Program<'info, System>,
 }
 
@@ -178,24 +1863,49 @@ pub struct DepositConfidential<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
     
-    /// Source token account (regular, unencrypted)
+    /// Confidential account - public balance is moved into its own
+    /// encrypted pending balance
     #[account(
         mut,
         token::authority = authority,
         token::mint = mint,
+        token::token_program = token_program,
     )]
-    pub source_account: InterfaceAccount<'info, TokenAccount>,
+    pub confidential_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: CreditPolicy PDA of the account, only initialized for
+    /// confidential-only accounts. Read in the handler
+    #[account(
+        seeds = [CREDIT_POLICY_SEED, confidential_account.key().as_ref()],
+        bump
+    )]
+    pub credit_policy: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyPendingBalance<'info> {
+    pub authority: Signer<'info>,
     
-    /// Destination confidential account (encrypted balances)
+    /// Confidential account whose pending balance is applied
     #[account(
         mut,
         token::authority = authority,
-        token::mint = mint,
+        token::token_program = token_program,
     )]
     pub confidential_account: InterfaceAccount<'info, TokenAccount>,
     
-    pub mint: InterfaceAccount<'info, Mint>,
-    pub token_program: Interface<'info, TokenInterface>,
+    /// CHECK: CreditPolicy PDA of the account, only initialized for
+    /// confidential-only accounts. Read in the handler
+    #[account(
+        seeds = [CREDIT_POLICY_SEED, confidential_account.key().as_ref()],
+        bump
+    )]
+    pub credit_policy: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
 }
 
 #[derive(Accounts)]
@@ -208,6 +1918,7 @@ pub struct TransferConfidential<'info> {
         mut,
         token::authority = sender,
         token::mint = mint,
+        token::token_program = token_program,
     )]
     pub sender_account: InterfaceAccount<'info, TokenAccount>,
     
@@ -215,11 +1926,71 @@ pub struct TransferConfidential<'info> {
     #[account(
         mut,
         token::mint = mint,
+        token::token_program = token_program,
     )]
     pub recipient_account: InterfaceAccount<'info, TokenAccount>,
     
     pub mint: InterfaceAccount<'info, Mint>,
-    pub token_program: Interface<'info, TokenInterface>,
+    
+    /// CHECK: CiphertextCommitmentEquality proof context state account,
+    /// validated in the handler
+    pub equality_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: BatchedGroupedCiphertext2HandlesValidity proof context state
+    /// account for the transfer amount, validated in the handler
+    pub ciphertext_validity_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: FeeSigma proof context state account, validated in the
+    /// handler. Only for mints with transfer fees
+    pub fee_sigma_proof: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: BatchedGroupedCiphertext2HandlesValidity proof context state
+    /// account for the fee, validated in the handler. Only for mints with
+    /// transfer fees
+    pub fee_ciphertext_validity_proof: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: BatchedRangeProofU128 (BatchedRangeProofU256 with fees) proof
+    /// context state account, validated in the handler
+    pub range_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: CreditPolicy PDA of the account, only initialized for
+    /// confidential-only accounts. Read in the handler
+    #[account(
+        seeds = [CREDIT_POLICY_SEED, sender_account.key().as_ref()],
+        bump
+    )]
+    pub credit_policy: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct BatchTransferConfidential<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// Sender's confidential account
+    #[account(
+        mut,
+        token::authority = sender,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub sender_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: CreditPolicy PDA of the account, only initialized for
+    /// confidential-only accounts. Read in the handler
+    #[account(
+        seeds = [CREDIT_POLICY_SEED, sender_account.key().as_ref()],
+        bump
+    )]
+    pub credit_policy: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    // remaining_accounts: per transfer the recipient account and its proof
+    // context state accounts, see batch_transfer_confidential
 }
 
 #[derive(Accounts)]
@@ -227,24 +1998,224 @@ pub struct WithdrawConfidential<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
     
-    /// Source confidential account (encrypted)
+    /// Confidential account - encrypted balance is moved back into its own
+    /// public balance
     #[account(
         mut,
         token::authority = authority,
         token::mint = mint,
+        token::token_program = token_program,
     )]
     pub confidential_account: InterfaceAccount<'info, TokenAccount>,
     
-    /// Destination regular account
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Instructions sysvar (inline proof) or Withdraw proof context
+    /// state account, validated in the handler
+    pub proof_account: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct CloseConfidentialAccount<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    /// Confidential account being closed
     #[account(
         mut,
-        token::authority = authority,
+        token::authority = user,
+        token::token_program = token_program,
+    )]
+    pub confidential_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Instructions sysvar (inline proof) or ZeroBalance proof context
+    /// state account, validated in the handler
+    pub proof_account: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureCredits<'info> {
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::authority = owner,
+        token::token_program = token_program,
+    )]
+    pub confidential_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: CreditPolicy PDA of the account, only initialized for
+    /// confidential-only accounts. Read in the handler
+    #[account(
+        seeds = [CREDIT_POLICY_SEED, confidential_account.key().as_ref()],
+        bump
+    )]
+    pub credit_policy: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct EnforceConfidentialOnly<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub compliance_authority: Signer<'info>,
+    
+    pub owner: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::authority = owner,
         token::mint = mint,
+        token::token_program = token_program,
     )]
-    pub destination_account: InterfaceAccount<'info, TokenAccount>,
+    pub confidential_account: InterfaceAccount<'info, TokenAccount>,
     
     pub mint: InterfaceAccount<'info, Mint>,
-    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        seeds = [AUDIT_CONFIG_SEED, mint.key().as_ref()],
+        bump = audit_config.bump,
+        has_one = mint,
+        has_one = compliance_authority @ PrivacyError::UnauthorizedComplianceAuthority
+    )]
+    pub audit_config: Account<'info, AuditConfig>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = CreditPolicy::LEN,
+        seeds = [CREDIT_POLICY_SEED, confidential_account.key().as_ref()],
+        bump
+    )]
+    pub credit_policy: Account<'info, CreditPolicy>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[event]
+pub struct AuditorRotated {
+    pub mint: Pubkey,
+    pub previous_auditor: [u8; 32],
+    pub new_auditor: [u8; 32],
+    pub rotation_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuditAuthorityChanged {
+    pub mint: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KycAuthorityChanged {
+    pub mint: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ComplianceAuthorityChanged {
+    pub mint: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransferHookUpdated {
+    pub mint: Pubkey,
+    pub previous_program_id: Option<Pubkey>,
+    pub new_program_id: Option<Pubkey>,
+    pub compliance_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountApprovalRequested {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountApproved {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub kyc_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountRejected {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub kyc_authority: Pubkey,
+    pub reason_code: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountFrozen {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub compliance_authority: Pubkey,
+    pub reason_code: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountThawed {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub compliance_authority: Pubkey,
+    pub reason_code: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CreditSettingsChanged {
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub allow_confidential_credits: bool,
+    pub allow_non_confidential_credits: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfidentialBatchTransferred {
+    pub mint: Pubkey,
+    pub sender_account: Pubkey,
+    pub transfer_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfidentialTransferMemo {
+    pub mint: Pubkey,
+    pub sender_account: Pubkey,
+    pub recipient_account: Pubkey,
+    pub memo: ConfidentialMemo,
+    pub timestamp: i64,
 }
 
 #[error_code]
@@ -257,4 +2228,76 @@ pub enum PrivacyError {
     InvalidProof,
     #[msg("Unauthorized auditor")]
     UnauthorizedAuditor,
+    #[msg("Account is not configured for confidential transfers")]
+    AccountNotConfigured,
+    #[msg("Mint is not configured for confidential transfers")]
+    MintNotConfigured,
+    #[msg("Pending balance changed since the new balance was computed")]
+    StalePendingBalanceCounter,
+    #[msg("Math operation overflow")]
+    MathOverflow,
+    #[msg("Signer is not the KYC authority for this mint")]
+    UnauthorizedKycAuthority,
+    #[msg("Account does not need approval")]
+    ApprovalNotRequired,
+    #[msg("Underlying and confidential mints must have the same decimals")]
+    DecimalsMismatch,
+    #[msg("Reserve holds less than the confidential token supply")]
+    ReserveInvariantViolated,
+    #[msg("Pending balance must be applied before closing")]
+    PendingBalanceNotEmpty,
+    #[msg("Public balance must be zero before closing")]
+    PublicBalanceNotEmpty,
+    #[msg("Transfer fee basis points out of range")]
+    InvalidTransferFee,
+    #[msg("Mint is not configured for confidential transfer fees")]
+    TransferFeesNotConfigured,
+    #[msg("Fee sigma and fee ciphertext validity proofs are required on fee mints")]
+    MissingFeeProofs,
+    #[msg("Account is locked to confidential credits only")]
+    ConfidentialOnlyAccount,
+    #[msg("Memo ciphertext is empty or too long")]
+    InvalidMemo,
+    #[msg("Batch must hold between 1 and MAX_BATCH_TRANSFERS transfers")]
+    InvalidBatchSize,
+    #[msg("Remaining accounts do not match the batch transfers")]
+    InvalidBatchAccounts,
+    #[msg("Recipient is not a writable token account of this mint")]
+    InvalidRecipient,
+    #[msg("Stream must start before it ends, with the cliff in between, over at least one period")]
+    InvalidStreamSchedule,
+    #[msg("Streams do not support mints with transfer fees")]
+    FeeMintNotSupported,
+    #[msg("Nothing has vested yet")]
+    NothingVested,
+    #[msg("Stream is already canceled")]
+    StreamAlreadyCanceled,
+    #[msg("Stream is fully vested")]
+    StreamFullyVested,
+    #[msg("Claim exceeds the vested share of the stream")]
+    StreamAllowanceExceeded,
+    #[msg("Invoice has expired")]
+    InvoiceExpired,
+    #[msg("Invoice has not expired yet")]
+    InvoiceNotExpired,
+    #[msg("Transfer amount does not match the invoiced amount")]
+    InvoiceAmountMismatch,
+    #[msg("Invoice is addressed to another confidential account")]
+    InvoicePayerMismatch,
+    #[msg("Signer is not the compliance authority for this mint")]
+    UnauthorizedComplianceAuthority,
+    #[msg("Account is already frozen")]
+    AccountAlreadyFrozen,
+    #[msg("Account is not frozen")]
+    AccountNotFrozen,
+    #[msg("Not supported on mints with a transfer hook")]
+    TransferHookNotSupported,
+    #[msg("No token accounts passed to harvest fees from")]
+    NoAccountsToHarvest,
+    #[msg("Account is not the fee treasury recorded for this mint")]
+    InvalidFeeTreasury,
+    #[msg("Stream can only be closed once canceled or fully vested")]
+    StreamNotSettled,
+    #[msg("Confidential-only account has public credits turned back on")]
+    PublicCreditsEnabled,
 }
@@ -0,0 +1,827 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{close_account, CloseAccount, Mint, TokenAccount};
+use bytemuck::Zeroable;
+use spl_token_2022::extension::confidential_transfer::ciphertext_extraction::SourceDecryptHandles;
+use spl_token_2022::extension::confidential_transfer::instruction::{
+    BatchedRangeProofContext, BatchedRangeProofU64Data, CiphertextCommitmentEqualityProofContext,
+    CiphertextCommitmentEqualityProofData, ProofInstruction, PubkeyValidityData,
+    PubkeyValidityProofContext, ZeroBalanceProofContext, ZeroBalanceProofData,
+};
+use spl_token_2022::extension::ExtensionType;
+use spl_token_2022::solana_zk_token_sdk::curve25519::scalar::PodScalar;
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::{ops, pod};
+
+use crate::confidential_transfer;
+use crate::privacy_payments::{
+    execute_transfer, ConfidentialMemo, PrivacyError, TransferLeg,
+    MAXIMUM_PENDING_BALANCE_CREDIT_COUNTER, MAX_MEMO_CIPHERTEXT_LEN,
+};
+
+// Streams Module - confidential vesting payments
+// The sender locks an encrypted total in a program-owned escrow; the
+// recipient claims the vested share over time without the amounts ever
+// being revealed on-chain.
+//
+// The escrow gets its own ElGamal and AE keys, generated by the sender and
+// shared with the recipient in the StreamCreated event. Every claim proves,
+// against the encrypted total T and the encrypted amount W already claimed,
+// that N·W <= k·T where k of the N periods have vested: the program
+// computes k·T - N·W homomorphically and requires a proof that it encrypts
+// a value below 2^64. Cancelling proves the same for the sender's refund
+// against the N - k unvested periods. Once the escrow is empty the sender
+// closes the stream and gets the rent of both accounts back.
+
+pub const STREAM_SEED: &[u8] = b"stream";
+pub const STREAM_ESCROW_SEED: &[u8] = b"stream_escrow";
+
+/// Bit length of the range proof over the claim allowance. T < 2^48 and
+/// N < 2^16, so an honest allowance always fits
+pub const STREAM_ALLOWANCE_BIT_LENGTH: u8 = 64;
+
+/// Lock `total` tokens in a new stream's escrow for `recipient`
+///
+/// The funding transfer goes from the sender's confidential account to the
+/// escrow and is applied right away, so the escrow only ever holds the
+/// encrypted total. Fee mints are not supported: the withheld fee would make
+/// the escrow balance differ from the amount the schedule is proven against.
+#[allow(clippy::too_many_arguments)]
+pub fn create_stream(
+    ctx: Context<CreateStream>,
+    schedule: StreamSchedule,
+    escrow_elgamal_pubkey: [u8; 32],
+    escrow_decryptable_zero_balance: [u8; 36],
+    escrow_proof_instruction_offset: i8,
+    new_source_decryptable_available_balance: [u8; 36],
+    source_decrypt_handles: [u8; 64],
+    escrow_decryptable_available_balance: [u8; 36],
+    stream_secret: ConfidentialMemo,
+) -> Result<()> {
+    require!(
+        schedule.start_time < schedule.end_time
+            && schedule.start_time <= schedule.cliff_time
+            && schedule.cliff_time <= schedule.end_time
+            && schedule.periods > 0,
+        PrivacyError::InvalidStreamSchedule
+    );
+    require!(
+        confidential_transfer::transfer_fee_config(&ctx.accounts.mint.to_account_info())?.is_none(),
+        PrivacyError::FeeMintNotSupported
+    );
+    require!(
+        confidential_transfer::transfer_hook_program_id(&ctx.accounts.mint.to_account_info())?.is_none(),
+        PrivacyError::TransferHookNotSupported
+    );
+    require!(
+        !stream_secret.ciphertext.is_empty()
+            && stream_secret.ciphertext.len() <= MAX_MEMO_CIPHERTEXT_LEN,
+        PrivacyError::InvalidMemo
+    );
+    
+    msg!("🌊 Creating confidential stream {}", schedule.stream_id);
+    
+    let sender_key = ctx.accounts.sender.key();
+    let stream_id = schedule.stream_id.to_le_bytes();
+    let bump = [ctx.bumps.stream];
+    let seeds: &[&[u8]] = &[STREAM_SEED, sender_key.as_ref(), &stream_id, &bump];
+    let signer_seeds = &[seeds];
+    
+    let token_program = ctx.accounts.token_program.to_account_info();
+    let escrow = ctx.accounts.escrow.to_account_info();
+    let stream = ctx.accounts.stream.to_account_info();
+    
+    // Same checks as initialize_user_account, with the stream PDA as owner
+    let proof = confidential_transfer::proof_context::<PubkeyValidityData, PubkeyValidityProofContext>(
+        ProofInstruction::VerifyPubkeyValidity,
+        &ctx.accounts.escrow_proof_account.to_account_info(),
+        escrow_proof_instruction_offset,
+    )?;
+    require!(
+        proof.pubkey == pod::ElGamalPubkey(escrow_elgamal_pubkey),
+        PrivacyError::InvalidProof
+    );
+    
+    confidential_transfer::reallocate(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            confidential_transfer::Reallocate {
+                token_program_id: token_program.clone(),
+                account: escrow.clone(),
+                payer: ctx.accounts.sender.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                owner: stream.clone(),
+            },
+            signer_seeds,
+        ),
+        &[ExtensionType::ConfidentialTransferAccount],
+    )?;
+    
+    confidential_transfer::configure_account(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            confidential_transfer::ConfigureAccount {
+                token_program_id: token_program.clone(),
+                token_account: escrow.clone(),
+                mint: ctx.accounts.mint.to_account_info(),
+                proof_account: ctx.accounts.escrow_proof_account.to_account_info(),
+                authority: stream.clone(),
+            },
+            signer_seeds,
+        ),
+        escrow_decryptable_zero_balance,
+        MAXIMUM_PENDING_BALANCE_CREDIT_COUNTER,
+        escrow_proof_instruction_offset,
+    )?;
+    
+    let source_decrypt_handles: SourceDecryptHandles =
+        bytemuck::pod_read_unaligned(&source_decrypt_handles);
+    let funding = execute_transfer(
+        TransferLeg {
+            token_program: &token_program,
+            sender: &ctx.accounts.sender.to_account_info(),
+            sender_account: &ctx.accounts.sender_account.to_account_info(),
+            recipient_account: &escrow,
+            mint: &ctx.accounts.mint.to_account_info(),
+            equality_proof: &ctx.accounts.equality_proof.to_account_info(),
+            ciphertext_validity_proof: &ctx.accounts.ciphertext_validity_proof.to_account_info(),
+            fee_sigma_proof: None,
+            fee_ciphertext_validity_proof: None,
+            range_proof: &ctx.accounts.range_proof.to_account_info(),
+            hook_accounts: &[],
+            signer_seeds: &[],
+        },
+        new_source_decryptable_available_balance,
+        &source_decrypt_handles,
+    )?;
+    
+    // The funding transfer is the only credit the escrow ever gets
+    confidential_transfer::apply_pending_balance(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            confidential_transfer::ApplyPendingBalance {
+                token_program_id: token_program.clone(),
+                token_account: escrow.clone(),
+                authority: stream.clone(),
+            },
+            signer_seeds,
+        ),
+        1,
+        escrow_decryptable_available_balance,
+    )?;
+    
+    let update_credits = || {
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            confidential_transfer::UpdateCredits {
+                token_program_id: token_program.clone(),
+                token_account: escrow.clone(),
+                authority: stream.clone(),
+            },
+            signer_seeds,
+        )
+    };
+    // Only the stream PDA could turn credits back on, and no instruction
+    // does, so the escrow stays closed to public and confidential credits
+    confidential_transfer::disable_confidential_credits(update_credits())?;
+    confidential_transfer::disable_non_confidential_credits(update_credits())?;
+    
+    let total = funding.destination_ciphertext()?;
+    
+    let stream = &mut ctx.accounts.stream;
+    stream.sender = sender_key;
+    stream.recipient = ctx.accounts.recipient.key();
+    stream.mint = ctx.accounts.mint.key();
+    stream.escrow = ctx.accounts.escrow.key();
+    stream.stream_id = schedule.stream_id;
+    stream.start_time = schedule.start_time;
+    stream.cliff_time = schedule.cliff_time;
+    stream.end_time = schedule.end_time;
+    stream.periods = schedule.periods;
+    stream.total_amount = total.0;
+    stream.withdrawn_amount = pod::ElGamalCiphertext::zeroed().0;
+    stream.decryptable_withdrawn_amount = escrow_decryptable_zero_balance;
+    stream.canceled = false;
+    stream.canceled_at_period = 0;
+    stream.bump = ctx.bumps.stream;
+    stream.escrow_bump = ctx.bumps.escrow;
+    
+    emit!(StreamCreated {
+        stream: stream.key(),
+        sender: stream.sender,
+        recipient: stream.recipient,
+        mint: stream.mint,
+        escrow: stream.escrow,
+        start_time: stream.start_time,
+        cliff_time: stream.cliff_time,
+        end_time: stream.end_time,
+        periods: stream.periods,
+        stream_secret,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("✅ Stream created - total remains encrypted");
+    Ok(())
+}
+
+/// Claim tokens from the stream's escrow
+///
+/// The recipient picks the amount, up to what has vested minus what they
+/// already claimed. Besides the usual transfer proofs (with the escrow as
+/// source) they prove that the allowance ciphertext, see
+/// `stream_allowance_ciphertext`, stays in range after the claim.
+pub fn withdraw_from_stream(
+    ctx: Context<WithdrawFromStream>,
+    new_escrow_decryptable_available_balance: [u8; 36],
+    source_decrypt_handles: [u8; 64],
+    new_decryptable_withdrawn_amount: [u8; 36],
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let vested_periods = ctx.accounts.stream.vested_periods(now);
+    require!(vested_periods > 0, PrivacyError::NothingVested);
+    
+    msg!(
+        "🌊 Withdrawing from stream, {}/{} periods vested",
+        vested_periods,
+        ctx.accounts.stream.periods
+    );
+    
+    let escrow = ctx.accounts.escrow.to_account_info();
+    let escrow_pubkey = confidential_transfer::account_extension(&escrow)?.elgamal_pubkey;
+    
+    let stream = &ctx.accounts.stream;
+    let stream_id = stream.stream_id.to_le_bytes();
+    let bump = [stream.bump];
+    let seeds: &[&[u8]] = &[STREAM_SEED, stream.sender.as_ref(), &stream_id, &bump];
+    
+    let source_decrypt_handles: SourceDecryptHandles =
+        bytemuck::pod_read_unaligned(&source_decrypt_handles);
+    let claimed = execute_transfer(
+        TransferLeg {
+            token_program: &ctx.accounts.token_program.to_account_info(),
+            sender: &stream.to_account_info(),
+            sender_account: &escrow,
+            recipient_account: &ctx.accounts.recipient_account.to_account_info(),
+            mint: &ctx.accounts.mint.to_account_info(),
+            equality_proof: &ctx.accounts.equality_proof.to_account_info(),
+            ciphertext_validity_proof: &ctx.accounts.ciphertext_validity_proof.to_account_info(),
+            fee_sigma_proof: None,
+            fee_ciphertext_validity_proof: None,
+            range_proof: &ctx.accounts.range_proof.to_account_info(),
+            hook_accounts: &[],
+            signer_seeds: &[seeds],
+        },
+        new_escrow_decryptable_available_balance,
+        &source_decrypt_handles,
+    )?;
+    
+    let claimed = claimed.source_ciphertext()?;
+    let withdrawn = ops::add(&pod::ElGamalCiphertext(stream.withdrawn_amount), &claimed)
+        .ok_or(PrivacyError::InvalidProof)?;
+    let allowance = stream_allowance_ciphertext(
+        &pod::ElGamalCiphertext(stream.total_amount),
+        &withdrawn,
+        vested_periods,
+        stream.periods,
+    )
+    .ok_or(PrivacyError::InvalidProof)?;
+    verify_allowance_proofs(
+        &escrow_pubkey,
+        &allowance,
+        &ctx.accounts.allowance_equality_proof.to_account_info(),
+        &ctx.accounts.allowance_range_proof.to_account_info(),
+    )?;
+    
+    let stream = &mut ctx.accounts.stream;
+    stream.withdrawn_amount = withdrawn.0;
+    stream.decryptable_withdrawn_amount = new_decryptable_withdrawn_amount;
+    
+    emit!(StreamWithdrawn {
+        stream: stream.key(),
+        recipient: stream.recipient,
+        recipient_account: ctx.accounts.recipient_account.key(),
+        vested_periods,
+        timestamp: now,
+    });
+    
+    msg!("✅ Stream withdrawal complete - amount remains encrypted");
+    Ok(())
+}
+
+/// Stop the stream and take back the unvested part
+///
+/// The sender proves the refund is at most the share of the N - k periods
+/// that have not vested. The recipient keeps the k vested periods and can
+/// still claim them afterwards.
+pub fn cancel_stream(
+    ctx: Context<CancelStream>,
+    new_escrow_decryptable_available_balance: [u8; 36],
+    source_decrypt_handles: [u8; 64],
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let stream = &ctx.accounts.stream;
+    require!(!stream.canceled, PrivacyError::StreamAlreadyCanceled);
+    let vested_periods = stream.vested_periods(now);
+    require!(
+        vested_periods < stream.periods,
+        PrivacyError::StreamFullyVested
+    );
+    
+    msg!(
+        "🛑 Canceling stream at {}/{} periods vested",
+        vested_periods,
+        stream.periods
+    );
+    
+    let escrow = ctx.accounts.escrow.to_account_info();
+    let escrow_pubkey = confidential_transfer::account_extension(&escrow)?.elgamal_pubkey;
+    
+    let stream_id = stream.stream_id.to_le_bytes();
+    let bump = [stream.bump];
+    let seeds: &[&[u8]] = &[STREAM_SEED, stream.sender.as_ref(), &stream_id, &bump];
+    
+    let source_decrypt_handles: SourceDecryptHandles =
+        bytemuck::pod_read_unaligned(&source_decrypt_handles);
+    let refund = execute_transfer(
+        TransferLeg {
+            token_program: &ctx.accounts.token_program.to_account_info(),
+            sender: &stream.to_account_info(),
+            sender_account: &escrow,
+            recipient_account: &ctx.accounts.sender_account.to_account_info(),
+            mint: &ctx.accounts.mint.to_account_info(),
+            equality_proof: &ctx.accounts.equality_proof.to_account_info(),
+            ciphertext_validity_proof: &ctx.accounts.ciphertext_validity_proof.to_account_info(),
+            fee_sigma_proof: None,
+            fee_ciphertext_validity_proof: None,
+            range_proof: &ctx.accounts.range_proof.to_account_info(),
+            hook_accounts: &[],
+            signer_seeds: &[seeds],
+        },
+        new_escrow_decryptable_available_balance,
+        &source_decrypt_handles,
+    )?;
+    
+    let refund = refund.source_ciphertext()?;
+    let allowance = stream_allowance_ciphertext(
+        &pod::ElGamalCiphertext(stream.total_amount),
+        &refund,
+        stream.periods - vested_periods,
+        stream.periods,
+    )
+    .ok_or(PrivacyError::InvalidProof)?;
+    verify_allowance_proofs(
+        &escrow_pubkey,
+        &allowance,
+        &ctx.accounts.allowance_equality_proof.to_account_info(),
+        &ctx.accounts.allowance_range_proof.to_account_info(),
+    )?;
+    
+    let stream = &mut ctx.accounts.stream;
+    stream.canceled = true;
+    stream.canceled_at_period = vested_periods;
+    
+    emit!(StreamCanceled {
+        stream: stream.key(),
+        sender: stream.sender,
+        vested_periods,
+        timestamp: now,
+    });
+    
+    msg!("✅ Stream canceled - unvested amount returned");
+    Ok(())
+}
+
+/// Close a canceled or fully vested stream whose escrow is empty
+///
+/// The escrow is empty once the recipient has claimed everything they keep,
+/// which the sender shows with a zero-balance proof under the escrow key.
+/// The rent of the escrow and of the stream goes back to the sender.
+pub fn close_stream(ctx: Context<CloseStream>, proof_instruction_offset: i8) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let stream = &ctx.accounts.stream;
+    require!(
+        stream.canceled || stream.vested_periods(now) == stream.periods,
+        PrivacyError::StreamNotSettled
+    );
+    
+    let escrow = ctx.accounts.escrow.to_account_info();
+    let extension = confidential_transfer::account_extension(&escrow)?;
+    let proof = confidential_transfer::proof_context::<ZeroBalanceProofData, ZeroBalanceProofContext>(
+        ProofInstruction::VerifyZeroBalance,
+        &ctx.accounts.proof_account.to_account_info(),
+        proof_instruction_offset,
+    )?;
+    require!(
+        proof.pubkey == extension.elgamal_pubkey
+            && proof.ciphertext == extension.available_balance,
+        PrivacyError::InvalidProof
+    );
+    
+    msg!("🧹 Closing stream {}", stream.stream_id);
+    
+    let stream_id = stream.stream_id.to_le_bytes();
+    let bump = [stream.bump];
+    let seeds: &[&[u8]] = &[STREAM_SEED, stream.sender.as_ref(), &stream_id, &bump];
+    let signer_seeds = &[seeds];
+    
+    let token_program = ctx.accounts.token_program.to_account_info();
+    // Credits were disabled at creation and the funding credit applied, so
+    // the pending balance is already empty
+    confidential_transfer::empty_account(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            confidential_transfer::EmptyAccount {
+                token_program_id: token_program.clone(),
+                token_account: escrow.clone(),
+                proof_account: ctx.accounts.proof_account.to_account_info(),
+                authority: stream.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        proof_instruction_offset,
+    )?;
+    close_account(CpiContext::new_with_signer(
+        token_program,
+        CloseAccount {
+            account: escrow,
+            destination: ctx.accounts.sender.to_account_info(),
+            authority: stream.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+    
+    emit!(StreamClosed {
+        stream: stream.key(),
+        sender: stream.sender,
+        timestamp: now,
+    });
+    
+    msg!("✅ Stream closed, rent returned");
+    Ok(())
+}
+
+/// Ciphertext of `allowed_periods·total - periods·claimed` under the escrow
+/// key. It encrypts a value below 2^64 exactly when `claimed` is at most
+/// `allowed_periods / periods` of `total`; any over-claim wraps around the
+/// group order and can't be range proven.
+pub fn stream_allowance_ciphertext(
+    total: &pod::ElGamalCiphertext,
+    claimed: &pod::ElGamalCiphertext,
+    allowed_periods: u16,
+    periods: u16,
+) -> Option<pod::ElGamalCiphertext> {
+    let allowed = ops::multiply(&u64_scalar(allowed_periods.into()), total)?;
+    let claimed = ops::multiply(&u64_scalar(periods.into()), claimed)?;
+    ops::subtract(&allowed, &claimed)
+}
+
+fn u64_scalar(value: u64) -> PodScalar {
+    let mut bytes = [0u8; 32];
+    bytes[..8].copy_from_slice(&value.to_le_bytes());
+    PodScalar(bytes)
+}
+
+/// The equality proof ties the allowance ciphertext to a Pedersen
+/// commitment, the range proof shows that commitment is below 2^64
+fn verify_allowance_proofs(
+    escrow_pubkey: &pod::ElGamalPubkey,
+    allowance: &pod::ElGamalCiphertext,
+    equality_proof: &AccountInfo,
+    range_proof: &AccountInfo,
+) -> Result<()> {
+    let equality = confidential_transfer::proof_context::<
+        CiphertextCommitmentEqualityProofData,
+        CiphertextCommitmentEqualityProofContext,
+    >(
+        ProofInstruction::VerifyCiphertextCommitmentEquality,
+        equality_proof,
+        0,
+    )?;
+    require!(
+        equality.pubkey == *escrow_pubkey && equality.ciphertext == *allowance,
+        PrivacyError::StreamAllowanceExceeded
+    );
+    
+    let range = confidential_transfer::proof_context::<
+        BatchedRangeProofU64Data,
+        BatchedRangeProofContext,
+    >(
+        ProofInstruction::VerifyBatchedRangeProofU64,
+        range_proof,
+        0,
+    )?;
+    require!(
+        range.commitments[0] == equality.commitment
+            && range.bit_lengths[0] == STREAM_ALLOWANCE_BIT_LENGTH,
+        PrivacyError::StreamAllowanceExceeded
+    );
+    
+    Ok(())
+}
+
+// ============================================================================
+// Types
+// ============================================================================
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct StreamSchedule {
+    /// Picked by the sender, tells apart their streams
+    pub stream_id: u64,
+    pub start_time: i64,
+    /// Nothing can be claimed before this
+    pub cliff_time: i64,
+    pub end_time: i64,
+    /// Number of equal steps the total vests in between start and end
+    pub periods: u16,
+}
+
+#[account]
+pub struct Stream {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    /// Confidential token account owned by this stream
+    pub escrow: Pubkey,
+    pub stream_id: u64,
+    pub start_time: i64,
+    pub cliff_time: i64,
+    pub end_time: i64,
+    pub periods: u16,
+    /// ElGamal ciphertext of the streamed total, under the escrow key
+    pub total_amount: [u8; 64],
+    /// ElGamal ciphertext of everything claimed so far, under the escrow key
+    pub withdrawn_amount: [u8; 64],
+    /// Same as `withdrawn_amount` under the escrow AE key, kept up to date by
+    /// the recipient so claims don't need a discrete log
+    pub decryptable_withdrawn_amount: [u8; 36],
+    pub canceled: bool,
+    /// Periods the recipient keeps after a cancel
+    pub canceled_at_period: u16,
+    pub bump: u8,
+    pub escrow_bump: u8,
+}
+
+impl Stream {
+    pub const LEN: usize = 8 + 32 * 4 + 8 + 8 * 3 + 2 + 64 + 64 + 36 + 1 + 2 + 1 + 1;
+    
+    /// Periods vested at `now`: none before the cliff, all of them from the
+    /// end time, linear in between. A cancel freezes the count.
+    pub fn vested_periods(&self, now: i64) -> u16 {
+        let vested = if now < self.cliff_time {
+            0
+        } else if now >= self.end_time {
+            self.periods
+        } else {
+            let elapsed = (now - self.start_time) as u128;
+            let duration = (self.end_time - self.start_time) as u128;
+            (elapsed * u128::from(self.periods) / duration) as u16
+        };
+        
+        if self.canceled {
+            vested.min(self.canceled_at_period)
+        } else {
+            vested
+        }
+    }
+}
+
+// ============================================================================
+// Account Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(schedule: StreamSchedule)]
+pub struct CreateStream<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    /// Sender's confidential account, funds the escrow
+    #[account(
+        mut,
+        token::authority = sender,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub sender_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Only recorded, claims must be signed by this key
+    pub recipient: UncheckedAccount<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = sender,
+        space = Stream::LEN,
+        seeds = [STREAM_SEED, sender.key().as_ref(), &schedule.stream_id.to_le_bytes()],
+        bump
+    )]
+    pub stream: Account<'info, Stream>,
+    
+    /// Escrow holding the encrypted total, owned by the stream
+    #[account(
+        init,
+        payer = sender,
+        seeds = [STREAM_ESCROW_SEED, stream.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = stream,
+        token::token_program = token_program,
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Instructions sysvar (inline proof) or PubkeyValidity proof
+    /// context state account for the escrow key, validated in the handler
+    pub escrow_proof_account: UncheckedAccount<'info>,
+    
+    /// CHECK: CiphertextCommitmentEquality proof context state account of
+    /// the funding transfer, validated in the handler
+    pub equality_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: BatchedGroupedCiphertext2HandlesValidity proof context state
+    /// account of the funding transfer, validated in the handler
+    pub ciphertext_validity_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: BatchedRangeProofU128 proof context state account of the
+    /// funding transfer, validated in the handler
+    pub range_proof: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFromStream<'info> {
+    pub recipient: Signer<'info>,
+    
+    #[account(
+        mut,
+        has_one = recipient,
+        has_one = escrow,
+        has_one = mint,
+    )]
+    pub stream: Account<'info, Stream>,
+    
+    #[account(
+        mut,
+        seeds = [STREAM_ESCROW_SEED, stream.key().as_ref()],
+        bump = stream.escrow_bump,
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Recipient's confidential account
+    #[account(
+        mut,
+        token::authority = recipient,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub recipient_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: CiphertextCommitmentEquality proof context state account of
+    /// the transfer, validated in the handler
+    pub equality_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: BatchedGroupedCiphertext2HandlesValidity proof context state
+    /// account of the transfer, validated in the handler
+    pub ciphertext_validity_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: BatchedRangeProofU128 proof context state account of the
+    /// transfer, validated in the handler
+    pub range_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: CiphertextCommitmentEquality proof context state account for
+    /// the claim allowance, validated in the handler
+    pub allowance_equality_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: BatchedRangeProofU64 proof context state account for the
+    /// claim allowance, validated in the handler
+    pub allowance_range_proof: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct CancelStream<'info> {
+    pub sender: Signer<'info>,
+    
+    #[account(
+        mut,
+        has_one = sender,
+        has_one = escrow,
+        has_one = mint,
+    )]
+    pub stream: Account<'info, Stream>,
+    
+    #[account(
+        mut,
+        seeds = [STREAM_ESCROW_SEED, stream.key().as_ref()],
+        bump = stream.escrow_bump,
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Sender's confidential account, receives the refund
+    #[account(
+        mut,
+        token::authority = sender,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub sender_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: CiphertextCommitmentEquality proof context state account of
+    /// the refund, validated in the handler
+    pub equality_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: BatchedGroupedCiphertext2HandlesValidity proof context state
+    /// account of the refund, validated in the handler
+    pub ciphertext_validity_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: BatchedRangeProofU128 proof context state account of the
+    /// refund, validated in the handler
+    pub range_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: CiphertextCommitmentEquality proof context state account for
+    /// the refund allowance, validated in the handler
+    pub allowance_equality_proof: UncheckedAccount<'info>,
+    
+    /// CHECK: BatchedRangeProofU64 proof context state account for the
+    /// refund allowance, validated in the handler
+    pub allowance_range_proof: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct CloseStream<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = sender,
+        has_one = sender,
+        has_one = escrow,
+    )]
+    pub stream: Account<'info, Stream>,
+    
+    #[account(
+        mut,
+        seeds = [STREAM_ESCROW_SEED, stream.key().as_ref()],
+        bump = stream.escrow_bump,
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Instructions sysvar (inline proof) or ZeroBalance proof context
+    /// state account for the escrow, validated in the handler
+    pub proof_account: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[event]
+pub struct StreamCreated {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub escrow: Pubkey,
+    pub start_time: i64,
+    pub cliff_time: i64,
+    pub end_time: i64,
+    pub periods: u16,
+    /// Escrow ElGamal secret, AE key and total, encrypted to the recipient
+    pub stream_secret: ConfidentialMemo,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamWithdrawn {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub recipient_account: Pubkey,
+    pub vested_periods: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamCanceled {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub vested_periods: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamClosed {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,86 @@
+//! CPI helper for the swap interface strategies trade through.
+//!
+//! Any program can back a strategy, once an admin allowlists one of its
+//! pools as a swap venue, as long as it exposes an Anchor-style
+//! `swap(amount_in: u64, minimum_amount_out: u64)` instruction taking, in
+//! order: the source token account, the destination token account, the
+//! authority of the source, the pool, the pool's source and destination
+//! vaults, and the token program. Anything else the swap program needs is
+//! passed through untouched from `remaining_accounts`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+/// First 8 bytes of sha256("global:swap"), the Anchor discriminator of `swap`
+pub const SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+struct SwapArgs {
+    amount_in: u64,
+    minimum_amount_out: u64,
+}
+
+/// Accounts of a swap, in interface order
+pub struct SwapAccounts<'info> {
+    pub source: AccountInfo<'info>,
+    pub destination: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub pool: AccountInfo<'info>,
+    pub pool_source_vault: AccountInfo<'info>,
+    pub pool_destination_vault: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+}
+
+/// Swap `amount_in` from `source` into `destination` through `swap_program`,
+/// signing as `authority` with `signer_seeds`
+pub fn swap<'info>(
+    swap_program: AccountInfo<'info>,
+    accounts: SwapAccounts<'info>,
+    extra_accounts: &[AccountInfo<'info>],
+    amount_in: u64,
+    minimum_amount_out: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut data = SWAP_DISCRIMINATOR.to_vec();
+    SwapArgs {
+        amount_in,
+        minimum_amount_out,
+    }
+    .serialize(&mut data)?;
+    
+    let mut metas = vec![
+        AccountMeta::new(*accounts.source.key, false),
+        AccountMeta::new(*accounts.destination.key, false),
+        AccountMeta::new_readonly(*accounts.authority.key, true),
+        AccountMeta::new(*accounts.pool.key, false),
+        AccountMeta::new(*accounts.pool_source_vault.key, false),
+        AccountMeta::new(*accounts.pool_destination_vault.key, false),
+        AccountMeta::new_readonly(*accounts.token_program.key, false),
+    ];
+    let mut account_infos = vec![
+        accounts.source,
+        accounts.destination,
+        accounts.authority,
+        accounts.pool,
+        accounts.pool_source_vault,
+        accounts.pool_destination_vault,
+        accounts.token_program,
+    ];
+    for account in extra_accounts {
+        metas.push(if account.is_writable {
+            AccountMeta::new(*account.key, account.is_signer)
+        } else {
+            AccountMeta::new_readonly(*account.key, account.is_signer)
+        });
+        account_infos.push(account.clone());
+    }
+    account_infos.push(swap_program.clone());
+    
+    let ix = Instruction {
+        program_id: *swap_program.key,
+        accounts: metas,
+        data,
+    };
+    invoke_signed(&ix, &account_infos, signer_seeds).map_err(Into::into)
+}
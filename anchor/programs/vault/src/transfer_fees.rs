@@ -0,0 +1,456 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_2022_extensions::transfer_fee::{
+    transfer_fee_set, withdraw_withheld_tokens_from_mint, TransferFeeSetTransferFee,
+    WithdrawWithheldTokensFromMint,
+};
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use spl_token_2022::extension::confidential_transfer::instruction::{
+    CiphertextCiphertextEqualityProofContext, CiphertextCiphertextEqualityProofData,
+    ProofInstruction,
+};
+use spl_token_2022::extension::transfer_fee::MAX_FEE_BASIS_POINTS;
+
+use crate::confidential_transfer;
+use crate::privacy_payments::{
+    AuditConfig, PrivacyError, AUDIT_CONFIG_SEED, CONFIDENTIAL_AUTHORITY_SEED,
+};
+
+// Transfer Fees Module - protocol revenue from confidential transfers
+// Fees are withheld, encrypted, in recipient accounts. Anyone can sweep them
+// into the mint, and the audit authority pays them out to the fee treasury
+// recorded in the audit config. Public transfers of a fee mint (e.g. wrap
+// and unwrap) withhold plain fees the same way, paid out by the
+// withdraw_withheld_fees_* instructions.
+
+/// Record the confidential account of the mint that fees are paid out to
+/// (audit authority only)
+pub fn set_fee_treasury(ctx: Context<SetFeeTreasury>) -> Result<()> {
+    confidential_transfer::account_extension(&ctx.accounts.treasury_account.to_account_info())?;
+    
+    let audit_config = &mut ctx.accounts.audit_config;
+    let previous_treasury = audit_config.fee_treasury;
+    audit_config.fee_treasury = ctx.accounts.treasury_account.key();
+    
+    emit!(FeeTreasuryChanged {
+        mint: audit_config.mint,
+        previous_treasury,
+        new_treasury: audit_config.fee_treasury,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Change the mint's transfer fee (audit authority only)
+/// Token-2022 applies the new fee two epochs after it is set
+pub fn set_transfer_fee(
+    ctx: Context<SetTransferFee>,
+    transfer_fee_basis_points: u16,
+    maximum_fee: u64,
+) -> Result<()> {
+    require!(
+        transfer_fee_basis_points <= MAX_FEE_BASIS_POINTS,
+        PrivacyError::InvalidTransferFee
+    );
+    
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        CONFIDENTIAL_AUTHORITY_SEED,
+        mint_key.as_ref(),
+        &[ctx.accounts.audit_config.confidential_authority_bump],
+    ]];
+    
+    transfer_fee_set(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferFeeSetTransferFee {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.confidential_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        transfer_fee_basis_points,
+        maximum_fee,
+    )?;
+    
+    emit!(TransferFeeUpdated {
+        mint: mint_key,
+        transfer_fee_basis_points,
+        maximum_fee,
+        authority: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("💸 Transfer fee set to {} bps, max {}", transfer_fee_basis_points, maximum_fee);
+    Ok(())
+}
+
+/// Move withheld fees from the token accounts passed in `remaining_accounts`
+/// into the mint. Permissionless - accounts without withheld fees are skipped
+pub fn harvest_confidential_fees<'info>(
+    ctx: Context<'_, '_, '_, 'info, HarvestConfidentialFees<'info>>,
+) -> Result<()> {
+    // Clear error for mints created without fees
+    confidential_transfer::fee_extension(&ctx.accounts.mint.to_account_info())?;
+    require!(
+        !ctx.remaining_accounts.is_empty(),
+        PrivacyError::NoAccountsToHarvest
+    );
+    
+    msg!("🌾 Harvesting fees from {} accounts", ctx.remaining_accounts.len());
+    
+    confidential_transfer::harvest_withheld_tokens_to_mint(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            confidential_transfer::HarvestWithheldTokensToMint {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+            },
+        ),
+        ctx.remaining_accounts.to_vec(),
+    )?;
+    
+    emit!(ConfidentialFeesHarvested {
+        mint: ctx.accounts.mint.key(),
+        source_count: ctx.remaining_accounts.len() as u32,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Pay out the fees harvested into the mint to the fee treasury
+/// The withdraw withheld authority (operator) proves the withheld amount and
+/// the treasury credit encrypt the same value
+pub fn withdraw_confidential_fees(
+    ctx: Context<WithdrawConfidentialFees>,
+    new_decryptable_available_balance: [u8; 36],
+    proof_instruction_offset: i8,
+) -> Result<()> {
+    let fee_config =
+        confidential_transfer::fee_extension(&ctx.accounts.mint.to_account_info())?;
+    let treasury = confidential_transfer::account_extension(
+        &ctx.accounts.treasury_account.to_account_info(),
+    )?;
+    
+    let proof = confidential_transfer::proof_context::<
+        CiphertextCiphertextEqualityProofData,
+        CiphertextCiphertextEqualityProofContext,
+    >(
+        ProofInstruction::VerifyCiphertextCiphertextEquality,
+        &ctx.accounts.proof_account.to_account_info(),
+        proof_instruction_offset,
+    )?;
+    require!(
+        proof.source_pubkey == fee_config.withdraw_withheld_authority_elgamal_pubkey
+            && proof.source_ciphertext == fee_config.withheld_amount
+            && proof.destination_pubkey == treasury.elgamal_pubkey,
+        PrivacyError::InvalidProof
+    );
+    
+    msg!("🏦 Withdrawing harvested fees to treasury");
+    
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        CONFIDENTIAL_AUTHORITY_SEED,
+        mint_key.as_ref(),
+        &[ctx.accounts.audit_config.confidential_authority_bump],
+    ]];
+    
+    confidential_transfer::withdraw_withheld_tokens_from_mint(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            confidential_transfer::WithdrawWithheldTokensFromMint {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                destination: ctx.accounts.treasury_account.to_account_info(),
+                proof_account: ctx.accounts.proof_account.to_account_info(),
+                authority: ctx.accounts.confidential_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        new_decryptable_available_balance,
+        proof_instruction_offset,
+    )?;
+    
+    emit!(ConfidentialFeesWithdrawn {
+        mint: mint_key,
+        treasury_account: ctx.accounts.treasury_account.key(),
+        authority: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("✅ Fees withdrawn - amount remains encrypted");
+    Ok(())
+}
+
+/// Pay out the public fees withheld in the mint to the fee treasury (audit
+/// authority only). Anyone can sweep them into the mint first with
+/// Token-2022's HarvestWithheldTokensToMint.
+pub fn withdraw_withheld_fees_from_mint(ctx: Context<WithdrawWithheldFees>) -> Result<()> {
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        CONFIDENTIAL_AUTHORITY_SEED,
+        mint_key.as_ref(),
+        &[ctx.accounts.audit_config.confidential_authority_bump],
+    ]];
+    
+    withdraw_withheld_tokens_from_mint(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        WithdrawWithheldTokensFromMint {
+            token_program_id: ctx.accounts.token_program.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            destination: ctx.accounts.treasury_account.to_account_info(),
+            authority: ctx.accounts.confidential_authority.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+    
+    emit!(WithheldFeesWithdrawn {
+        mint: mint_key,
+        treasury_account: ctx.accounts.treasury_account.key(),
+        source_count: 0,
+        authority: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Pay out the public fees withheld in the token accounts passed in
+/// `remaining_accounts` straight to the fee treasury (audit authority only)
+pub fn withdraw_withheld_fees_from_accounts<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawWithheldFees<'info>>,
+) -> Result<()> {
+    require!(
+        !ctx.remaining_accounts.is_empty(),
+        PrivacyError::NoAccountsToHarvest
+    );
+    
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        CONFIDENTIAL_AUTHORITY_SEED,
+        mint_key.as_ref(),
+        &[ctx.accounts.audit_config.confidential_authority_bump],
+    ]];
+    
+    confidential_transfer::withdraw_withheld_tokens_from_accounts(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            confidential_transfer::WithdrawWithheldTokensFromAccounts {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                destination: ctx.accounts.treasury_account.to_account_info(),
+                authority: ctx.accounts.confidential_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        ctx.remaining_accounts.to_vec(),
+    )?;
+    
+    emit!(WithheldFeesWithdrawn {
+        mint: mint_key,
+        treasury_account: ctx.accounts.treasury_account.key(),
+        source_count: ctx.remaining_accounts.len() as u32,
+        authority: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+// ============================================================================
+// Account Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct SetFeeTreasury<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [AUDIT_CONFIG_SEED, mint.key().as_ref()],
+        bump = audit_config.bump,
+        has_one = mint,
+        has_one = authority @ PrivacyError::UnauthorizedAuditor
+    )]
+    pub audit_config: Account<'info, AuditConfig>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// Confidential account of the mint that receives protocol fees
+    #[account(
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub treasury_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct SetTransferFee<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [AUDIT_CONFIG_SEED, mint.key().as_ref()],
+        bump = audit_config.bump,
+        has_one = mint,
+        has_one = authority @ PrivacyError::UnauthorizedAuditor
+    )]
+    pub audit_config: Account<'info, AuditConfig>,
+    
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: PDA transfer fee config authority of the mint
+    #[account(
+        seeds = [CONFIDENTIAL_AUTHORITY_SEED, mint.key().as_ref()],
+        bump = audit_config.confidential_authority_bump
+    )]
+    pub confidential_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawWithheldFees<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [AUDIT_CONFIG_SEED, mint.key().as_ref()],
+        bump = audit_config.bump,
+        has_one = mint,
+        has_one = authority @ PrivacyError::UnauthorizedAuditor
+    )]
+    pub audit_config: Account<'info, AuditConfig>,
+    
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// Fee treasury recorded in the audit config
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        constraint = treasury_account.key() == audit_config.fee_treasury @ PrivacyError::InvalidFeeTreasury
+    )]
+    pub treasury_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: PDA withdraw withheld authority of the mint
+    #[account(
+        seeds = [CONFIDENTIAL_AUTHORITY_SEED, mint.key().as_ref()],
+        bump = audit_config.confidential_authority_bump
+    )]
+    pub confidential_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    // remaining_accounts (withdraw_withheld_fees_from_accounts only): token
+    // accounts of the mint holding withheld fees
+}
+
+#[derive(Accounts)]
+pub struct HarvestConfidentialFees<'info> {
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawConfidentialFees<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [AUDIT_CONFIG_SEED, mint.key().as_ref()],
+        bump = audit_config.bump,
+        has_one = mint,
+        has_one = authority @ PrivacyError::UnauthorizedAuditor
+    )]
+    pub audit_config: Account<'info, AuditConfig>,
+    
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// Fee treasury recorded in the audit config
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+        constraint = treasury_account.key() == audit_config.fee_treasury @ PrivacyError::InvalidFeeTreasury
+    )]
+    pub treasury_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Instructions sysvar (inline proof) or CiphertextCiphertextEquality
+    /// proof context state account, validated in the handler
+    pub proof_account: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA withdraw withheld authority of the mint
+    #[account(
+        seeds = [CONFIDENTIAL_AUTHORITY_SEED, mint.key().as_ref()],
+        bump = audit_config.confidential_authority_bump
+    )]
+    pub confidential_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[event]
+pub struct ConfidentialFeesHarvested {
+    pub mint: Pubkey,
+    pub source_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfidentialFeesWithdrawn {
+    pub mint: Pubkey,
+    pub treasury_account: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeTreasuryChanged {
+    pub mint: Pubkey,
+    pub previous_treasury: Pubkey,
+    pub new_treasury: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TransferFeeUpdated {
+    pub mint: Pubkey,
+    pub transfer_fee_basis_points: u16,
+    pub maximum_fee: u64,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithheldFeesWithdrawn {
+    pub mint: Pubkey,
+    pub treasury_account: Pubkey,
+    /// Token accounts withdrawn from, 0 when withdrawn from the mint
+    pub source_count: u32,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,409 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{
+    self, Burn, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::confidential_transfer;
+use crate::privacy_payments::{
+    AuditConfig, CreditPolicy, PrivacyError, AUDIT_CONFIG_SEED, CREDIT_POLICY_SEED,
+    MAXIMUM_DEPOSIT_TRANSFER_AMOUNT, MINT_AUTHORITY_SEED,
+};
+
+// Wrapper Module - 1:1 backed confidential tokens
+// Underlying tokens (e.g. USDC) sit in a program-owned reserve and the
+// confidential mint can only be minted against them
+
+pub const WRAPPER_SEED: &[u8] = b"wrapper";
+pub const RESERVE_SEED: &[u8] = b"reserve";
+
+/// Link a confidential mint to the underlying token it wraps
+/// Only the mint's audit authority can set this up, once
+pub fn initialize_wrapper(ctx: Context<InitializeWrapper>) -> Result<()> {
+    require!(
+        ctx.accounts.underlying_mint.decimals == ctx.accounts.mint.decimals,
+        PrivacyError::DecimalsMismatch
+    );
+    
+    let wrapper = &mut ctx.accounts.wrapper;
+    wrapper.mint = ctx.accounts.mint.key();
+    wrapper.underlying_mint = ctx.accounts.underlying_mint.key();
+    wrapper.reserve = ctx.accounts.reserve.key();
+    wrapper.mint_authority_bump = ctx.bumps.mint_authority;
+    wrapper.bump = ctx.bumps.wrapper;
+    
+    emit!(WrapperInitialized {
+        mint: wrapper.mint,
+        underlying_mint: wrapper.underlying_mint,
+        reserve: wrapper.reserve,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Deposit underlying tokens, receive the same amount of confidential tokens
+/// The new tokens land directly in the pending encrypted balance
+pub fn wrap(ctx: Context<Wrap>, amount: u64) -> Result<()> {
+    require!(amount > 0, PrivacyError::InvalidAmount);
+    require!(
+        amount <= MAXIMUM_DEPOSIT_TRANSFER_AMOUNT,
+        PrivacyError::InvalidAmount
+    );
+    // Minting is a public credit, which Token-2022 lets through whatever the
+    // account's credit settings
+    require!(
+        !CreditPolicy::is_confidential_only(&ctx.accounts.credit_policy)?,
+        PrivacyError::ConfidentialOnlyAccount
+    );
+    
+    msg!("🎁 Wrapping {} tokens", amount);
+    
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.underlying_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.user_underlying_account.to_account_info(),
+                mint: ctx.accounts.underlying_mint.to_account_info(),
+                to: ctx.accounts.reserve.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.underlying_mint.decimals,
+    )?;
+    
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        MINT_AUTHORITY_SEED,
+        mint_key.as_ref(),
+        &[ctx.accounts.wrapper.mint_authority_bump],
+    ]];
+    
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.confidential_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+    
+    // Encrypt right away so the wrapped amount never sits in public balance
+    confidential_transfer::deposit(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            confidential_transfer::Deposit {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                token_account: ctx.accounts.confidential_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+    )?;
+    
+    check_reserve_invariant(&mut ctx.accounts.reserve, &mut ctx.accounts.mint)?;
+    
+    emit!(Wrapped {
+        mint: mint_key,
+        user: ctx.accounts.user.key(),
+        amount,
+        reserve_balance: ctx.accounts.reserve.amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("✅ Wrapped - tokens are in your pending encrypted balance");
+    Ok(())
+}
+
+/// Burn confidential tokens, get the same amount of underlying back
+/// The tokens must be in public balance first (see withdraw_confidential)
+pub fn unwrap(ctx: Context<Unwrap>, amount: u64) -> Result<()> {
+    require!(amount > 0, PrivacyError::InvalidAmount);
+    require!(
+        ctx.accounts.confidential_account.amount >= amount,
+        PrivacyError::InsufficientBalance
+    );
+    
+    msg!("📤 Unwrapping {} tokens", amount);
+    
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.mint.to_account_info(),
+                from: ctx.accounts.confidential_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+    
+    let mint_key = ctx.accounts.mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        MINT_AUTHORITY_SEED,
+        mint_key.as_ref(),
+        &[ctx.accounts.wrapper.mint_authority_bump],
+    ]];
+    
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.underlying_token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.reserve.to_account_info(),
+                mint: ctx.accounts.underlying_mint.to_account_info(),
+                to: ctx.accounts.user_underlying_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        ctx.accounts.underlying_mint.decimals,
+    )?;
+    
+    check_reserve_invariant(&mut ctx.accounts.reserve, &mut ctx.accounts.mint)?;
+    
+    emit!(Unwrapped {
+        mint: mint_key,
+        user: ctx.accounts.user.key(),
+        amount,
+        reserve_balance: ctx.accounts.reserve.amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("✅ Unwrapped");
+    Ok(())
+}
+
+/// Every confidential token must stay backed by an underlying token
+fn check_reserve_invariant<'info>(
+    reserve: &mut InterfaceAccount<'info, TokenAccount>,
+    mint: &mut InterfaceAccount<'info, Mint>,
+) -> Result<()> {
+    reserve.reload()?;
+    mint.reload()?;
+    require!(
+        reserve.amount >= mint.supply,
+        PrivacyError::ReserveInvariantViolated
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[account]
+pub struct WrapperConfig {
+    pub mint: Pubkey,                  // 32
+    pub underlying_mint: Pubkey,       // 32
+    pub reserve: Pubkey,               // 32
+    pub mint_authority_bump: u8,       // 1
+    pub bump: u8,                      // 1
+}
+
+impl WrapperConfig {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 1 + 1;
+}
+
+// ============================================================================
+// Account Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeWrapper<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        seeds = [AUDIT_CONFIG_SEED, mint.key().as_ref()],
+        bump = audit_config.bump,
+        has_one = mint,
+        has_one = authority @ PrivacyError::UnauthorizedAuditor
+    )]
+    pub audit_config: Account<'info, AuditConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = WrapperConfig::LEN,
+        seeds = [WRAPPER_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub wrapper: Account<'info, WrapperConfig>,
+    
+    /// Confidential mint
+    #[account(mint::authority = mint_authority)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// Token being wrapped (e.g. USDC)
+    #[account(mint::token_program = underlying_token_program)]
+    pub underlying_mint: InterfaceAccount<'info, Mint>,
+    
+    /// Program-owned reserve holding the wrapped underlying tokens
+    #[account(
+        init,
+        payer = authority,
+        seeds = [RESERVE_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = underlying_mint,
+        token::authority = mint_authority,
+        token::token_program = underlying_token_program,
+    )]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: PDA mint authority of the confidential mint, owns the reserve
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    pub underlying_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Wrap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [WRAPPER_SEED, mint.key().as_ref()],
+        bump = wrapper.bump,
+        has_one = mint,
+        has_one = underlying_mint,
+        has_one = reserve
+    )]
+    pub wrapper: Account<'info, WrapperConfig>,
+    
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub underlying_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    
+    /// User's underlying token account (source)
+    #[account(
+        mut,
+        token::authority = user,
+        token::mint = underlying_mint,
+        token::token_program = underlying_token_program,
+    )]
+    pub user_underlying_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// User's confidential account (receives the wrapped tokens)
+    #[account(
+        mut,
+        token::authority = user,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub confidential_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: CreditPolicy PDA of the confidential account, only initialized
+    /// for confidential-only accounts. Read in the handler
+    #[account(
+        seeds = [CREDIT_POLICY_SEED, confidential_account.key().as_ref()],
+        bump
+    )]
+    pub credit_policy: UncheckedAccount<'info>,
+    
+    /// CHECK: PDA mint authority, signs the mint
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED, mint.key().as_ref()],
+        bump = wrapper.mint_authority_bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub underlying_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Unwrap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        seeds = [WRAPPER_SEED, mint.key().as_ref()],
+        bump = wrapper.bump,
+        has_one = mint,
+        has_one = underlying_mint,
+        has_one = reserve
+    )]
+    pub wrapper: Account<'info, WrapperConfig>,
+    
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub underlying_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub reserve: InterfaceAccount<'info, TokenAccount>,
+    
+    /// User's underlying token account (destination)
+    #[account(
+        mut,
+        token::mint = underlying_mint,
+        token::token_program = underlying_token_program,
+    )]
+    pub user_underlying_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// User's confidential account - burns from its public balance
+    #[account(
+        mut,
+        token::authority = user,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub confidential_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: PDA reserve owner, signs the release
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED, mint.key().as_ref()],
+        bump = wrapper.mint_authority_bump
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub underlying_token_program: Interface<'info, TokenInterface>,
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[event]
+pub struct WrapperInitialized {
+    pub mint: Pubkey,
+    pub underlying_mint: Pubkey,
+    pub reserve: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Wrapped {
+    pub mint: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub reserve_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Unwrapped {
+    pub mint: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub reserve_balance: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,201 @@
+mod common;
+
+use allowlist_hook::{HookError, ALLOWLIST_CONFIG_SEED, ALLOWLIST_ENTRY_SEED};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::{anchor_error, custom_error, hook_pda, ConfidentialAccount, Harness, WrappedMint};
+use solana_program_test::{tokio, BanksClientError};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signer::Signer, system_program};
+use spectre_client::instructions::allowlist_hook_accounts;
+
+const FUNDED: u64 = 1_000;
+const AMOUNT: u64 = 100;
+
+/// Sender and recipient on an allowlisted mint, both allowlisted, and an
+/// outsider who isn't. The sender is funded with `FUNDED`.
+struct Accounts {
+    wrapped: WrappedMint,
+    sender: ConfidentialAccount,
+    recipient: ConfidentialAccount,
+    outsider: ConfidentialAccount,
+}
+
+fn entry(mint: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    hook_pda(&[ALLOWLIST_ENTRY_SEED, mint.as_ref(), wallet.as_ref()])
+}
+
+async fn add_to_allowlist(harness: &mut Harness, mint: &Pubkey, wallet: &Pubkey) {
+    let ix = Instruction {
+        program_id: allowlist_hook::ID,
+        accounts: allowlist_hook::accounts::AddToAllowlist {
+            admin: harness.payer().pubkey(),
+            config: hook_pda(&[ALLOWLIST_CONFIG_SEED, mint.as_ref()]),
+            entry: entry(mint, wallet),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: allowlist_hook::instruction::AddToAllowlist { wallet: *wallet }.data(),
+    };
+    harness.process(&[ix], &[]).await.unwrap();
+}
+
+async fn remove_from_allowlist(harness: &mut Harness, mint: &Pubkey, wallet: &Pubkey) {
+    let ix = Instruction {
+        program_id: allowlist_hook::ID,
+        accounts: allowlist_hook::accounts::RemoveFromAllowlist {
+            admin: harness.payer().pubkey(),
+            config: hook_pda(&[ALLOWLIST_CONFIG_SEED, mint.as_ref()]),
+            entry: entry(mint, wallet),
+        }
+        .to_account_metas(None),
+        data: allowlist_hook::instruction::RemoveFromAllowlist { wallet: *wallet }.data(),
+    };
+    harness.process(&[ix], &[]).await.unwrap();
+}
+
+async fn setup(harness: &mut Harness) -> Accounts {
+    let wrapped = harness.create_allowlisted_mint().await;
+    let sender = harness.create_confidential_account(&wrapped.mint).await;
+    let recipient = harness.create_confidential_account(&wrapped.mint).await;
+    let outsider = harness.create_confidential_account(&wrapped.mint).await;
+    add_to_allowlist(harness, &wrapped.mint, &sender.owner.pubkey()).await;
+    add_to_allowlist(harness, &wrapped.mint, &recipient.owner.pubkey()).await;
+    // Wrapping mints and deposits, neither of which calls the hook
+    harness.fund(&wrapped, &sender, FUNDED).await;
+    Accounts {
+        wrapped,
+        sender,
+        recipient,
+        outsider,
+    }
+}
+
+async fn public_transfer(
+    harness: &mut Harness,
+    mint: &Pubkey,
+    sender: &ConfidentialAccount,
+    recipient: &ConfidentialAccount,
+) -> Result<(), BanksClientError> {
+    let mut ix = spl_token_2022::instruction::transfer_checked(
+        &spl_token_2022::ID,
+        &sender.address,
+        mint,
+        &recipient.address,
+        &sender.owner.pubkey(),
+        &[],
+        AMOUNT,
+        common::DECIMALS,
+    )
+    .unwrap();
+    ix.accounts.extend(allowlist_hook_accounts(
+        mint,
+        &sender.owner.pubkey(),
+        &recipient.owner.pubkey(),
+    ));
+    harness.process(&[ix], &[&sender.owner]).await
+}
+
+async fn confidential_transfer(
+    harness: &mut Harness,
+    mint: &Pubkey,
+    sender: &ConfidentialAccount,
+    recipient: &ConfidentialAccount,
+) -> Result<(), BanksClientError> {
+    let mut ix = harness.transfer_ix(mint, sender, recipient, AMOUNT).await;
+    ix.accounts.extend(allowlist_hook_accounts(
+        mint,
+        &sender.owner.pubkey(),
+        &recipient.owner.pubkey(),
+    ));
+    harness.process(&[ix], &[&sender.owner]).await
+}
+
+/// Applies the pending balance first
+async fn balance(harness: &mut Harness, account: &ConfidentialAccount) -> u64 {
+    harness.apply_pending_balance(account).await;
+    harness.available_balance(account).await
+}
+
+#[tokio::test]
+async fn public_transfers_need_both_owners_allowlisted() {
+    let mut harness = Harness::new().await;
+    let accounts = setup(&mut harness).await;
+    let mint = accounts.wrapped.mint;
+    harness
+        .withdraw_confidential(&mint, &accounts.sender, FUNDED)
+        .await;
+
+    public_transfer(&mut harness, &mint, &accounts.sender, &accounts.recipient)
+        .await
+        .unwrap();
+    assert_eq!(
+        harness.token_amount(&accounts.recipient.address).await,
+        AMOUNT
+    );
+
+    let result = public_transfer(&mut harness, &mint, &accounts.sender, &accounts.outsider).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(HookError::RecipientNotAllowlisted)
+    );
+
+    // Removal takes effect on the next transfer, in either direction
+    remove_from_allowlist(&mut harness, &mint, &accounts.recipient.owner.pubkey()).await;
+    let result = public_transfer(&mut harness, &mint, &accounts.sender, &accounts.recipient).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(HookError::RecipientNotAllowlisted)
+    );
+    let result = public_transfer(&mut harness, &mint, &accounts.recipient, &accounts.sender).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(HookError::SenderNotAllowlisted)
+    );
+    assert_eq!(
+        harness.token_amount(&accounts.recipient.address).await,
+        AMOUNT
+    );
+    assert_eq!(
+        harness.token_amount(&accounts.sender.address).await,
+        FUNDED - AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn confidential_transfers_need_both_owners_allowlisted() {
+    let mut harness = Harness::new().await;
+    let accounts = setup(&mut harness).await;
+    let mint = accounts.wrapped.mint;
+
+    confidential_transfer(&mut harness, &mint, &accounts.sender, &accounts.recipient)
+        .await
+        .unwrap();
+    assert_eq!(balance(&mut harness, &accounts.recipient).await, AMOUNT);
+
+    let result =
+        confidential_transfer(&mut harness, &mint, &accounts.sender, &accounts.outsider).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(HookError::RecipientNotAllowlisted)
+    );
+
+    remove_from_allowlist(&mut harness, &mint, &accounts.sender.owner.pubkey()).await;
+    let result =
+        confidential_transfer(&mut harness, &mint, &accounts.sender, &accounts.recipient).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(HookError::SenderNotAllowlisted)
+    );
+    assert_eq!(
+        balance(&mut harness, &accounts.sender).await,
+        FUNDED - AMOUNT
+    );
+    assert_eq!(balance(&mut harness, &accounts.recipient).await, AMOUNT);
+    assert_eq!(balance(&mut harness, &accounts.outsider).await, 0);
+
+    // Allowlisting the sender again lets it through
+    add_to_allowlist(&mut harness, &mint, &accounts.sender.owner.pubkey()).await;
+    confidential_transfer(&mut harness, &mint, &accounts.sender, &accounts.recipient)
+        .await
+        .unwrap();
+    assert_eq!(balance(&mut harness, &accounts.recipient).await, 2 * AMOUNT);
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use common::{signers, ConfidentialAccount, Harness};
+use solana_program_test::tokio;
+use solana_sdk::{clock::Clock, signature::Keypair, signer::Signer};
+use spectre_client::instructions::{self, BatchRecipient};
+use spectre_client::proofs;
+use spl_token_2022::solana_zk_token_sdk::encryption::elgamal::ElGamalKeypair;
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::pod;
+use vault::{batch_transfer_compute_units, TransferFeeParams, MAX_BATCH_TRANSFERS};
+
+/// Fill a batch of MAX_BATCH_TRANSFERS on a new wrapped mint with the client
+/// builders and check every recipient is credited `amount` less the mint's
+/// fee. Returns the compute units the batch instruction's transaction
+/// consumed, which are only metered on `Harness::with_deployed_programs`.
+async fn full_batch(harness: &mut Harness, transfer_fee: Option<TransferFeeParams>) -> u64 {
+    let wrapped = harness.create_wrapped_mint(transfer_fee).await;
+    let mint = wrapped.mint;
+    let sender = harness.create_confidential_account(&mint).await;
+    harness.fund(&wrapped, &sender, 1_000_000).await;
+
+    let mut recipients: Vec<ConfidentialAccount> = Vec::new();
+    for _ in 0..MAX_BATCH_TRANSFERS {
+        recipients.push(harness.create_confidential_account(&mint).await);
+    }
+    let amounts: Vec<u64> = (1..=MAX_BATCH_TRANSFERS as u64)
+        .map(|i| i * 12_345)
+        .collect();
+
+    let clock: Clock = harness.context.banks_client.get_sysvar().await.unwrap();
+    let fee = proofs::mint_transfer_fee(&harness.account_data(&mint).await, clock.epoch).unwrap();
+    assert_eq!(fee.is_some(), transfer_fee.is_some());
+
+    let sender_state = harness.confidential_extension(&sender.address).await;
+    let rent = harness.context.banks_client.get_rent().await.unwrap();
+    let batch_recipients: Vec<BatchRecipient> = recipients
+        .iter()
+        .zip(&amounts)
+        .map(|(recipient, amount)| BatchRecipient {
+            account: recipient.address,
+            elgamal_pubkey: recipient.elgamal_pubkey(),
+            amount: *amount,
+        })
+        .collect();
+    let batch = match &fee {
+        None => instructions::batch_transfer_confidential(
+            &sender.owner.pubkey(),
+            &sender.address,
+            &sender_state,
+            &mint,
+            &batch_recipients,
+            None,
+            &sender.keys(),
+            &rent,
+        ),
+        Some(fee) => instructions::batch_transfer_confidential_with_fee(
+            &sender.owner.pubkey(),
+            &sender.address,
+            &sender_state,
+            &mint,
+            &batch_recipients,
+            None,
+            &sender.keys(),
+            fee,
+            &rent,
+        ),
+    }
+    .unwrap();
+
+    let mut candidates: Vec<&Keypair> = vec![&sender.owner];
+    for proofs in &batch.proofs {
+        candidates.extend([
+            &proofs.equality_proof.keypair,
+            &proofs.ciphertext_validity_proof.keypair,
+            &proofs.range_proof.keypair,
+        ]);
+        if let Some(fee_proofs) = &proofs.fee_proofs {
+            candidates.extend([
+                &fee_proofs.fee_sigma_proof.keypair,
+                &fee_proofs.fee_ciphertext_validity_proof.keypair,
+            ]);
+        }
+    }
+    let mut compute_units = 0;
+    for transaction in batch.transactions() {
+        let signers = signers(&transaction, &candidates);
+        if transaction.contains(&batch.transfer) {
+            compute_units = harness.compute_units(&transaction, &signers).await;
+        }
+        harness.process(&transaction, &signers).await.unwrap();
+    }
+
+    let sent: u64 = amounts.iter().sum();
+    assert_eq!(harness.available_balance(&sender).await, 1_000_000 - sent);
+    for (recipient, amount) in recipients.iter().zip(amounts) {
+        let withheld = fee.as_ref().map_or(0, |fee| fee.fee(amount));
+        harness.apply_pending_balance(recipient).await;
+        assert_eq!(
+            harness.available_balance(recipient).await,
+            amount - withheld
+        );
+    }
+    compute_units
+}
+
+/// Run a full batch on the BPF builds and check it fits the compute budget
+/// the client requests. Skipped unless the vault and Token-2022 are built,
+/// see `Harness::with_deployed_programs`.
+async fn full_batch_compute_units(transfer_fee: Option<TransferFeeParams>) {
+    let Some(mut harness) = Harness::with_deployed_programs().await else {
+        eprintln!("skipped: the vault or Token-2022 BPF build is missing");
+        return;
+    };
+    let with_fee = transfer_fee.is_some();
+    let compute_units = full_batch(&mut harness, transfer_fee).await;
+    let budget = batch_transfer_compute_units(MAX_BATCH_TRANSFERS, with_fee);
+    eprintln!("full batch, with fee {with_fee}: {compute_units} of {budget} compute units");
+    assert!(compute_units <= u64::from(budget));
+}
+
+fn transfer_fee() -> TransferFeeParams {
+    let withdraw_withheld_authority_elgamal_pubkey: pod::ElGamalPubkey =
+        (*ElGamalKeypair::new_rand().pubkey()).into();
+    TransferFeeParams {
+        transfer_fee_basis_points: 100,
+        maximum_fee: 1_000_000,
+        withdraw_withheld_authority_elgamal_pubkey: withdraw_withheld_authority_elgamal_pubkey.0,
+    }
+}
+
+#[tokio::test]
+async fn full_batch_without_fees() {
+    full_batch(&mut Harness::new().await, None).await;
+}
+
+#[tokio::test]
+async fn full_batch_with_fees() {
+    full_batch(&mut Harness::new().await, Some(transfer_fee())).await;
+}
+
+#[tokio::test]
+async fn full_batch_without_fees_fits_its_compute_budget() {
+    full_batch_compute_units(None).await;
+}
+
+#[tokio::test]
+async fn full_batch_with_fees_fits_its_compute_budget() {
+    full_batch_compute_units(Some(transfer_fee())).await;
+}
@@ -0,0 +1,918 @@
+//! Program-test harness for the confidential payment and copy trading
+//! instructions. The vault, the allowlist hook, the constant-product AMM and
+//! Token-2022 run natively, SPL Token runs from the BPF build bundled with
+//! solana-program-test, and the ZK proofs are generated with the same SDK the
+//! clients use.
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+
+use allowlist_hook::{ALLOWLIST_CONFIG_SEED, EXTRA_ACCOUNT_METAS_SEED};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    account_info::AccountInfo,
+    bpf_loader,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signature},
+    signer::{EncodableKey, Signer},
+    system_instruction, system_program, sysvar,
+    transaction::{Transaction, TransactionError},
+};
+use spectre_client::instructions::credit_policy_address;
+use spectre_client::ConfidentialKeys;
+use spl_token_2022::extension::confidential_transfer::account_info::{
+    ApplyPendingBalanceAccountInfo, TransferAccountInfo, WithdrawAccountInfo,
+};
+use spl_token_2022::extension::confidential_transfer::ConfidentialTransferAccount;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::solana_zk_token_sdk::encryption::auth_encryption::AeKey;
+use spl_token_2022::solana_zk_token_sdk::encryption::elgamal::ElGamalKeypair;
+use spl_token_2022::solana_zk_token_sdk::instruction::{PubkeyValidityData, ZkProofData};
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::pod;
+use spl_token_2022::solana_zk_token_sdk::zk_token_proof_instruction::{
+    ContextStateInfo, ProofInstruction,
+};
+use spl_token_2022::solana_zk_token_sdk::zk_token_proof_program;
+use spl_token_2022::solana_zk_token_sdk::zk_token_proof_state::ProofContextState;
+use vault::{
+    TransferFeeParams, AUDIT_CONFIG_SEED, CONFIDENTIAL_AUTHORITY_SEED, FREEZE_AUTHORITY_SEED,
+    HOOK_AUTHORITY_SEED, MINT_AUTHORITY_SEED, RESERVE_SEED, WRAPPER_SEED,
+};
+
+pub const DECIMALS: u8 = 6;
+
+/// The vault as built by `anchor build`
+const VAULT_SO: &str = "../../target/deploy/vault.so";
+/// Token-2022 3.0 built with the `zk-ops` feature, which the confidential
+/// transfer ops need
+const TOKEN_2022_SO: &str = "tests/fixtures/spl_token_2022.so";
+
+/// Anchor hands the entrypoint accounts that must outlive the call, which
+/// the program-test processor signature can't express
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    vault::entry(program_id, accounts, data)
+}
+
+fn process_amm_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    constant_product_amm::entry(program_id, accounts, data)
+}
+
+fn process_hook_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    allowlist_hook::entry(program_id, accounts, data)
+}
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &vault::ID).0
+}
+
+pub fn hook_pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &allowlist_hook::ID).0
+}
+
+pub fn vault_ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: vault::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Custom error code of a failed transaction, if the program returned one
+pub fn custom_error(result: Result<(), BanksClientError>) -> Option<u32> {
+    match result.expect_err("transaction should fail").unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+        _ => None,
+    }
+}
+
+pub fn anchor_error(error: impl Into<u32>) -> Option<u32> {
+    Some(error.into())
+}
+
+/// The keypairs among `candidates` that `instructions` need signatures from
+pub fn signers<'a>(instructions: &[Instruction], candidates: &[&'a Keypair]) -> Vec<&'a Keypair> {
+    candidates
+        .iter()
+        .copied()
+        .filter(|keypair| {
+            instructions.iter().any(|ix| {
+                ix.accounts
+                    .iter()
+                    .any(|meta| meta.is_signer && meta.pubkey == keypair.pubkey())
+            })
+        })
+        .collect()
+}
+
+pub struct Harness {
+    pub context: ProgramTestContext,
+    signatures: HashSet<Signature>,
+}
+
+/// A Token-2022 account configured for confidential transfers, with the
+/// owner's keys
+pub struct ConfidentialAccount {
+    pub owner: Keypair,
+    pub address: Pubkey,
+    pub elgamal: ElGamalKeypair,
+    pub aes: AeKey,
+}
+
+impl ConfidentialAccount {
+    /// The account's keys as the client takes them
+    pub fn keys(&self) -> ConfidentialKeys {
+        // AeKey isn't Clone, copy it through its encoding
+        let mut ae = Vec::new();
+        self.aes.write(&mut ae).unwrap();
+        ConfidentialKeys {
+            elgamal: self.elgamal.clone(),
+            ae: AeKey::read(&mut ae.as_slice()).unwrap(),
+        }
+    }
+
+    pub fn elgamal_pubkey(&self) -> pod::ElGamalPubkey {
+        (*self.elgamal.pubkey()).into()
+    }
+}
+
+/// Confidential mint backed 1:1 by a classic SPL token
+pub struct WrappedMint {
+    pub mint: Pubkey,
+    pub underlying_mint: Pubkey,
+    pub reserve: Pubkey,
+    /// Mint authority of the underlying token
+    pub underlying_authority: Keypair,
+}
+
+/// Executable account holding a BPF program build
+fn deployed_program(elf: Vec<u8>) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(elf.len()),
+        data: elf,
+        owner: bpf_loader::ID,
+        executable: true,
+        rent_epoch: 0,
+    }
+}
+
+impl Harness {
+    pub async fn new() -> Self {
+        let mut program_test =
+            ProgramTest::new("vault", vault::ID, processor!(process_instruction));
+        program_test.prefer_bpf(false);
+        // The Token-2022 build bundled with program-test leaves out the
+        // confidential transfer ops (zk-ops), so run the crate's processor
+        program_test.add_program(
+            "spl_token_2022",
+            spl_token_2022::ID,
+            processor!(spl_token_2022::processor::Processor::process),
+        );
+        Self::start(program_test).await
+    }
+
+    /// Harness running the BPF builds of the vault and Token-2022 from
+    /// `VAULT_SO` and `TOKEN_2022_SO`, so compute units are metered. None
+    /// unless both are built.
+    pub async fn with_deployed_programs() -> Option<Self> {
+        let vault = std::fs::read(VAULT_SO).ok()?;
+        let token_2022 = std::fs::read(TOKEN_2022_SO).ok()?;
+        let mut program_test = ProgramTest::default();
+        program_test.prefer_bpf(false);
+        program_test.add_account(vault::ID, deployed_program(vault));
+        program_test.add_account(spl_token_2022::ID, deployed_program(token_2022));
+        Some(Self::start(program_test).await)
+    }
+
+    async fn start(mut program_test: ProgramTest) -> Self {
+        program_test.add_program(
+            "constant_product_amm",
+            constant_product_amm::ID,
+            processor!(process_amm_instruction),
+        );
+        program_test.add_program(
+            "allowlist_hook",
+            allowlist_hook::ID,
+            processor!(process_hook_instruction),
+        );
+        Self {
+            context: program_test.start_with_context().await,
+            signatures: HashSet::new(),
+        }
+    }
+
+    pub fn payer(&self) -> Keypair {
+        self.context.payer.insecure_clone()
+    }
+
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let transaction = self.transaction(instructions, signers).await;
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+    }
+
+    async fn transaction(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Transaction {
+        let payer = self.payer();
+        let mut all_signers = vec![&payer];
+        all_signers.extend_from_slice(signers);
+        let mut blockhash = self
+            .context
+            .banks_client
+            .get_latest_blockhash()
+            .await
+            .unwrap();
+        loop {
+            let transaction = Transaction::new_signed_with_payer(
+                instructions,
+                Some(&payer.pubkey()),
+                &all_signers,
+                blockhash,
+            );
+            if self.signatures.insert(transaction.signatures[0]) {
+                return transaction;
+            }
+            // Resending the same transaction, wait for a fresh blockhash so it
+            // isn't dropped as already processed
+            blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        }
+    }
+
+    /// Land a client instruction set's transactions in order: the proofs, the
+    /// instruction under test, then the one closing the proof accounts.
+    /// Signs each with the `candidates` it needs and returns the result of
+    /// the instruction under test.
+    pub async fn process_with_proofs(
+        &mut self,
+        transactions: &[Vec<Instruction>],
+        candidates: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let (close, transactions) = transactions.split_last().unwrap();
+        let (instruction, proofs) = transactions.split_last().unwrap();
+        for transaction in proofs {
+            self.process(transaction, &signers(transaction, candidates))
+                .await
+                .unwrap();
+        }
+        let result = self
+            .process(instruction, &signers(instruction, candidates))
+            .await;
+        self.process(close, &signers(close, candidates))
+            .await
+            .unwrap();
+        result
+    }
+
+    /// Compute units the transaction consumes, simulated without landing it
+    pub async fn compute_units(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> u64 {
+        let payer = self.payer();
+        let mut all_signers = vec![&payer];
+        all_signers.extend_from_slice(signers);
+        let blockhash = self
+            .context
+            .banks_client
+            .get_latest_blockhash()
+            .await
+            .unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        simulation.result.unwrap().unwrap();
+        simulation.simulation_details.unwrap().units_consumed
+    }
+
+    pub async fn unix_timestamp(&mut self) -> i64 {
+        let clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp
+    }
+
+    pub async fn warp_to(&mut self, unix_timestamp: i64) {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp = unix_timestamp;
+        self.context.set_sysvar(&clock);
+    }
+
+    pub async fn funded_keypair(&mut self) -> Keypair {
+        let keypair = Keypair::new();
+        let payer = self.payer();
+        self.process(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &keypair.pubkey(),
+                1_000_000_000,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+        keypair
+    }
+
+    pub async fn account_data(&mut self, address: &Pubkey) -> Vec<u8> {
+        self.context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .expect("account exists")
+            .data
+    }
+
+    /// Write an account directly, for state no instruction can create any
+    /// more
+    pub fn set_account(&mut self, address: &Pubkey, owner: &Pubkey, lamports: u64, data: Vec<u8>) {
+        let mut account = AccountSharedData::new(lamports, data.len(), owner);
+        account.set_data_from_slice(&data);
+        self.context.set_account(address, &account);
+    }
+
+    pub async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.context
+            .banks_client
+            .get_balance(*address)
+            .await
+            .unwrap()
+    }
+
+    pub async fn account_exists(&mut self, address: &Pubkey) -> bool {
+        self.context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    /// Public balance of a Token-2022 or SPL Token account
+    pub async fn token_amount(&mut self, address: &Pubkey) -> u64 {
+        let data = self.account_data(address).await;
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    pub async fn is_frozen(&mut self, address: &Pubkey) -> bool {
+        let data = self.account_data(address).await;
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)
+            .unwrap()
+            .base
+            .is_frozen()
+    }
+
+    pub async fn mint_supply(&mut self, mint: &Pubkey) -> u64 {
+        let data = self.account_data(mint).await;
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)
+            .unwrap()
+            .base
+            .supply
+    }
+
+    pub async fn confidential_extension(
+        &mut self,
+        address: &Pubkey,
+    ) -> ConfidentialTransferAccount {
+        let data = self.account_data(address).await;
+        *StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)
+            .unwrap()
+            .get_extension::<ConfidentialTransferAccount>()
+            .unwrap()
+    }
+
+    // ------------------------------------------------------------------------
+    // Mint and accounts
+    // ------------------------------------------------------------------------
+
+    pub async fn create_confidential_mint(
+        &mut self,
+        auto_approve_new_accounts: bool,
+        transfer_fee: Option<TransferFeeParams>,
+    ) -> Pubkey {
+        self.create_hooked_mint(auto_approve_new_accounts, transfer_fee, None)
+            .await
+    }
+
+    async fn create_hooked_mint(
+        &mut self,
+        auto_approve_new_accounts: bool,
+        transfer_fee: Option<TransferFeeParams>,
+        transfer_hook_program_id: Option<Pubkey>,
+    ) -> Pubkey {
+        let payer = self.payer();
+        let mint = Keypair::new();
+        let mint_key = mint.pubkey();
+        let ix = vault_ix(
+            vault::accounts::InitializeMint {
+                payer: payer.pubkey(),
+                mint: mint_key,
+                mint_authority: pda(&[MINT_AUTHORITY_SEED, mint_key.as_ref()]),
+                confidential_authority: pda(&[CONFIDENTIAL_AUTHORITY_SEED, mint_key.as_ref()]),
+                freeze_authority: pda(&[FREEZE_AUTHORITY_SEED, mint_key.as_ref()]),
+                hook_authority: pda(&[HOOK_AUTHORITY_SEED, mint_key.as_ref()]),
+                audit_config: pda(&[AUDIT_CONFIG_SEED, mint_key.as_ref()]),
+                token_program: spl_token_2022::ID,
+                system_program: system_program::ID,
+            },
+            vault::instruction::InitializeConfidentialMint {
+                decimals: DECIMALS,
+                auto_approve_new_accounts,
+                auditor_elgamal_pubkey: None,
+                transfer_fee,
+                transfer_hook_program_id,
+            },
+        );
+        self.process(&[ix], &[&mint]).await.unwrap();
+        mint_key
+    }
+
+    /// Confidential mint plus a classic SPL token it wraps. The harness payer
+    /// is the audit, KYC and compliance authority of the mint.
+    pub async fn create_wrapped_mint(
+        &mut self,
+        transfer_fee: Option<TransferFeeParams>,
+    ) -> WrappedMint {
+        let mint = self.create_confidential_mint(true, transfer_fee).await;
+        self.wrap_mint(mint).await
+    }
+
+    /// Wrapped mint whose transfers go through the allowlist hook, with an
+    /// empty allowlist administered by the harness payer
+    pub async fn create_allowlisted_mint(&mut self) -> WrappedMint {
+        let mint = self
+            .create_hooked_mint(true, None, Some(allowlist_hook::ID))
+            .await;
+        let payer = self.payer();
+        let ix = vault_ix(
+            vault::accounts::InitializeTransferHookAllowlist {
+                compliance_authority: payer.pubkey(),
+                mint,
+                audit_config: pda(&[AUDIT_CONFIG_SEED, mint.as_ref()]),
+                hook_authority: pda(&[HOOK_AUTHORITY_SEED, mint.as_ref()]),
+                extra_account_meta_list: hook_pda(&[EXTRA_ACCOUNT_METAS_SEED, mint.as_ref()]),
+                allowlist_config: hook_pda(&[ALLOWLIST_CONFIG_SEED, mint.as_ref()]),
+                allowlist_program: allowlist_hook::ID,
+                system_program: system_program::ID,
+            },
+            vault::instruction::InitializeTransferHookAllowlist {},
+        );
+        self.process(&[ix], &[]).await.unwrap();
+        self.wrap_mint(mint).await
+    }
+
+    async fn wrap_mint(&mut self, mint: Pubkey) -> WrappedMint {
+        let payer = self.payer();
+        let underlying_authority = Keypair::new();
+        let underlying_mint = self.create_spl_mint(&underlying_authority.pubkey()).await;
+
+        let reserve = pda(&[RESERVE_SEED, mint.as_ref()]);
+        let ix = vault_ix(
+            vault::accounts::InitializeWrapper {
+                authority: payer.pubkey(),
+                audit_config: pda(&[AUDIT_CONFIG_SEED, mint.as_ref()]),
+                wrapper: pda(&[WRAPPER_SEED, mint.as_ref()]),
+                mint,
+                underlying_mint,
+                reserve,
+                mint_authority: pda(&[MINT_AUTHORITY_SEED, mint.as_ref()]),
+                underlying_token_program: spl_token::ID,
+                system_program: system_program::ID,
+            },
+            vault::instruction::InitializeWrapper {},
+        );
+        self.process(&[ix], &[]).await.unwrap();
+
+        WrappedMint {
+            mint,
+            underlying_mint,
+            reserve,
+            underlying_authority,
+        }
+    }
+
+    /// New owner with a confidential account on `mint`, configured through
+    /// initialize_user_account with an inline pubkey validity proof
+    pub async fn create_confidential_account(&mut self, mint: &Pubkey) -> ConfidentialAccount {
+        let owner = self.funded_keypair().await;
+        let account = Keypair::new();
+        let elgamal = ElGamalKeypair::new_rand();
+        let aes = AeKey::new_rand();
+        let proof_data = PubkeyValidityData::new(&elgamal).unwrap();
+        let decryptable_zero_balance: pod::AeCiphertext = aes.encrypt(0).into();
+
+        let ix = vault_ix(
+            vault::accounts::InitializeUserAccount {
+                user: owner.pubkey(),
+                confidential_account: account.pubkey(),
+                mint: *mint,
+                proof_account: sysvar::instructions::ID,
+                token_program: spl_token_2022::ID,
+                system_program: system_program::ID,
+            },
+            vault::instruction::InitializeUserAccount {
+                elgamal_pubkey: proof_data.context.pubkey.0,
+                decryptable_zero_balance: decryptable_zero_balance.0,
+                proof_instruction_offset: 1,
+            },
+        );
+        let proof_ix =
+            ProofInstruction::VerifyPubkeyValidity.encode_verify_proof(None, &proof_data);
+        self.process(&[ix, proof_ix], &[&owner, &account])
+            .await
+            .unwrap();
+
+        ConfidentialAccount {
+            owner,
+            address: account.pubkey(),
+            elgamal,
+            aes,
+        }
+    }
+
+    /// Classic SPL token account of `owner` holding `amount` underlying tokens
+    pub async fn underlying_account(
+        &mut self,
+        wrapped: &WrappedMint,
+        owner: &Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        self.spl_token_account(
+            &wrapped.underlying_mint,
+            &wrapped.underlying_authority,
+            owner,
+            amount,
+        )
+        .await
+    }
+
+    /// Classic SPL token mint with `DECIMALS` decimals
+    pub async fn create_spl_mint(&mut self, authority: &Pubkey) -> Pubkey {
+        let payer = self.payer();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let mint = Keypair::new();
+        self.process(
+            &[
+                system_instruction::create_account(
+                    &payer.pubkey(),
+                    &mint.pubkey(),
+                    rent.minimum_balance(spl_token::state::Mint::LEN),
+                    spl_token::state::Mint::LEN as u64,
+                    &spl_token::ID,
+                ),
+                spl_token::instruction::initialize_mint2(
+                    &spl_token::ID,
+                    &mint.pubkey(),
+                    authority,
+                    None,
+                    DECIMALS,
+                )
+                .unwrap(),
+            ],
+            &[&mint],
+        )
+        .await
+        .unwrap();
+        mint.pubkey()
+    }
+
+    /// Classic SPL token account of `owner` holding `amount` tokens of
+    /// `mint`, minted by `mint_authority`
+    pub async fn spl_token_account(
+        &mut self,
+        mint: &Pubkey,
+        mint_authority: &Keypair,
+        owner: &Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        let payer = self.payer();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let account = Keypair::new();
+        let mut instructions = vec![
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(spl_token::state::Account::LEN),
+                spl_token::state::Account::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_account3(
+                &spl_token::ID,
+                &account.pubkey(),
+                mint,
+                owner,
+            )
+            .unwrap(),
+        ];
+        let mut signers = vec![&account];
+        if amount > 0 {
+            signers.push(mint_authority);
+            instructions.push(
+                spl_token::instruction::mint_to(
+                    &spl_token::ID,
+                    mint,
+                    &account.pubkey(),
+                    &mint_authority.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            );
+        }
+        self.process(&instructions, &signers).await.unwrap();
+        account.pubkey()
+    }
+
+    // ------------------------------------------------------------------------
+    // Wrapper
+    // ------------------------------------------------------------------------
+
+    pub fn wrap_ix(
+        &self,
+        wrapped: &WrappedMint,
+        user: &ConfidentialAccount,
+        underlying_account: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        vault_ix(
+            vault::accounts::Wrap {
+                user: user.owner.pubkey(),
+                wrapper: pda(&[WRAPPER_SEED, wrapped.mint.as_ref()]),
+                mint: wrapped.mint,
+                underlying_mint: wrapped.underlying_mint,
+                reserve: wrapped.reserve,
+                user_underlying_account: *underlying_account,
+                confidential_account: user.address,
+                credit_policy: credit_policy_address(&user.address),
+                mint_authority: pda(&[MINT_AUTHORITY_SEED, wrapped.mint.as_ref()]),
+                token_program: spl_token_2022::ID,
+                underlying_token_program: spl_token::ID,
+            },
+            vault::instruction::Wrap { amount },
+        )
+    }
+
+    pub fn unwrap_ix(
+        &self,
+        wrapped: &WrappedMint,
+        user: &ConfidentialAccount,
+        underlying_account: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        vault_ix(
+            vault::accounts::Unwrap {
+                user: user.owner.pubkey(),
+                wrapper: pda(&[WRAPPER_SEED, wrapped.mint.as_ref()]),
+                mint: wrapped.mint,
+                underlying_mint: wrapped.underlying_mint,
+                reserve: wrapped.reserve,
+                user_underlying_account: *underlying_account,
+                confidential_account: user.address,
+                mint_authority: pda(&[MINT_AUTHORITY_SEED, wrapped.mint.as_ref()]),
+                token_program: spl_token_2022::ID,
+                underlying_token_program: spl_token::ID,
+            },
+            vault::instruction::Unwrap { amount },
+        )
+    }
+
+    /// Wrap `amount` fresh underlying tokens into the user's available
+    /// encrypted balance. Returns the underlying token account.
+    pub async fn fund(
+        &mut self,
+        wrapped: &WrappedMint,
+        user: &ConfidentialAccount,
+        amount: u64,
+    ) -> Pubkey {
+        let underlying_account = self
+            .underlying_account(wrapped, &user.owner.pubkey(), amount)
+            .await;
+        let ix = self.wrap_ix(wrapped, user, &underlying_account, amount);
+        self.process(&[ix], &[&user.owner]).await.unwrap();
+        self.apply_pending_balance(user).await;
+        underlying_account
+    }
+
+    // ------------------------------------------------------------------------
+    // Confidential balance
+    // ------------------------------------------------------------------------
+
+    pub async fn apply_pending_balance(&mut self, user: &ConfidentialAccount) {
+        let extension = self.confidential_extension(&user.address).await;
+        let info = ApplyPendingBalanceAccountInfo::new(&extension);
+        let new_decryptable_available_balance: pod::AeCiphertext = info
+            .new_decryptable_available_balance(user.elgamal.secret(), &user.aes)
+            .unwrap()
+            .into();
+        let ix = vault_ix(
+            vault::accounts::ApplyPendingBalance {
+                authority: user.owner.pubkey(),
+                confidential_account: user.address,
+                credit_policy: credit_policy_address(&user.address),
+                token_program: spl_token_2022::ID,
+            },
+            vault::instruction::ApplyPendingBalance {
+                expected_pending_balance_credit_counter: info.pending_balance_credit_counter(),
+                new_decryptable_available_balance: new_decryptable_available_balance.0,
+            },
+        );
+        self.process(&[ix], &[&user.owner]).await.unwrap();
+    }
+
+    /// Decrypted available balance of the account
+    pub async fn available_balance(&mut self, user: &ConfidentialAccount) -> u64 {
+        let extension = self.confidential_extension(&user.address).await;
+        let decryptable = extension.decryptable_available_balance.try_into().unwrap();
+        user.aes.decrypt(&decryptable).unwrap()
+    }
+
+    /// Move `amount` from the encrypted available balance to public balance
+    pub async fn withdraw_confidential(
+        &mut self,
+        mint: &Pubkey,
+        user: &ConfidentialAccount,
+        amount: u64,
+    ) {
+        let extension = self.confidential_extension(&user.address).await;
+        let info = WithdrawAccountInfo::new(&extension);
+        let proof_data = info
+            .generate_proof_data(amount, &user.elgamal, &user.aes)
+            .unwrap();
+        let new_decryptable_available_balance: pod::AeCiphertext = info
+            .new_decryptable_available_balance(amount, &user.aes)
+            .unwrap()
+            .into();
+        let ix = vault_ix(
+            vault::accounts::WithdrawConfidential {
+                authority: user.owner.pubkey(),
+                confidential_account: user.address,
+                mint: *mint,
+                proof_account: sysvar::instructions::ID,
+                token_program: spl_token_2022::ID,
+            },
+            vault::instruction::WithdrawConfidential {
+                amount,
+                new_decryptable_available_balance: new_decryptable_available_balance.0,
+                proof_instruction_offset: 1,
+            },
+        );
+        let proof_ix = ProofInstruction::VerifyWithdraw.encode_verify_proof(None, &proof_data);
+        self.process(&[ix, proof_ix], &[&user.owner]).await.unwrap();
+    }
+
+    // ------------------------------------------------------------------------
+    // Confidential transfers
+    // ------------------------------------------------------------------------
+
+    /// Verify `proof_data` into a new context state account owned by
+    /// `authority`
+    pub async fn create_proof_context<T, U>(
+        &mut self,
+        proof_instruction: ProofInstruction,
+        proof_data: &T,
+        authority: &Pubkey,
+    ) -> Pubkey
+    where
+        T: bytemuck::Pod + ZkProofData<U>,
+        U: bytemuck::Pod,
+    {
+        let payer = self.payer();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let context_state = Keypair::new();
+        let space = std::mem::size_of::<ProofContextState<U>>();
+        self.process(
+            &[
+                system_instruction::create_account(
+                    &payer.pubkey(),
+                    &context_state.pubkey(),
+                    rent.minimum_balance(space),
+                    space as u64,
+                    &zk_token_proof_program::id(),
+                ),
+                proof_instruction.encode_verify_proof(
+                    Some(ContextStateInfo {
+                        context_state_account: &context_state.pubkey(),
+                        context_state_authority: authority,
+                    }),
+                    proof_data,
+                ),
+            ],
+            &[&context_state],
+        )
+        .await
+        .unwrap();
+        context_state.pubkey()
+    }
+
+    /// Prove a transfer of `amount` from `sender` to `recipient` on a mint
+    /// without fees and build its transfer_confidential instruction
+    pub async fn transfer_ix(
+        &mut self,
+        mint: &Pubkey,
+        sender: &ConfidentialAccount,
+        recipient: &ConfidentialAccount,
+        amount: u64,
+    ) -> Instruction {
+        let extension = self.confidential_extension(&sender.address).await;
+        let (equality, ciphertext_validity, range, source_decrypt_handles) =
+            TransferAccountInfo::new(&extension)
+                .generate_split_transfer_proof_data(
+                    amount,
+                    &sender.elgamal,
+                    &sender.aes,
+                    recipient.elgamal.pubkey(),
+                    None,
+                )
+                .unwrap();
+        let new_decryptable_available_balance: pod::AeCiphertext = sender
+            .aes
+            .encrypt(self.available_balance(sender).await - amount)
+            .into();
+
+        let owner = sender.owner.pubkey();
+        let equality_proof = self
+            .create_proof_context(
+                ProofInstruction::VerifyCiphertextCommitmentEquality,
+                &equality,
+                &owner,
+            )
+            .await;
+        let ciphertext_validity_proof = self
+            .create_proof_context(
+                ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity,
+                &ciphertext_validity,
+                &owner,
+            )
+            .await;
+        let range_proof = self
+            .create_proof_context(
+                ProofInstruction::VerifyBatchedRangeProofU128,
+                &range,
+                &owner,
+            )
+            .await;
+
+        vault_ix(
+            vault::accounts::TransferConfidential {
+                sender: owner,
+                sender_account: sender.address,
+                recipient_account: recipient.address,
+                mint: *mint,
+                equality_proof,
+                ciphertext_validity_proof,
+                fee_sigma_proof: None,
+                fee_ciphertext_validity_proof: None,
+                range_proof,
+                credit_policy: credit_policy_address(&sender.address),
+                token_program: spl_token_2022::ID,
+            },
+            vault::instruction::TransferConfidential {
+                new_source_decryptable_available_balance: new_decryptable_available_balance.0,
+                source_decrypt_handles: bytemuck::cast(source_decrypt_handles),
+                memo: None,
+            },
+        )
+    }
+}
@@ -0,0 +1,791 @@
+mod common;
+
+use anchor_lang::{
+    AccountDeserialize, AccountSerialize, AnchorSerialize, Discriminator, InstructionData,
+    ToAccountMetas,
+};
+use anchor_spl::token::spl_token;
+use common::{anchor_error, custom_error, pda, vault_ix, Harness};
+use constant_product_amm::{AmmError, POOL_SEED, POOL_VAULT_SEED};
+use solana_program_test::{tokio, BanksClientError};
+use solana_sdk::{
+    clock::Clock, instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    system_program,
+};
+use vault::{
+    LegacyPosition, LegacyStrategy, Strategy, TradeSide, TradingConfig, UserPosition, VaultError,
+    SWAP_VENUE_SEED, TRADING_CONFIG_SEED,
+};
+
+const LEGACY_DEPOSIT: u64 = 5_000_000_000;
+const POOL_LIQUIDITY: u64 = 1_000_000_000;
+const POOL_FEE_BPS: u16 = 30;
+const MAX_DEVIATION_BPS: u16 = 300;
+const DEPOSIT: u64 = 10_000_000;
+
+fn trader_profile_address(trader: &Pubkey) -> Pubkey {
+    pda(&[b"trader_profile", trader.as_ref()])
+}
+
+fn strategy_address(trader: &Pubkey, index: u32) -> Pubkey {
+    pda(&[b"strategy", trader.as_ref(), &index.to_le_bytes()])
+}
+
+fn position_address(user: &Pubkey, strategy: &Pubkey) -> Pubkey {
+    pda(&[b"position", user.as_ref(), strategy.as_ref()])
+}
+
+fn strategy_vault_address(strategy: &Pubkey, mint: &Pubkey) -> Pubkey {
+    pda(&[b"strategy_vault", strategy.as_ref(), mint.as_ref()])
+}
+
+async fn strategy(harness: &mut Harness, address: &Pubkey) -> Strategy {
+    let data = harness.account_data(address).await;
+    Strategy::try_deserialize(&mut data.as_slice()).unwrap()
+}
+
+async fn position(harness: &mut Harness, address: &Pubkey) -> UserPosition {
+    let data = harness.account_data(address).await;
+    UserPosition::try_deserialize(&mut data.as_slice()).unwrap()
+}
+
+/// Write a strategy in the single-strategy layout at `[b"strategy", trader]`
+async fn create_legacy_strategy(harness: &mut Harness, trader: &Pubkey) -> Pubkey {
+    let (address, bump) = Pubkey::find_program_address(&[b"strategy", trader.as_ref()], &vault::ID);
+    let legacy = LegacyStrategy {
+        trader: *trader,
+        name: "Legacy Book".to_string(),
+        description: "Strategy from before indexed PDAs".to_string(),
+        performance_fee_bps: 1_500,
+        total_subscribers: 3,
+        total_volume_traded: 42_000,
+        total_fees_earned: 700,
+        is_active: true,
+        created_at: 1_700_000_000,
+        bump,
+    };
+    let mut data = Strategy::DISCRIMINATOR.to_vec();
+    legacy.serialize(&mut data).unwrap();
+    let rent = harness.context.banks_client.get_rent().await.unwrap();
+    harness.set_account(&address, &vault::ID, rent.minimum_balance(data.len()), data);
+    address
+}
+
+/// Write a position in the pre-custody layout, holding its deposit as
+/// lamports on top of the rent
+async fn create_legacy_position(
+    harness: &mut Harness,
+    user: &Pubkey,
+    legacy_strategy: &Pubkey,
+) -> Pubkey {
+    let (address, bump) = Pubkey::find_program_address(
+        &[b"position", user.as_ref(), legacy_strategy.as_ref()],
+        &vault::ID,
+    );
+    let legacy = LegacyPosition {
+        user: *user,
+        strategy: *legacy_strategy,
+        initial_balance: LEGACY_DEPOSIT,
+        current_balance: LEGACY_DEPOSIT,
+        total_fees_paid: 0,
+        last_fee_settlement: 1_700_000_000,
+        subscribed_at: 1_700_000_000,
+        is_active: true,
+        bump,
+    };
+    let mut data = UserPosition::DISCRIMINATOR.to_vec();
+    legacy.serialize(&mut data).unwrap();
+    let rent = harness.context.banks_client.get_rent().await.unwrap();
+    let lamports = rent.minimum_balance(data.len()) + LEGACY_DEPOSIT;
+    harness.set_account(&address, &vault::ID, lamports, data);
+    address
+}
+
+/// Trader with a profile and a legacy strategy migrated to index 0
+async fn migrated_strategy(harness: &mut Harness) -> (Keypair, Pubkey, Pubkey) {
+    let trader = harness.funded_keypair().await;
+    let legacy_strategy = create_legacy_strategy(harness, &trader.pubkey()).await;
+    let initialize_profile = vault_ix(
+        vault::accounts::InitializeTraderProfile {
+            trader: trader.pubkey(),
+            trader_profile: trader_profile_address(&trader.pubkey()),
+            system_program: system_program::ID,
+        },
+        vault::instruction::InitializeTraderProfile {},
+    );
+    let migrate = vault_ix(
+        vault::accounts::MigrateStrategy {
+            trader: trader.pubkey(),
+            trader_profile: trader_profile_address(&trader.pubkey()),
+            legacy_strategy,
+            strategy: strategy_address(&trader.pubkey(), 0),
+            system_program: system_program::ID,
+        },
+        vault::instruction::MigrateStrategy {},
+    );
+    harness
+        .process(&[initialize_profile, migrate], &[&trader])
+        .await
+        .unwrap();
+    let strategy = strategy_address(&trader.pubkey(), 0);
+    (trader, legacy_strategy, strategy)
+}
+
+fn migrate_position_ix(user: &Keypair, strategy: &Pubkey, legacy_position: &Pubkey) -> Instruction {
+    vault_ix(
+        vault::accounts::MigratePosition {
+            user: user.pubkey(),
+            strategy: *strategy,
+            legacy_position: *legacy_position,
+        },
+        vault::instruction::MigratePosition {},
+    )
+}
+
+/// Base and asset mints with a 1:1 constant-product pool between them,
+/// allowlisted as their venue by the harness payer
+struct Market {
+    base_mint: Pubkey,
+    asset_mint: Pubkey,
+    /// Mint authority of both mints
+    authority: Keypair,
+    pool: Pubkey,
+    pool_base_vault: Pubkey,
+    pool_asset_vault: Pubkey,
+}
+
+fn amm_ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: constant_product_amm::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn venue_address(base_mint: &Pubkey, asset_mint: &Pubkey) -> Pubkey {
+    pda(&[SWAP_VENUE_SEED, base_mint.as_ref(), asset_mint.as_ref()])
+}
+
+/// Name `admin` in the trading config. Creating it takes the upgrade
+/// authority of a deployed program, which the native vault doesn't have.
+fn set_trading_admin(harness: &mut Harness, admin: &Pubkey) {
+    let (address, bump) = Pubkey::find_program_address(&[TRADING_CONFIG_SEED], &vault::ID);
+    let mut data = Vec::new();
+    TradingConfig {
+        admin: *admin,
+        bump,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    harness.set_account(&address, &vault::ID, 1_000_000_000, data);
+}
+
+fn add_swap_venue_ix(admin: &Keypair, market: &Market) -> Instruction {
+    vault_ix(
+        vault::accounts::AddSwapVenue {
+            admin: admin.pubkey(),
+            trading_config: pda(&[TRADING_CONFIG_SEED]),
+            base_mint: market.base_mint,
+            asset_mint: market.asset_mint,
+            venue: venue_address(&market.base_mint, &market.asset_mint),
+            swap_program: constant_product_amm::ID,
+            pool: market.pool,
+            pool_base_vault: market.pool_base_vault,
+            pool_asset_vault: market.pool_asset_vault,
+            system_program: system_program::ID,
+        },
+        vault::instruction::AddSwapVenue {
+            reference_base: 1,
+            reference_asset: 1,
+            max_deviation_bps: MAX_DEVIATION_BPS,
+        },
+    )
+}
+
+fn set_reference_price_ix(
+    admin: &Keypair,
+    market: &Market,
+    reference_base: u64,
+    reference_asset: u64,
+) -> Instruction {
+    vault_ix(
+        vault::accounts::ManageSwapVenue {
+            admin: admin.pubkey(),
+            trading_config: pda(&[TRADING_CONFIG_SEED]),
+            venue: venue_address(&market.base_mint, &market.asset_mint),
+        },
+        vault::instruction::SetReferencePrice {
+            reference_base,
+            reference_asset,
+        },
+    )
+}
+
+/// Pool for a new pair, not allowlisted yet
+async fn create_pool(harness: &mut Harness) -> Market {
+    let payer = harness.payer();
+    let authority = Keypair::new();
+    let base_mint = harness.create_spl_mint(&authority.pubkey()).await;
+    let asset_mint = harness.create_spl_mint(&authority.pubkey()).await;
+    let (mint_a, mint_b) = if base_mint < asset_mint {
+        (base_mint, asset_mint)
+    } else {
+        (asset_mint, base_mint)
+    };
+    let pool = Pubkey::find_program_address(
+        &[POOL_SEED, mint_a.as_ref(), mint_b.as_ref()],
+        &constant_product_amm::ID,
+    )
+    .0;
+    let pool_vault = |mint: &Pubkey| {
+        Pubkey::find_program_address(
+            &[POOL_VAULT_SEED, pool.as_ref(), mint.as_ref()],
+            &constant_product_amm::ID,
+        )
+        .0
+    };
+    let initialize_pool = amm_ix(
+        constant_product_amm::accounts::InitializePool {
+            payer: payer.pubkey(),
+            mint_a,
+            mint_b,
+            pool,
+            vault_a: pool_vault(&mint_a),
+            vault_b: pool_vault(&mint_b),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        constant_product_amm::instruction::InitializePool {
+            fee_bps: POOL_FEE_BPS,
+        },
+    );
+    harness.process(&[initialize_pool], &[]).await.unwrap();
+
+    let provider_a = harness
+        .spl_token_account(&mint_a, &authority, &payer.pubkey(), POOL_LIQUIDITY)
+        .await;
+    let provider_b = harness
+        .spl_token_account(&mint_b, &authority, &payer.pubkey(), POOL_LIQUIDITY)
+        .await;
+    let add_liquidity = amm_ix(
+        constant_product_amm::accounts::AddLiquidity {
+            provider: payer.pubkey(),
+            pool,
+            vault_a: pool_vault(&mint_a),
+            vault_b: pool_vault(&mint_b),
+            provider_a,
+            provider_b,
+            token_program: spl_token::ID,
+        },
+        constant_product_amm::instruction::AddLiquidity {
+            amount_a: POOL_LIQUIDITY,
+            amount_b: POOL_LIQUIDITY,
+        },
+    );
+    harness.process(&[add_liquidity], &[]).await.unwrap();
+
+    Market {
+        base_mint,
+        asset_mint,
+        authority,
+        pool,
+        pool_base_vault: pool_vault(&base_mint),
+        pool_asset_vault: pool_vault(&asset_mint),
+    }
+}
+
+async fn create_market(harness: &mut Harness) -> Market {
+    let market = create_pool(harness).await;
+    let payer = harness.payer();
+    set_trading_admin(harness, &payer.pubkey());
+    harness
+        .process(&[add_swap_venue_ix(&payer, &market)], &[])
+        .await
+        .unwrap();
+    market
+}
+
+fn initialize_strategy_vaults_ix(
+    trader: &Keypair,
+    strategy: &Pubkey,
+    market: &Market,
+) -> Instruction {
+    vault_ix(
+        vault::accounts::InitializeStrategyVaults {
+            trader: trader.pubkey(),
+            strategy: *strategy,
+            base_mint: market.base_mint,
+            asset_mint: market.asset_mint,
+            venue: venue_address(&market.base_mint, &market.asset_mint),
+            base_vault: strategy_vault_address(strategy, &market.base_mint),
+            asset_vault: strategy_vault_address(strategy, &market.asset_mint),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        vault::instruction::InitializeStrategyVaults {},
+    )
+}
+
+/// New trader with a profile and an empty strategy at index 0
+async fn new_strategy(harness: &mut Harness) -> (Keypair, Pubkey) {
+    let trader = harness.funded_keypair().await;
+    let strategy = strategy_address(&trader.pubkey(), 0);
+    let initialize_profile = vault_ix(
+        vault::accounts::InitializeTraderProfile {
+            trader: trader.pubkey(),
+            trader_profile: trader_profile_address(&trader.pubkey()),
+            system_program: system_program::ID,
+        },
+        vault::instruction::InitializeTraderProfile {},
+    );
+    let initialize_strategy = vault_ix(
+        vault::accounts::InitializeStrategy {
+            trader: trader.pubkey(),
+            trader_profile: trader_profile_address(&trader.pubkey()),
+            strategy,
+            system_program: system_program::ID,
+        },
+        vault::instruction::InitializeStrategy {
+            name: "Momentum".to_string(),
+            description: "Follows the trend".to_string(),
+            performance_fee_bps: 2_000,
+        },
+    );
+    harness
+        .process(&[initialize_profile, initialize_strategy], &[&trader])
+        .await
+        .unwrap();
+    (trader, strategy)
+}
+
+/// New trader with a strategy on `market` and one subscriber who deposited
+/// `DEPOSIT` base tokens
+async fn subscribed_strategy(harness: &mut Harness, market: &Market) -> (Keypair, Pubkey, Keypair) {
+    let (trader, strategy) = new_strategy(harness).await;
+    let initialize_vaults = initialize_strategy_vaults_ix(&trader, &strategy, market);
+    harness
+        .process(&[initialize_vaults], &[&trader])
+        .await
+        .unwrap();
+    let user = subscribe(harness, market, &strategy, DEPOSIT)
+        .await
+        .unwrap();
+    (trader, strategy, user)
+}
+
+/// New user depositing `deposit` base tokens into `strategy`
+async fn subscribe(
+    harness: &mut Harness,
+    market: &Market,
+    strategy: &Pubkey,
+    deposit: u64,
+) -> Result<Keypair, BanksClientError> {
+    let user = harness.funded_keypair().await;
+    let user_base = harness
+        .spl_token_account(
+            &market.base_mint,
+            &market.authority,
+            &user.pubkey(),
+            deposit,
+        )
+        .await;
+    let subscribe = subscribe_ix(&user, strategy, market, &user_base, deposit);
+    harness.process(&[subscribe], &[&user]).await?;
+    Ok(user)
+}
+
+fn execute_trade_ix(
+    trader: &Keypair,
+    strategy: &Pubkey,
+    market: &Market,
+    side: TradeSide,
+    amount_in: u64,
+) -> Instruction {
+    vault_ix(
+        vault::accounts::ExecuteTrade {
+            trader: trader.pubkey(),
+            strategy: *strategy,
+            base_vault: strategy_vault_address(strategy, &market.base_mint),
+            asset_vault: strategy_vault_address(strategy, &market.asset_mint),
+            venue: venue_address(&market.base_mint, &market.asset_mint),
+            pool: market.pool,
+            pool_base_vault: market.pool_base_vault,
+            pool_asset_vault: market.pool_asset_vault,
+            swap_program: constant_product_amm::ID,
+            token_program: spl_token::ID,
+        },
+        vault::instruction::ExecuteTrade { side, amount_in },
+    )
+}
+
+/// Buy the asset straight from the pool, pushing its price up
+async fn pump_asset_price(harness: &mut Harness, market: &Market, amount_in: u64) {
+    let trader = harness.funded_keypair().await;
+    let source = harness
+        .spl_token_account(
+            &market.base_mint,
+            &market.authority,
+            &trader.pubkey(),
+            amount_in,
+        )
+        .await;
+    let destination = harness
+        .spl_token_account(&market.asset_mint, &market.authority, &trader.pubkey(), 0)
+        .await;
+    let swap = amm_ix(
+        constant_product_amm::accounts::Swap {
+            source,
+            destination,
+            authority: trader.pubkey(),
+            pool: market.pool,
+            pool_source_vault: market.pool_base_vault,
+            pool_destination_vault: market.pool_asset_vault,
+            token_program: spl_token::ID,
+        },
+        constant_product_amm::instruction::Swap {
+            amount_in,
+            minimum_amount_out: 0,
+        },
+    );
+    harness.process(&[swap], &[&trader]).await.unwrap();
+}
+
+fn subscribe_ix(
+    user: &Keypair,
+    strategy: &Pubkey,
+    market: &Market,
+    user_base_account: &Pubkey,
+    deposit: u64,
+) -> Instruction {
+    vault_ix(
+        vault::accounts::SubscribeToStrategy {
+            user: user.pubkey(),
+            strategy: *strategy,
+            venue: venue_address(&market.base_mint, &market.asset_mint),
+            position: position_address(&user.pubkey(), strategy),
+            base_vault: strategy_vault_address(strategy, &market.base_mint),
+            user_base_account: *user_base_account,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        vault::instruction::SubscribeToStrategy {
+            initial_deposit: deposit,
+        },
+    )
+}
+
+fn settle_fees_ix(
+    user: &Keypair,
+    trader: &Pubkey,
+    strategy: &Pubkey,
+    market: &Market,
+    trader_base_account: &Pubkey,
+) -> Instruction {
+    vault_ix(
+        vault::accounts::SettleFees {
+            user: user.pubkey(),
+            trader: *trader,
+            strategy: *strategy,
+            venue: venue_address(&market.base_mint, &market.asset_mint),
+            position: position_address(&user.pubkey(), strategy),
+            base_vault: strategy_vault_address(strategy, &market.base_mint),
+            trader_base_account: *trader_base_account,
+            token_program: spl_token::ID,
+        },
+        vault::instruction::SettleFees {},
+    )
+}
+
+#[tokio::test]
+async fn legacy_strategy_migrates_to_the_next_index() {
+    let mut harness = Harness::new().await;
+    let (trader, legacy_strategy, address) = migrated_strategy(&mut harness).await;
+
+    assert!(!harness.account_exists(&legacy_strategy).await);
+    let migrated = strategy(&mut harness, &address).await;
+    assert_eq!(migrated.trader, trader.pubkey());
+    assert_eq!(migrated.index, 0);
+    assert_eq!(migrated.name, "Legacy Book");
+    assert_eq!(migrated.performance_fee_bps, 1_500);
+    assert_eq!(migrated.total_volume_traded, 42_000);
+    assert_eq!(migrated.total_fees_earned, 700);
+    assert_eq!(migrated.migrated_from, Some(legacy_strategy));
+    // Legacy subscribers are not carried over
+    assert_eq!(migrated.total_subscribers, 0);
+    assert_eq!(migrated.total_shares, 0);
+
+    // The legacy address can't be migrated twice
+    let migrate = vault_ix(
+        vault::accounts::MigrateStrategy {
+            trader: trader.pubkey(),
+            trader_profile: trader_profile_address(&trader.pubkey()),
+            legacy_strategy,
+            strategy: strategy_address(&trader.pubkey(), 1),
+            system_program: system_program::ID,
+        },
+        vault::instruction::MigrateStrategy {},
+    );
+    let result = harness.process(&[migrate], &[&trader]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(VaultError::InvalidLegacyStrategy)
+    );
+}
+
+#[tokio::test]
+async fn migrated_position_is_refunded_and_can_subscribe_again() {
+    let mut harness = Harness::new().await;
+    let (trader, legacy_strategy, address) = migrated_strategy(&mut harness).await;
+    let user = harness.funded_keypair().await;
+    let legacy_position =
+        create_legacy_position(&mut harness, &user.pubkey(), &legacy_strategy).await;
+
+    let legacy_lamports = harness.lamports(&legacy_position).await;
+    let user_lamports = harness.lamports(&user.pubkey()).await;
+    let migrate = migrate_position_ix(&user, &address, &legacy_position);
+    harness.process(&[migrate], &[&user]).await.unwrap();
+
+    // Deposit and rent go back to the user, and no position is created
+    assert!(!harness.account_exists(&legacy_position).await);
+    assert_eq!(
+        harness.lamports(&user.pubkey()).await,
+        user_lamports + legacy_lamports
+    );
+    let position_key = position_address(&user.pubkey(), &address);
+    assert!(!harness.account_exists(&position_key).await);
+    assert_eq!(strategy(&mut harness, &address).await.total_subscribers, 0);
+
+    // The user subscribes to the migrated strategy like anyone else
+    let market = create_market(&mut harness).await;
+    let initialize_vaults = initialize_strategy_vaults_ix(&trader, &address, &market);
+    harness
+        .process(&[initialize_vaults], &[&trader])
+        .await
+        .unwrap();
+    let deposit = 2_000_000;
+    let user_base = harness
+        .spl_token_account(
+            &market.base_mint,
+            &market.authority,
+            &user.pubkey(),
+            deposit,
+        )
+        .await;
+    let subscribe = subscribe_ix(&user, &address, &market, &user_base, deposit);
+    harness.process(&[subscribe], &[&user]).await.unwrap();
+
+    assert_eq!(position(&mut harness, &position_key).await.shares, deposit);
+    let migrated = strategy(&mut harness, &address).await;
+    assert_eq!(migrated.total_subscribers, 1);
+    assert_eq!(migrated.total_shares, deposit);
+}
+
+#[tokio::test]
+async fn only_the_positions_owner_can_migrate_it() {
+    let mut harness = Harness::new().await;
+    let (_, legacy_strategy, address) = migrated_strategy(&mut harness).await;
+    let user = harness.funded_keypair().await;
+    let legacy_position =
+        create_legacy_position(&mut harness, &user.pubkey(), &legacy_strategy).await;
+
+    let outsider = harness.funded_keypair().await;
+    let migrate = migrate_position_ix(&outsider, &address, &legacy_position);
+    let result = harness.process(&[migrate], &[&outsider]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(VaultError::InvalidLegacyPosition)
+    );
+    assert!(harness.account_exists(&legacy_position).await);
+}
+
+#[tokio::test]
+async fn trades_against_a_manipulated_pool_price_are_rejected() {
+    let mut harness = Harness::new().await;
+    let market = create_market(&mut harness).await;
+    let (trader, strategy, _) = subscribed_strategy(&mut harness, &market).await;
+
+    let buy = execute_trade_ix(&trader, &strategy, &market, TradeSide::Buy, 1_000_000);
+    harness.process(&[buy], &[&trader]).await.unwrap();
+
+    // Someone, the trader included, pushes the pool price far from the
+    // reference price before the strategy buys
+    pump_asset_price(&mut harness, &market, POOL_LIQUIDITY / 2).await;
+    let buy = execute_trade_ix(&trader, &strategy, &market, TradeSide::Buy, 1_000_000);
+    let result = harness.process(&[buy], &[&trader]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(AmmError::SlippageExceeded)
+    );
+
+    // The trader can't move the reference price
+    let result = harness
+        .process(
+            &[set_reference_price_ix(&trader, &market, 9, 4)],
+            &[&trader],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(VaultError::UnauthorizedAdmin)
+    );
+
+    // Once the admin accepts the new price, the strategy trades at it
+    let payer = harness.payer();
+    harness
+        .process(&[set_reference_price_ix(&payer, &market, 9, 4)], &[])
+        .await
+        .unwrap();
+    let buy = execute_trade_ix(&trader, &strategy, &market, TradeSide::Buy, 1_000_000);
+    harness.process(&[buy], &[&trader]).await.unwrap();
+}
+
+#[tokio::test]
+async fn trades_need_a_fresh_reference_price() {
+    let mut harness = Harness::new().await;
+    let market = create_market(&mut harness).await;
+    let (trader, strategy, _) = subscribed_strategy(&mut harness, &market).await;
+
+    let mut clock: Clock = harness.context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += 2 * 60 * 60;
+    harness.context.set_sysvar(&clock);
+
+    let buy = execute_trade_ix(&trader, &strategy, &market, TradeSide::Buy, 1_000_000);
+    let result = harness.process(&[buy], &[&trader]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(VaultError::StaleReferencePrice)
+    );
+}
+
+#[tokio::test]
+async fn trades_only_go_through_the_allowlisted_venue() {
+    let mut harness = Harness::new().await;
+    let market = create_market(&mut harness).await;
+    let (trader, strategy, _) = subscribed_strategy(&mut harness, &market).await;
+
+    // Pool vaults swapped around
+    let mut buy = execute_trade_ix(&trader, &strategy, &market, TradeSide::Buy, 1_000_000);
+    buy.accounts[6].pubkey = market.pool_asset_vault;
+    buy.accounts[7].pubkey = market.pool_base_vault;
+    let result = harness.process(&[buy], &[&trader]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(VaultError::InvalidSwapPool)
+    );
+
+    // Only allowlisted pairs get vaults, and only the admin allowlists them
+    let other = create_pool(&mut harness).await;
+    let (other_trader, other_strategy) = new_strategy(&mut harness).await;
+    let initialize_vaults = initialize_strategy_vaults_ix(&other_trader, &other_strategy, &other);
+    let result = harness
+        .process(&[initialize_vaults], &[&other_trader])
+        .await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(anchor_lang::error::ErrorCode::AccountNotInitialized)
+    );
+    let result = harness
+        .process(&[add_swap_venue_ix(&trader, &other)], &[&trader])
+        .await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(VaultError::UnauthorizedAdmin)
+    );
+}
+
+#[tokio::test]
+async fn nav_values_the_asset_at_the_reference_price() {
+    let mut harness = Harness::new().await;
+    let market = create_market(&mut harness).await;
+    let (trader, address, first) = subscribed_strategy(&mut harness, &market).await;
+    let buy = execute_trade_ix(&trader, &address, &market, TradeSide::Buy, DEPOSIT / 2);
+    harness.process(&[buy], &[&trader]).await.unwrap();
+
+    // Pushing the pool price doesn't move NAV: a late subscriber still gets
+    // shares at the 1:1 reference price
+    pump_asset_price(&mut harness, &market, POOL_LIQUIDITY / 2).await;
+    let before = strategy(&mut harness, &address).await;
+    let nav = before.base_balance + before.asset_balance;
+    let late = subscribe(&mut harness, &market, &address, DEPOSIT)
+        .await
+        .unwrap();
+    let late_position = position_address(&late.pubkey(), &address);
+    assert_eq!(
+        u128::from(position(&mut harness, &late_position).await.shares),
+        u128::from(DEPOSIT) * u128::from(before.total_shares) / u128::from(nav)
+    );
+
+    // Once the admin prices the asset at 2 base, fees are charged on the
+    // position's growth at that price and paid from the pool's base tokens
+    let payer = harness.payer();
+    harness
+        .process(&[set_reference_price_ix(&payer, &market, 2, 1)], &[])
+        .await
+        .unwrap();
+    let before = strategy(&mut harness, &address).await;
+    let first_position = position_address(&first.pubkey(), &address);
+    let shares = position(&mut harness, &first_position).await.shares;
+    let nav = before.base_balance + 2 * before.asset_balance;
+    let value = (u128::from(shares) * u128::from(nav) / u128::from(before.total_shares)) as u64;
+    let fee = ((value - DEPOSIT) * 2_000 / 10_000).min(DEPOSIT / 10);
+
+    let trader_base = harness
+        .spl_token_account(&market.base_mint, &market.authority, &trader.pubkey(), 0)
+        .await;
+    let settle = settle_fees_ix(&first, &trader.pubkey(), &address, &market, &trader_base);
+    harness.process(&[settle], &[&first]).await.unwrap();
+
+    assert_eq!(harness.token_amount(&trader_base).await, fee);
+    let settled = position(&mut harness, &first_position).await;
+    assert_eq!(settled.total_fees_paid, fee);
+    assert_eq!(settled.initial_balance, value - fee);
+    let after = strategy(&mut harness, &address).await;
+    assert_eq!(after.base_balance, before.base_balance - fee);
+    assert_eq!(after.asset_balance, before.asset_balance);
+    assert_eq!(
+        after.total_shares,
+        before.total_shares - (shares - settled.shares)
+    );
+}
+
+#[tokio::test]
+async fn nav_needs_a_fresh_reference_price_while_the_pool_holds_the_asset() {
+    let mut harness = Harness::new().await;
+    let market = create_market(&mut harness).await;
+    let (trader, address, first) = subscribed_strategy(&mut harness, &market).await;
+    let payer = harness.payer();
+
+    // A flat pool is worth its base balance, whatever the price's age
+    let mut clock: Clock = harness.context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += 2 * 60 * 60;
+    harness.context.set_sysvar(&clock);
+    subscribe(&mut harness, &market, &address, DEPOSIT)
+        .await
+        .unwrap();
+
+    harness
+        .process(&[set_reference_price_ix(&payer, &market, 1, 1)], &[])
+        .await
+        .unwrap();
+    let buy = execute_trade_ix(&trader, &address, &market, TradeSide::Buy, DEPOSIT / 2);
+    harness.process(&[buy], &[&trader]).await.unwrap();
+
+    clock.unix_timestamp += 2 * 60 * 60;
+    harness.context.set_sysvar(&clock);
+    let result = subscribe(&mut harness, &market, &address, DEPOSIT)
+        .await
+        .map(|_| ());
+    assert_eq!(
+        custom_error(result),
+        anchor_error(VaultError::StaleReferencePrice)
+    );
+    let trader_base = harness
+        .spl_token_account(&market.base_mint, &market.authority, &trader.pubkey(), 0)
+        .await;
+    let settle = settle_fees_ix(&first, &trader.pubkey(), &address, &market, &trader_base);
+    let result = harness.process(&[settle], &[&first]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(VaultError::StaleReferencePrice)
+    );
+}
@@ -0,0 +1,176 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::AccountDeserialize;
+use common::{anchor_error, custom_error, ConfidentialAccount, Harness};
+use solana_program_test::{tokio, BanksClientError};
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+use spectre_client::instructions;
+use spectre_client::proofs;
+use vault::{Invoice, PrivacyError};
+
+const AMOUNT: u64 = 300;
+const FUNDED: u64 = 1_000;
+const LIFETIME: i64 = 3_600;
+
+struct TestInvoice {
+    payee: ConfidentialAccount,
+    payer: ConfidentialAccount,
+    address: Pubkey,
+    state: Invoice,
+}
+
+/// Invoice from a fresh payee to a payer funded with `FUNDED`, for `AMOUNT`
+/// and expiring in `LIFETIME` seconds
+async fn create_invoice(harness: &mut Harness) -> TestInvoice {
+    let wrapped = harness.create_wrapped_mint(None).await;
+    let payee = harness.create_confidential_account(&wrapped.mint).await;
+    let payer = harness.create_confidential_account(&wrapped.mint).await;
+    harness.fund(&wrapped, &payer, FUNDED).await;
+
+    let expires_at = harness.unix_timestamp().await + LIFETIME;
+    let create = instructions::create_invoice(
+        &payee.owner.pubkey(),
+        &payee.address,
+        &payer.address,
+        &payer.elgamal_pubkey(),
+        &wrapped.mint,
+        1,
+        AMOUNT,
+        expires_at,
+        None,
+    )
+    .unwrap();
+    harness.process(&[create], &[&payee.owner]).await.unwrap();
+
+    let address = instructions::invoice_address(&payee.owner.pubkey(), 1);
+    let data = harness.account_data(&address).await;
+    TestInvoice {
+        payee,
+        payer,
+        address,
+        state: Invoice::try_deserialize(&mut data.as_slice()).unwrap(),
+    }
+}
+
+/// Pay `amount` against `state`, which the proofs are generated from
+async fn pay(
+    harness: &mut Harness,
+    invoice: &TestInvoice,
+    state: &Invoice,
+    amount: u64,
+) -> Result<(), BanksClientError> {
+    let payer = &invoice.payer;
+    let payer_state = harness.confidential_extension(&payer.address).await;
+    let rent = harness.context.banks_client.get_rent().await.unwrap();
+    let payment = instructions::pay_invoice(
+        &payer.owner.pubkey(),
+        &payer.address,
+        &payer_state,
+        &invoice.address,
+        state,
+        &invoice.payee.elgamal_pubkey(),
+        None,
+        &payer.keys(),
+        amount,
+        &rent,
+    )
+    .unwrap();
+    harness
+        .process_with_proofs(
+            &payment.transactions(),
+            &[
+                &payer.owner,
+                &payment.equality_proof.keypair,
+                &payment.ciphertext_validity_proof.keypair,
+                &payment.range_proof.keypair,
+            ],
+        )
+        .await
+}
+
+/// Applies the pending balance first
+async fn balance(harness: &mut Harness, account: &ConfidentialAccount) -> u64 {
+    harness.apply_pending_balance(account).await;
+    harness.available_balance(account).await
+}
+
+#[tokio::test]
+async fn exact_payment_settles_the_invoice_once() {
+    let mut harness = Harness::new().await;
+    let invoice = create_invoice(&mut harness).await;
+
+    let payee = invoice.payee.owner.pubkey();
+    let rent = harness.lamports(&invoice.address).await;
+    let payee_lamports = harness.lamports(&payee).await;
+    pay(&mut harness, &invoice, &invoice.state, AMOUNT)
+        .await
+        .unwrap();
+    assert_eq!(balance(&mut harness, &invoice.payee).await, AMOUNT);
+    assert_eq!(balance(&mut harness, &invoice.payer).await, FUNDED - AMOUNT);
+
+    // Settled invoices are closed, their rent back to the payee
+    assert!(!harness.account_exists(&invoice.address).await);
+    assert_eq!(harness.lamports(&payee).await, payee_lamports + rent);
+
+    let result = pay(&mut harness, &invoice, &invoice.state, AMOUNT).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(ErrorCode::AccountNotInitialized)
+    );
+    assert_eq!(balance(&mut harness, &invoice.payee).await, AMOUNT);
+}
+
+#[tokio::test]
+async fn payment_of_another_amount_is_rejected() {
+    let mut harness = Harness::new().await;
+    let invoice = create_invoice(&mut harness).await;
+
+    // The client refuses to pay a different amount, so prove against an
+    // invoice for the underpayment to get it on chain
+    let underpaid = AMOUNT - 1;
+    let mut state = invoice.state.clone();
+    state.amount_ciphertext =
+        proofs::encrypt_invoice_amount(underpaid, &invoice.payer.elgamal_pubkey()).unwrap();
+    let result = pay(&mut harness, &invoice, &state, underpaid).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::InvoiceAmountMismatch)
+    );
+    assert_eq!(balance(&mut harness, &invoice.payee).await, 0);
+    assert_eq!(balance(&mut harness, &invoice.payer).await, FUNDED);
+    assert!(harness.account_exists(&invoice.address).await);
+
+    pay(&mut harness, &invoice, &invoice.state, AMOUNT)
+        .await
+        .unwrap();
+    assert_eq!(balance(&mut harness, &invoice.payee).await, AMOUNT);
+}
+
+#[tokio::test]
+async fn only_expired_invoices_can_be_closed() {
+    let mut harness = Harness::new().await;
+    let invoice = create_invoice(&mut harness).await;
+    let close = instructions::close_expired_invoice(&invoice.address, &invoice.state);
+
+    let result = harness.process(std::slice::from_ref(&close), &[]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::InvoiceNotExpired)
+    );
+
+    harness.warp_to(invoice.state.expires_at + 1).await;
+    let result = pay(&mut harness, &invoice, &invoice.state, AMOUNT).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::InvoiceExpired)
+    );
+
+    // Anyone can close it once expired, the rent goes to the payee
+    let payee = invoice.payee.owner.pubkey();
+    let rent = harness.lamports(&invoice.address).await;
+    let payee_lamports = harness.lamports(&payee).await;
+    harness.process(&[close], &[]).await.unwrap();
+    assert!(!harness.account_exists(&invoice.address).await);
+    assert_eq!(harness.lamports(&payee).await, payee_lamports + rent);
+}
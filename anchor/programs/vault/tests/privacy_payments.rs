@@ -0,0 +1,249 @@
+mod common;
+
+use anchor_lang::InstructionData;
+use common::{anchor_error, custom_error, pda, vault_ix, Harness};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer, system_program,
+};
+use spectre_client::instructions::credit_policy_address;
+use spl_token_2022::error::TokenError;
+use spl_token_2022::extension::confidential_transfer::instruction::{
+    disable_non_confidential_credits, enable_non_confidential_credits,
+};
+use vault::{
+    PrivacyError, APPROVAL_REQUEST_SEED, AUDIT_CONFIG_SEED, CONFIDENTIAL_AUTHORITY_SEED,
+    FREEZE_AUTHORITY_SEED,
+};
+
+/// freeze_confidential_account or thaw_confidential_account signed by
+/// `compliance_authority`
+fn compliance_ix(
+    compliance_authority: &Keypair,
+    mint: &Pubkey,
+    account: &Pubkey,
+    data: impl InstructionData,
+) -> Instruction {
+    vault_ix(
+        vault::accounts::ComplianceAction {
+            compliance_authority: compliance_authority.pubkey(),
+            confidential_account: *account,
+            mint: *mint,
+            audit_config: pda(&[AUDIT_CONFIG_SEED, mint.as_ref()]),
+            freeze_authority: pda(&[FREEZE_AUTHORITY_SEED, mint.as_ref()]),
+            token_program: spl_token_2022::ID,
+        },
+        data,
+    )
+}
+
+fn freeze_ix(compliance_authority: &Keypair, mint: &Pubkey, account: &Pubkey) -> Instruction {
+    let data = vault::instruction::FreezeConfidentialAccount { reason_code: 1 };
+    compliance_ix(compliance_authority, mint, account, data)
+}
+
+fn thaw_ix(compliance_authority: &Keypair, mint: &Pubkey, account: &Pubkey) -> Instruction {
+    let data = vault::instruction::ThawConfidentialAccount { reason_code: 1 };
+    compliance_ix(compliance_authority, mint, account, data)
+}
+
+#[tokio::test]
+async fn only_the_compliance_authority_can_freeze() {
+    let mut harness = Harness::new().await;
+    let mint = harness.create_confidential_mint(true, None).await;
+    let user = harness.create_confidential_account(&mint).await;
+
+    // Not even the account owner
+    let freeze = freeze_ix(&user.owner, &mint, &user.address);
+    let result = harness.process(&[freeze], &[&user.owner]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::UnauthorizedComplianceAuthority)
+    );
+    assert!(!harness.is_frozen(&user.address).await);
+
+    let payer = harness.payer();
+    let freeze = freeze_ix(&payer, &mint, &user.address);
+    harness.process(&[freeze], &[]).await.unwrap();
+    assert!(harness.is_frozen(&user.address).await);
+}
+
+#[tokio::test]
+async fn frozen_accounts_reject_transfers() {
+    let mut harness = Harness::new().await;
+    let wrapped = harness.create_wrapped_mint(None).await;
+    let sender = harness.create_confidential_account(&wrapped.mint).await;
+    let recipient = harness.create_confidential_account(&wrapped.mint).await;
+    harness.fund(&wrapped, &sender, 1_000).await;
+    let transfer = harness
+        .transfer_ix(&wrapped.mint, &sender, &recipient, 100)
+        .await;
+
+    let payer = harness.payer();
+    let freeze = freeze_ix(&payer, &wrapped.mint, &sender.address);
+    harness.process(&[freeze], &[]).await.unwrap();
+    assert!(harness.is_frozen(&sender.address).await);
+
+    let result = harness
+        .process(std::slice::from_ref(&transfer), &[&sender.owner])
+        .await;
+    assert_eq!(custom_error(result), Some(TokenError::AccountFrozen as u32));
+    assert_eq!(harness.available_balance(&sender).await, 1_000);
+
+    // A frozen recipient can't be paid either
+    let freeze = freeze_ix(&payer, &wrapped.mint, &recipient.address);
+    let thaw = thaw_ix(&payer, &wrapped.mint, &sender.address);
+    harness.process(&[freeze, thaw], &[]).await.unwrap();
+    let result = harness
+        .process(std::slice::from_ref(&transfer), &[&sender.owner])
+        .await;
+    assert_eq!(custom_error(result), Some(TokenError::AccountFrozen as u32));
+    assert_eq!(harness.available_balance(&sender).await, 1_000);
+
+    // The same transfer goes through once both accounts are thawed
+    let thaw = thaw_ix(&payer, &wrapped.mint, &recipient.address);
+    harness
+        .process(&[thaw, transfer], &[&sender.owner])
+        .await
+        .unwrap();
+    assert_eq!(harness.available_balance(&sender).await, 900);
+}
+
+#[tokio::test]
+async fn only_a_pending_account_can_be_approved() {
+    let mut harness = Harness::new().await;
+    let mint = harness.create_confidential_mint(false, None).await;
+    let user = harness.create_confidential_account(&mint).await;
+    let approval_request = pda(&[APPROVAL_REQUEST_SEED, mint.as_ref(), user.address.as_ref()]);
+
+    let payer = harness.payer();
+    let approve = vault_ix(
+        vault::accounts::ReviewAccountApproval {
+            kyc_authority: payer.pubkey(),
+            confidential_account: user.address,
+            mint,
+            audit_config: pda(&[AUDIT_CONFIG_SEED, mint.as_ref()]),
+            approval_request,
+            owner: user.owner.pubkey(),
+            confidential_authority: pda(&[CONFIDENTIAL_AUTHORITY_SEED, mint.as_ref()]),
+            token_program: spl_token_2022::ID,
+        },
+        vault::instruction::ApproveConfidentialAccount {},
+    );
+
+    // No request yet
+    let result = harness.process(std::slice::from_ref(&approve), &[]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(anchor_lang::error::ErrorCode::AccountNotInitialized)
+    );
+    assert!(!bool::from(
+        harness.confidential_extension(&user.address).await.approved
+    ));
+
+    let request = vault_ix(
+        vault::accounts::RequestAccountApproval {
+            owner: user.owner.pubkey(),
+            confidential_account: user.address,
+            mint,
+            approval_request,
+            token_program: spl_token_2022::ID,
+            system_program: system_program::ID,
+        },
+        vault::instruction::RequestAccountApproval {},
+    );
+    harness.process(&[request], &[&user.owner]).await.unwrap();
+    harness
+        .process(std::slice::from_ref(&approve), &[])
+        .await
+        .unwrap();
+    assert!(bool::from(
+        harness.confidential_extension(&user.address).await.approved
+    ));
+    assert!(!harness.account_exists(&approval_request).await);
+
+    // The request was closed on approval, so it can't be approved twice
+    let result = harness.process(&[approve], &[]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(anchor_lang::error::ErrorCode::AccountNotInitialized)
+    );
+}
+
+#[tokio::test]
+async fn confidential_only_accounts_refuse_public_credits() {
+    let mut harness = Harness::new().await;
+    let wrapped = harness.create_wrapped_mint(None).await;
+    let mint = wrapped.mint;
+    let sender = harness.create_confidential_account(&mint).await;
+    let locked = harness.create_confidential_account(&mint).await;
+    harness.fund(&wrapped, &sender, 1_000).await;
+
+    let payer = harness.payer();
+    let enforce = vault_ix(
+        vault::accounts::EnforceConfidentialOnly {
+            payer: payer.pubkey(),
+            compliance_authority: payer.pubkey(),
+            owner: locked.owner.pubkey(),
+            confidential_account: locked.address,
+            mint,
+            audit_config: pda(&[AUDIT_CONFIG_SEED, mint.as_ref()]),
+            credit_policy: credit_policy_address(&locked.address),
+            token_program: spl_token_2022::ID,
+            system_program: system_program::ID,
+        },
+        vault::instruction::EnforceConfidentialOnly {},
+    );
+    harness.process(&[enforce], &[&locked.owner]).await.unwrap();
+
+    // Wrapping mints a public amount into the account
+    let underlying_account = harness
+        .underlying_account(&wrapped, &locked.owner.pubkey(), 100)
+        .await;
+    let wrap = harness.wrap_ix(&wrapped, &locked, &underlying_account, 100);
+    let result = harness.process(&[wrap], &[&locked.owner]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::ConfidentialOnlyAccount)
+    );
+
+    let transfer = harness.transfer_ix(&mint, &sender, &locked, 100).await;
+    harness
+        .process(&[transfer], &[&sender.owner])
+        .await
+        .unwrap();
+    harness.apply_pending_balance(&locked).await;
+    assert_eq!(harness.available_balance(&locked).await, 100);
+
+    // The owner turns public credits back on behind the program's back
+    let enable = enable_non_confidential_credits(
+        &spl_token_2022::ID,
+        &locked.address,
+        &locked.owner.pubkey(),
+        &[],
+    )
+    .unwrap();
+    harness.process(&[enable], &[&locked.owner]).await.unwrap();
+    let transfer = harness.transfer_ix(&mint, &locked, &sender, 40).await;
+    let result = harness
+        .process(std::slice::from_ref(&transfer), &[&locked.owner])
+        .await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::PublicCreditsEnabled)
+    );
+    assert_eq!(harness.available_balance(&locked).await, 100);
+
+    let disable = disable_non_confidential_credits(
+        &spl_token_2022::ID,
+        &locked.address,
+        &locked.owner.pubkey(),
+        &[],
+    )
+    .unwrap();
+    harness
+        .process(&[disable, transfer], &[&locked.owner])
+        .await
+        .unwrap();
+    assert_eq!(harness.available_balance(&locked).await, 60);
+}
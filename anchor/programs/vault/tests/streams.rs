@@ -0,0 +1,248 @@
+mod common;
+
+use anchor_lang::AccountDeserialize;
+use common::{anchor_error, custom_error, ConfidentialAccount, Harness};
+use solana_program_test::{tokio, BanksClientError};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+use spectre_client::instructions::{self, StreamInstructions};
+use spectre_client::stream::StreamSecret;
+use vault::{PrivacyError, Stream, StreamSchedule};
+
+const TOTAL: u64 = 1_000;
+const PERIODS: u16 = 4;
+const PERIOD: i64 = 100;
+
+struct TestStream {
+    sender: ConfidentialAccount,
+    recipient: ConfidentialAccount,
+    address: Pubkey,
+    escrow: Pubkey,
+    secret: StreamSecret,
+    start_time: i64,
+}
+
+async fn stream_state(harness: &mut Harness, address: &Pubkey) -> Stream {
+    let data = harness.account_data(address).await;
+    Stream::try_deserialize(&mut data.as_slice()).unwrap()
+}
+
+/// Land the proofs, then the stream instruction, then close the proof
+/// accounts. Returns the result of the stream instruction.
+async fn send(
+    harness: &mut Harness,
+    instructions: StreamInstructions,
+    authority: &Keypair,
+) -> Result<(), BanksClientError> {
+    let mut candidates = vec![
+        authority,
+        &instructions.equality_proof.keypair,
+        &instructions.ciphertext_validity_proof.keypair,
+        &instructions.range_proof.keypair,
+    ];
+    if let Some((equality_proof, range_proof)) = &instructions.allowance_proofs {
+        candidates.extend([&equality_proof.keypair, &range_proof.keypair]);
+    }
+    harness
+        .process_with_proofs(&instructions.transactions(), &candidates)
+        .await
+}
+
+/// Stream of `TOTAL` tokens over `PERIODS` periods of `PERIOD` seconds,
+/// starting now with no cliff
+async fn create_stream(harness: &mut Harness) -> TestStream {
+    let wrapped = harness.create_wrapped_mint(None).await;
+    let sender = harness.create_confidential_account(&wrapped.mint).await;
+    let recipient = harness.create_confidential_account(&wrapped.mint).await;
+    harness.fund(&wrapped, &sender, TOTAL).await;
+
+    let start_time = harness.unix_timestamp().await;
+    let schedule = StreamSchedule {
+        stream_id: 1,
+        start_time,
+        cliff_time: start_time,
+        end_time: start_time + PERIOD * i64::from(PERIODS),
+        periods: PERIODS,
+    };
+    let secret = StreamSecret::new(TOTAL);
+    let rent = harness.context.banks_client.get_rent().await.unwrap();
+    let sender_state = harness.confidential_extension(&sender.address).await;
+    let create = instructions::create_stream(
+        &sender.owner.pubkey(),
+        &sender.address,
+        &sender_state,
+        &recipient.owner.pubkey(),
+        &recipient.elgamal_pubkey(),
+        &wrapped.mint,
+        None,
+        &sender.keys(),
+        schedule,
+        &secret,
+        &rent,
+    )
+    .unwrap();
+    send(harness, create, &sender.owner).await.unwrap();
+
+    let (address, escrow) = instructions::stream_addresses(&sender.owner.pubkey(), 1);
+    TestStream {
+        sender,
+        recipient,
+        address,
+        escrow,
+        secret,
+        start_time,
+    }
+}
+
+/// Claim `amount`, proven against `vested_periods`
+async fn claim(
+    harness: &mut Harness,
+    stream: &TestStream,
+    vested_periods: u16,
+    amount: u64,
+) -> Result<(), BanksClientError> {
+    let state = stream_state(harness, &stream.address).await;
+    let escrow_state = harness.confidential_extension(&stream.escrow).await;
+    let rent = harness.context.banks_client.get_rent().await.unwrap();
+    let withdraw = instructions::withdraw_from_stream(
+        &stream.recipient.owner.pubkey(),
+        &stream.recipient.address,
+        &stream.recipient.elgamal_pubkey(),
+        &stream.address,
+        &state,
+        &escrow_state,
+        &stream.secret,
+        None,
+        vested_periods,
+        amount,
+        &rent,
+    )
+    .unwrap();
+    send(harness, withdraw, &stream.recipient.owner).await
+}
+
+/// Cancel with a refund of `amount`, proven against `vested_periods`
+async fn cancel(
+    harness: &mut Harness,
+    stream: &TestStream,
+    vested_periods: u16,
+    amount: u64,
+) -> Result<(), BanksClientError> {
+    let state = stream_state(harness, &stream.address).await;
+    let escrow_state = harness.confidential_extension(&stream.escrow).await;
+    let rent = harness.context.banks_client.get_rent().await.unwrap();
+    let cancel = instructions::cancel_stream(
+        &stream.sender.owner.pubkey(),
+        &stream.sender.address,
+        &stream.sender.elgamal_pubkey(),
+        &stream.address,
+        &state,
+        &escrow_state,
+        &stream.secret,
+        None,
+        vested_periods,
+        amount,
+        &rent,
+    )
+    .unwrap();
+    send(harness, cancel, &stream.sender.owner).await
+}
+
+async fn close_stream(harness: &mut Harness, stream: &TestStream) -> Result<(), BanksClientError> {
+    let state = stream_state(harness, &stream.address).await;
+    let escrow_state = harness.confidential_extension(&stream.escrow).await;
+    let close =
+        instructions::close_stream(&stream.address, &state, &escrow_state, &stream.secret).unwrap();
+    harness.process(&close, &[&stream.sender.owner]).await
+}
+
+/// Applies the pending balance first
+async fn balance(harness: &mut Harness, account: &ConfidentialAccount) -> u64 {
+    harness.apply_pending_balance(account).await;
+    harness.available_balance(account).await
+}
+
+#[tokio::test]
+async fn claims_are_limited_to_the_vested_share() {
+    let mut harness = Harness::new().await;
+    let stream = create_stream(&mut harness).await;
+    harness.warp_to(stream.start_time + 2 * PERIOD).await;
+
+    // Proven as if three periods had vested, but only two have
+    let result = claim(&mut harness, &stream, 3, 750).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::StreamAllowanceExceeded)
+    );
+    assert_eq!(balance(&mut harness, &stream.recipient).await, 0);
+
+    // Exactly the vested amount
+    claim(&mut harness, &stream, 2, 500).await.unwrap();
+    assert_eq!(balance(&mut harness, &stream.recipient).await, 500);
+
+    // Still vesting, so the sender can't close it yet
+    let result = close_stream(&mut harness, &stream).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::StreamNotSettled)
+    );
+
+    harness
+        .warp_to(stream.start_time + i64::from(PERIODS) * PERIOD)
+        .await;
+    claim(&mut harness, &stream, PERIODS, 500).await.unwrap();
+    assert_eq!(balance(&mut harness, &stream.recipient).await, TOTAL);
+
+    // Fully claimed: both accounts close and their rent goes to the sender
+    let sender = stream.sender.owner.pubkey();
+    let rent = harness.lamports(&stream.address).await + harness.lamports(&stream.escrow).await;
+    let sender_lamports = harness.lamports(&sender).await;
+    close_stream(&mut harness, &stream).await.unwrap();
+    assert!(!harness.account_exists(&stream.address).await);
+    assert!(!harness.account_exists(&stream.escrow).await);
+    assert_eq!(harness.lamports(&sender).await, sender_lamports + rent);
+}
+
+#[tokio::test]
+async fn cancel_refunds_the_unvested_share() {
+    let mut harness = Harness::new().await;
+    let stream = create_stream(&mut harness).await;
+    harness.warp_to(stream.start_time + PERIOD).await;
+
+    cancel(&mut harness, &stream, 1, 750).await.unwrap();
+    assert_eq!(balance(&mut harness, &stream.sender).await, 750);
+
+    // The recipient keeps the period that vested before the cancel
+    harness
+        .warp_to(stream.start_time + i64::from(PERIODS) * PERIOD)
+        .await;
+    claim(&mut harness, &stream, 1, 250).await.unwrap();
+    assert_eq!(balance(&mut harness, &stream.recipient).await, 250);
+
+    close_stream(&mut harness, &stream).await.unwrap();
+    assert!(!harness.account_exists(&stream.address).await);
+    assert!(!harness.account_exists(&stream.escrow).await);
+}
+
+#[tokio::test]
+async fn claims_after_a_cancel_stop_at_the_vested_share() {
+    let mut harness = Harness::new().await;
+    let stream = create_stream(&mut harness).await;
+    harness.warp_to(stream.start_time + PERIOD).await;
+
+    // Refunding less than allowed leaves enough in the escrow to cover an
+    // over-claim, so only the allowance check can stop it
+    cancel(&mut harness, &stream, 1, 500).await.unwrap();
+
+    harness
+        .warp_to(stream.start_time + i64::from(PERIODS) * PERIOD)
+        .await;
+    let result = claim(&mut harness, &stream, 2, 500).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::StreamAllowanceExceeded)
+    );
+    assert_eq!(balance(&mut harness, &stream.recipient).await, 0);
+
+    claim(&mut harness, &stream, 1, 250).await.unwrap();
+    assert_eq!(balance(&mut harness, &stream.recipient).await, 250);
+}
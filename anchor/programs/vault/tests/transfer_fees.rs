@@ -0,0 +1,207 @@
+mod common;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use common::{anchor_error, custom_error, pda, vault_ix, ConfidentialAccount, Harness, DECIMALS};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+};
+use spl_token_2022::extension::transfer_fee::TransferFeeAmount;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::solana_zk_token_sdk::encryption::elgamal::ElGamalKeypair;
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::pod;
+use vault::{PrivacyError, TransferFeeParams, AUDIT_CONFIG_SEED, CONFIDENTIAL_AUTHORITY_SEED};
+
+const TRANSFER_FEE_BASIS_POINTS: u16 = 100;
+const MAXIMUM_FEE: u64 = 1_000_000;
+
+fn transfer_fee() -> TransferFeeParams {
+    let withdraw_withheld_authority_elgamal_pubkey: pod::ElGamalPubkey =
+        (*ElGamalKeypair::new_rand().pubkey()).into();
+    TransferFeeParams {
+        transfer_fee_basis_points: TRANSFER_FEE_BASIS_POINTS,
+        maximum_fee: MAXIMUM_FEE,
+        withdraw_withheld_authority_elgamal_pubkey: withdraw_withheld_authority_elgamal_pubkey.0,
+    }
+}
+
+async fn withheld_amount(harness: &mut Harness, address: &Pubkey) -> u64 {
+    let data = harness.account_data(address).await;
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)
+        .unwrap()
+        .get_extension::<TransferFeeAmount>()
+        .unwrap()
+        .withheld_amount
+        .into()
+}
+
+/// Public Token-2022 transfer, which withholds the mint's transfer fee in the
+/// recipient account
+fn public_transfer_ix(
+    mint: &Pubkey,
+    sender: &ConfidentialAccount,
+    recipient: &ConfidentialAccount,
+    amount: u64,
+) -> Instruction {
+    spl_token_2022::instruction::transfer_checked(
+        &spl_token_2022::ID,
+        &sender.address,
+        mint,
+        &recipient.address,
+        &sender.owner.pubkey(),
+        &[],
+        amount,
+        DECIMALS,
+    )
+    .unwrap()
+}
+
+fn set_fee_treasury_ix(authority: &Keypair, mint: &Pubkey, treasury: &Pubkey) -> Instruction {
+    vault_ix(
+        vault::accounts::SetFeeTreasury {
+            authority: authority.pubkey(),
+            audit_config: pda(&[AUDIT_CONFIG_SEED, mint.as_ref()]),
+            mint: *mint,
+            treasury_account: *treasury,
+            token_program: spl_token_2022::ID,
+        },
+        vault::instruction::SetFeeTreasury {},
+    )
+}
+
+fn withdraw_withheld_fees_ix(
+    authority: &Keypair,
+    mint: &Pubkey,
+    treasury: &Pubkey,
+    sources: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vault::accounts::WithdrawWithheldFees {
+        authority: authority.pubkey(),
+        audit_config: pda(&[AUDIT_CONFIG_SEED, mint.as_ref()]),
+        mint: *mint,
+        treasury_account: *treasury,
+        confidential_authority: pda(&[CONFIDENTIAL_AUTHORITY_SEED, mint.as_ref()]),
+        token_program: spl_token_2022::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(
+        sources
+            .iter()
+            .map(|source| AccountMeta::new(*source, false)),
+    );
+    Instruction {
+        program_id: vault::ID,
+        accounts,
+        data: vault::instruction::WithdrawWithheldFeesFromAccounts {}.data(),
+    }
+}
+
+#[tokio::test]
+async fn withheld_fees_are_only_paid_to_the_fee_treasury() {
+    let mut harness = Harness::new().await;
+    let wrapped = harness.create_wrapped_mint(Some(transfer_fee())).await;
+    let mint = wrapped.mint;
+    let sender = harness.create_confidential_account(&mint).await;
+    let recipient = harness.create_confidential_account(&mint).await;
+    let treasury = harness.create_confidential_account(&mint).await;
+    harness.fund(&wrapped, &sender, 10_000).await;
+    harness.withdraw_confidential(&mint, &sender, 10_000).await;
+
+    let transfer = public_transfer_ix(&mint, &sender, &recipient, 10_000);
+    harness
+        .process(&[transfer], &[&sender.owner])
+        .await
+        .unwrap();
+    assert_eq!(harness.token_amount(&recipient.address).await, 9_900);
+    assert_eq!(withheld_amount(&mut harness, &recipient.address).await, 100);
+
+    // No treasury recorded yet
+    let payer = harness.payer();
+    let withdraw =
+        withdraw_withheld_fees_ix(&payer, &mint, &treasury.address, &[recipient.address]);
+    let result = harness.process(&[withdraw], &[]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::InvalidFeeTreasury)
+    );
+
+    // Only the audit authority can record it
+    let set_treasury = set_fee_treasury_ix(&sender.owner, &mint, &sender.address);
+    let result = harness.process(&[set_treasury], &[&sender.owner]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::UnauthorizedAuditor)
+    );
+    let set_treasury = set_fee_treasury_ix(&payer, &mint, &treasury.address);
+    harness.process(&[set_treasury], &[]).await.unwrap();
+
+    // Any other destination is rejected
+    let withdraw = withdraw_withheld_fees_ix(&payer, &mint, &sender.address, &[recipient.address]);
+    let result = harness.process(&[withdraw], &[]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::InvalidFeeTreasury)
+    );
+
+    // And so is anyone but the audit authority
+    let withdraw = withdraw_withheld_fees_ix(
+        &sender.owner,
+        &mint,
+        &treasury.address,
+        &[recipient.address],
+    );
+    let result = harness.process(&[withdraw], &[&sender.owner]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::UnauthorizedAuditor)
+    );
+    assert_eq!(withheld_amount(&mut harness, &recipient.address).await, 100);
+
+    let withdraw =
+        withdraw_withheld_fees_ix(&payer, &mint, &treasury.address, &[recipient.address]);
+    harness.process(&[withdraw], &[]).await.unwrap();
+    assert_eq!(withheld_amount(&mut harness, &recipient.address).await, 0);
+    assert_eq!(harness.token_amount(&treasury.address).await, 100);
+}
+
+#[tokio::test]
+async fn only_the_audit_authority_can_change_the_transfer_fee() {
+    let mut harness = Harness::new().await;
+    let mint = harness
+        .create_confidential_mint(true, Some(transfer_fee()))
+        .await;
+    let outsider = harness.funded_keypair().await;
+
+    let set_transfer_fee = |authority: &Keypair| {
+        vault_ix(
+            vault::accounts::SetTransferFee {
+                authority: authority.pubkey(),
+                audit_config: pda(&[AUDIT_CONFIG_SEED, mint.as_ref()]),
+                mint,
+                confidential_authority: pda(&[CONFIDENTIAL_AUTHORITY_SEED, mint.as_ref()]),
+                token_program: spl_token_2022::ID,
+            },
+            vault::instruction::SetTransferFee {
+                transfer_fee_basis_points: 50,
+                maximum_fee: MAXIMUM_FEE,
+            },
+        )
+    };
+
+    let result = harness
+        .process(&[set_transfer_fee(&outsider)], &[&outsider])
+        .await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::UnauthorizedAuditor)
+    );
+
+    let payer = harness.payer();
+    harness
+        .process(&[set_transfer_fee(&payer)], &[])
+        .await
+        .unwrap();
+}
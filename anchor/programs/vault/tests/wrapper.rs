@@ -0,0 +1,51 @@
+mod common;
+
+use common::{anchor_error, custom_error, Harness};
+use solana_program_test::tokio;
+use vault::PrivacyError;
+
+#[tokio::test]
+async fn wrap_then_unwrap_keeps_reserve_backing_supply() {
+    let mut harness = Harness::new().await;
+    let wrapped = harness.create_wrapped_mint(None).await;
+    let user = harness.create_confidential_account(&wrapped.mint).await;
+
+    let underlying_account = harness.fund(&wrapped, &user, 1_000).await;
+    assert_eq!(harness.available_balance(&user).await, 1_000);
+    assert_eq!(harness.token_amount(&wrapped.reserve).await, 1_000);
+    assert_eq!(harness.mint_supply(&wrapped.mint).await, 1_000);
+
+    harness
+        .withdraw_confidential(&wrapped.mint, &user, 400)
+        .await;
+    let unwrap = harness.unwrap_ix(&wrapped, &user, &underlying_account, 400);
+    harness.process(&[unwrap], &[&user.owner]).await.unwrap();
+
+    let reserve = harness.token_amount(&wrapped.reserve).await;
+    let supply = harness.mint_supply(&wrapped.mint).await;
+    assert_eq!((reserve, supply), (600, 600));
+    assert!(reserve >= supply);
+    assert_eq!(harness.token_amount(&underlying_account).await, 400);
+    assert_eq!(harness.available_balance(&user).await, 600);
+}
+
+#[tokio::test]
+async fn unwrap_beyond_supply_fails() {
+    let mut harness = Harness::new().await;
+    let wrapped = harness.create_wrapped_mint(None).await;
+    let user = harness.create_confidential_account(&wrapped.mint).await;
+
+    let underlying_account = harness.fund(&wrapped, &user, 500).await;
+    harness
+        .withdraw_confidential(&wrapped.mint, &user, 500)
+        .await;
+
+    let unwrap = harness.unwrap_ix(&wrapped, &user, &underlying_account, 501);
+    let result = harness.process(&[unwrap], &[&user.owner]).await;
+    assert_eq!(
+        custom_error(result),
+        anchor_error(PrivacyError::InsufficientBalance)
+    );
+    assert_eq!(harness.token_amount(&wrapped.reserve).await, 500);
+    assert_eq!(harness.mint_supply(&wrapped.mint).await, 500);
+}
@@ -0,0 +1,417 @@
+//! Auditor view of confidential transfer history.
+//!
+//! Every confidential transfer, single or batched, is backed by a ciphertext
+//! validity proof whose grouped ciphertexts carry a decrypt handle for the
+//! mint's auditor. Given the auditor ElGamal secret key and exported
+//! transactions, this module pairs each transfer with the verify instruction
+//! that populated its proof context account and decrypts the transfer amount.
+//! No RPC access is needed, the proof context accounts are long closed by the
+//! time an audit runs.
+//!
+//! Exports are [`ExportedTransaction`] lists, as JSON (pubkeys in base58,
+//! instruction data in base64) or bincode.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use anchor_lang::Discriminator;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::solana_zk_token_sdk::encryption::elgamal::ElGamalSecretKey;
+use spl_token_2022::solana_zk_token_sdk::instruction::{
+    BatchedGroupedCiphertext2HandlesValidityProofContext,
+    BatchedGroupedCiphertext2HandlesValidityProofData, ZkProofData,
+};
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::pod;
+use spl_token_2022::solana_zk_token_sdk::zk_token_proof_instruction::ProofInstruction;
+use spl_token_2022::solana_zk_token_sdk::zk_token_proof_program;
+
+use crate::balance;
+use crate::error::{ClientError, Result};
+
+/// Account positions in `transfer_confidential`, see
+/// `vault::accounts::TransferConfidential`
+const TRANSFER_SENDER: usize = 0;
+const TRANSFER_SENDER_ACCOUNT: usize = 1;
+const TRANSFER_RECIPIENT_ACCOUNT: usize = 2;
+const TRANSFER_MINT: usize = 3;
+const TRANSFER_CIPHERTEXT_VALIDITY_PROOF: usize = 5;
+
+/// Account positions in `batch_transfer_confidential`: the fixed accounts of
+/// `vault::accounts::BatchTransferConfidential`, then per transfer the
+/// recipient followed by its proof accounts
+const BATCH_SENDER: usize = 0;
+const BATCH_SENDER_ACCOUNT: usize = 1;
+const BATCH_MINT: usize = 2;
+const BATCH_FIXED_ACCOUNTS: usize = 5;
+const BATCH_CIPHERTEXT_VALIDITY_PROOF: usize = 2;
+
+/// Index of the auditor handle in the transfer amount grouped ciphertexts
+/// (destination first, auditor second)
+const AUDITOR_HANDLE_INDEX: usize = 1;
+
+/// Bits of the transfer amount in the low ciphertext
+const TRANSFER_AMOUNT_LO_BIT_LENGTH: u32 = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedTransaction {
+    pub slot: u64,
+    pub signature: String,
+    pub instructions: Vec<ExportedInstruction>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedInstruction {
+    #[serde(with = "human_readable::pubkey")]
+    pub program_id: Pubkey,
+    #[serde(with = "human_readable::pubkeys")]
+    pub accounts: Vec<Pubkey>,
+    #[serde(with = "human_readable::bytes")]
+    pub data: Vec<u8>,
+}
+
+impl From<&solana_sdk::instruction::Instruction> for ExportedInstruction {
+    fn from(instruction: &solana_sdk::instruction::Instruction) -> Self {
+        Self {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|meta| meta.pubkey)
+                .collect(),
+            data: instruction.data.clone(),
+        }
+    }
+}
+
+/// One decrypted confidential transfer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LedgerEntry {
+    pub slot: u64,
+    pub signature: String,
+    pub mint: Pubkey,
+    pub sender: Pubkey,
+    pub sender_account: Pubkey,
+    pub recipient_account: Pubkey,
+    pub amount: u64,
+}
+
+/// A transfer that could not be decrypted
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkippedTransfer {
+    pub slot: u64,
+    pub signature: String,
+    pub reason: SkipReason,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The export has no verify instruction for the validity proof account
+    MissingProof,
+    /// The transfer was audited under another auditor key
+    OtherAuditor,
+    /// The auditor ciphertexts did not decrypt to a transfer amount
+    Undecryptable,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ledger {
+    pub entries: Vec<LedgerEntry>,
+    pub skipped: Vec<SkippedTransfer>,
+}
+
+impl Ledger {
+    /// Write the ledger as CSV, one row per decrypted transfer
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writeln!(
+            writer,
+            "slot,signature,mint,sender,sender_account,recipient_account,amount"
+        )?;
+        for entry in &self.entries {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                entry.slot,
+                entry.signature,
+                entry.mint,
+                entry.sender,
+                entry.sender_account,
+                entry.recipient_account,
+                entry.amount
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Read every `.json`, `.bin` and `.bincode` export in `dir`
+pub fn read_exports(dir: &Path) -> Result<Vec<ExportedTransaction>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+
+    let mut transactions = Vec::new();
+    for path in paths {
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        match extension {
+            Some("json") => {
+                let data = fs::read(&path)?;
+                transactions.extend(
+                    serde_json::from_slice::<Vec<ExportedTransaction>>(&data).map_err(|e| {
+                        ClientError::InvalidExport(format!("{}: {e}", path.display()))
+                    })?,
+                );
+            }
+            Some("bin" | "bincode") => {
+                let data = fs::read(&path)?;
+                transactions.extend(
+                    bincode::deserialize::<Vec<ExportedTransaction>>(&data).map_err(|e| {
+                        ClientError::InvalidExport(format!("{}: {e}", path.display()))
+                    })?,
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(transactions)
+}
+
+/// Decrypt the amount of every `transfer_confidential` and batch transfer
+/// in `transactions`
+///
+/// Transactions are replayed in slot order so a transfer is paired with the
+/// latest proof verified into its context account before it.
+pub fn build_ledger(
+    transactions: &[ExportedTransaction],
+    auditor_secret: &ElGamalSecretKey,
+    auditor_pubkey: &pod::ElGamalPubkey,
+) -> Result<Ledger> {
+    let mut ordered: Vec<&ExportedTransaction> = transactions.iter().collect();
+    ordered.sort_by_key(|transaction| transaction.slot);
+
+    let mut proofs = HashMap::<Pubkey, BatchedGroupedCiphertext2HandlesValidityProofContext>::new();
+    let mut ledger = Ledger::default();
+
+    for transaction in ordered {
+        for instruction in &transaction.instructions {
+            if let Some((account, context)) = ciphertext_validity_context(instruction) {
+                proofs.insert(account, context);
+                continue;
+            }
+            for transfer in transfers(instruction) {
+                let skip = |reason| SkippedTransfer {
+                    slot: transaction.slot,
+                    signature: transaction.signature.clone(),
+                    reason,
+                };
+                let Some(context) = proofs.get(&transfer.ciphertext_validity_proof) else {
+                    ledger.skipped.push(skip(SkipReason::MissingProof));
+                    continue;
+                };
+                if context.auditor_pubkey != *auditor_pubkey {
+                    ledger.skipped.push(skip(SkipReason::OtherAuditor));
+                    continue;
+                }
+                let Some(amount) = decrypt_transfer_amount(context, auditor_secret)? else {
+                    ledger.skipped.push(skip(SkipReason::Undecryptable));
+                    continue;
+                };
+
+                ledger.entries.push(LedgerEntry {
+                    slot: transaction.slot,
+                    signature: transaction.signature.clone(),
+                    mint: transfer.mint,
+                    sender: transfer.sender,
+                    sender_account: transfer.sender_account,
+                    recipient_account: transfer.recipient_account,
+                    amount,
+                });
+            }
+        }
+    }
+    Ok(ledger)
+}
+
+/// Decrypt the transfer amount from the auditor handles of a validity proof
+pub fn decrypt_transfer_amount(
+    context: &BatchedGroupedCiphertext2HandlesValidityProofContext,
+    auditor_secret: &ElGamalSecretKey,
+) -> Result<Option<u64>> {
+    let lo = auditor_ciphertext(&context.grouped_ciphertext_lo);
+    let hi = auditor_ciphertext(&context.grouped_ciphertext_hi);
+    let Some(lo) = balance::decrypt_u32(auditor_secret, &lo)? else {
+        return Ok(None);
+    };
+    let Some(hi) = balance::decrypt_u32(auditor_secret, &hi)? else {
+        return Ok(None);
+    };
+    Ok((hi << TRANSFER_AMOUNT_LO_BIT_LENGTH).checked_add(lo))
+}
+
+/// Accounts of one confidential transfer in a vault instruction
+struct TransferAccounts {
+    sender: Pubkey,
+    sender_account: Pubkey,
+    recipient_account: Pubkey,
+    mint: Pubkey,
+    ciphertext_validity_proof: Pubkey,
+}
+
+/// Transfers made by `instruction`, none if it is not a vault transfer
+fn transfers(instruction: &ExportedInstruction) -> Vec<TransferAccounts> {
+    let accounts = &instruction.accounts;
+    if instruction.program_id != vault::ID {
+        return Vec::new();
+    }
+
+    if instruction
+        .data
+        .starts_with(&vault::instruction::TransferConfidential::DISCRIMINATOR)
+        && accounts.len() > TRANSFER_CIPHERTEXT_VALIDITY_PROOF
+    {
+        return vec![TransferAccounts {
+            sender: accounts[TRANSFER_SENDER],
+            sender_account: accounts[TRANSFER_SENDER_ACCOUNT],
+            recipient_account: accounts[TRANSFER_RECIPIENT_ACCOUNT],
+            mint: accounts[TRANSFER_MINT],
+            ciphertext_validity_proof: accounts[TRANSFER_CIPHERTEXT_VALIDITY_PROOF],
+        }];
+    }
+
+    if instruction
+        .data
+        .starts_with(&vault::instruction::BatchTransferConfidential::DISCRIMINATOR)
+    {
+        // Borsh length prefix of the transfers vector
+        let count = instruction
+            .data
+            .get(8..12)
+            .map(|len| u32::from_le_bytes(len.try_into().unwrap()) as usize)
+            .unwrap_or_default();
+        let Some(per_transfer) = accounts
+            .len()
+            .checked_sub(BATCH_FIXED_ACCOUNTS)
+            .and_then(|remaining| remaining.checked_div(count))
+        else {
+            return Vec::new();
+        };
+        if per_transfer <= BATCH_CIPHERTEXT_VALIDITY_PROOF {
+            return Vec::new();
+        }
+        return accounts[BATCH_FIXED_ACCOUNTS..]
+            .chunks_exact(per_transfer)
+            .map(|transfer| TransferAccounts {
+                sender: accounts[BATCH_SENDER],
+                sender_account: accounts[BATCH_SENDER_ACCOUNT],
+                recipient_account: transfer[0],
+                mint: accounts[BATCH_MINT],
+                ciphertext_validity_proof: transfer[BATCH_CIPHERTEXT_VALIDITY_PROOF],
+            })
+            .collect();
+    }
+
+    Vec::new()
+}
+
+/// Context of a verify instruction that writes a batched grouped 2-handles
+/// validity proof into a context state account
+fn ciphertext_validity_context(
+    instruction: &ExportedInstruction,
+) -> Option<(Pubkey, BatchedGroupedCiphertext2HandlesValidityProofContext)> {
+    if instruction.program_id != zk_token_proof_program::id()
+        || ProofInstruction::instruction_type(&instruction.data)
+            != Some(ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity)
+    {
+        return None;
+    }
+    let context_state_account = *instruction.accounts.first()?;
+    let proof_data = ProofInstruction::proof_data::<
+        BatchedGroupedCiphertext2HandlesValidityProofData,
+        BatchedGroupedCiphertext2HandlesValidityProofContext,
+    >(&instruction.data)?;
+    Some((context_state_account, *proof_data.context_data()))
+}
+
+/// Commitment followed by the auditor decrypt handle
+fn auditor_ciphertext(grouped: &pod::GroupedElGamalCiphertext2Handles) -> pod::ElGamalCiphertext {
+    let handle = 32 * (AUDITOR_HANDLE_INDEX + 1);
+    let mut ciphertext = [0u8; 64];
+    ciphertext[..32].copy_from_slice(&grouped.0[..32]);
+    ciphertext[32..].copy_from_slice(&grouped.0[handle..handle + 32]);
+    pod::ElGamalCiphertext(ciphertext)
+}
+
+/// Base58 pubkeys and base64 data in JSON, raw bytes in bincode
+mod human_readable {
+    pub mod pubkey {
+        use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+        use solana_sdk::pubkey::Pubkey;
+        use std::str::FromStr;
+
+        pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+            if serializer.is_human_readable() {
+                serializer.serialize_str(&pubkey.to_string())
+            } else {
+                pubkey.serialize(serializer)
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+            if deserializer.is_human_readable() {
+                let encoded = String::deserialize(deserializer)?;
+                Pubkey::from_str(&encoded).map_err(D::Error::custom)
+            } else {
+                Pubkey::deserialize(deserializer)
+            }
+        }
+    }
+
+    pub mod pubkeys {
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+        use solana_sdk::pubkey::Pubkey;
+
+        #[derive(Serialize, Deserialize)]
+        struct Wrapped(#[serde(with = "super::pubkey")] Pubkey);
+
+        pub fn serialize<S: Serializer>(
+            pubkeys: &[Pubkey],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(pubkeys.iter().map(|pubkey| Wrapped(*pubkey)))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<Pubkey>, D::Error> {
+            let wrapped = Vec::<Wrapped>::deserialize(deserializer)?;
+            Ok(wrapped.into_iter().map(|Wrapped(pubkey)| pubkey).collect())
+        }
+    }
+
+    pub mod bytes {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+        use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S: Serializer>(bytes: &Vec<u8>, serializer: S) -> Result<S::Ok, S::Error> {
+            if serializer.is_human_readable() {
+                serializer.serialize_str(&STANDARD.encode(bytes))
+            } else {
+                bytes.serialize(serializer)
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<u8>, D::Error> {
+            if deserializer.is_human_readable() {
+                let encoded = String::deserialize(deserializer)?;
+                STANDARD.decode(encoded).map_err(D::Error::custom)
+            } else {
+                Vec::<u8>::deserialize(deserializer)
+            }
+        }
+    }
+}
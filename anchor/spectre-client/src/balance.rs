@@ -0,0 +1,179 @@
+//! Balance decryption for confidential token accounts.
+//!
+//! The available balance is read from the AE ciphertext, which only the
+//! owner can decrypt and which decrypts instantly. Pending balances only
+//! exist as ElGamal ciphertexts, so they are recovered by solving a discrete
+//! log bounded to 32 bits per half. The ElGamal available balance is checked
+//! against the AE value: they drift apart when a credit lands between
+//! building and executing `apply_pending_balance`, and the AE copy is then
+//! stale until the next apply.
+
+use spl_token_2022::extension::confidential_transfer::ConfidentialTransferAccount;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::solana_zk_token_sdk::encryption::auth_encryption::AeCiphertext;
+use spl_token_2022::solana_zk_token_sdk::encryption::elgamal::{
+    ElGamalCiphertext, ElGamalSecretKey,
+};
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::{ops, pod};
+use spl_token_2022::state::Account;
+
+use crate::error::{ClientError, Result};
+use crate::keys::ConfidentialKeys;
+
+/// Threads used by the discrete log solver, must be a power of two
+pub const DISCRETE_LOG_THREADS: usize = 4;
+
+/// Bits of a credit that go to the low pending ciphertext
+const PENDING_BALANCE_LO_BIT_LENGTH: u32 = 16;
+
+/// Decrypted view of a confidential token account
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfidentialBalance {
+    /// Non-confidential token amount
+    pub public: u64,
+    /// Available confidential balance, from the AE ciphertext
+    pub available: u64,
+    /// Pending confidential balance, `None` if it is outside the solver bound
+    pub pending: Option<u64>,
+    pub pending_balance_credit_counter: u64,
+    pub maximum_pending_balance_credit_counter: u64,
+    pub expected_pending_balance_credit_counter: u64,
+    pub actual_pending_balance_credit_counter: u64,
+    pub allow_confidential_credits: bool,
+    pub allow_non_confidential_credits: bool,
+    pub warnings: Vec<BalanceWarning>,
+}
+
+impl ConfidentialBalance {
+    /// Confidential balance once the pending balance is applied
+    pub fn total_confidential(&self) -> Option<u64> {
+        self.available.checked_add(self.pending?)
+    }
+
+    /// Further credits are rejected until the pending balance is applied
+    pub fn pending_credits_full(&self) -> bool {
+        self.pending_balance_credit_counter >= self.maximum_pending_balance_credit_counter
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BalanceWarning {
+    /// The AE available balance does not match the ElGamal ciphertext.
+    /// `elgamal_available` is the ElGamal balance when it is within the
+    /// solver bound of the AE value.
+    AvailableBalanceMismatch {
+        decryptable_available: u64,
+        elgamal_available: Option<u64>,
+    },
+    /// Credits arrived after the last `apply_pending_balance` was built
+    /// and were not included in its AE balance
+    MissedPendingCredits { expected: u64, actual: u64 },
+    /// A pending ciphertext is outside the solver bound
+    PendingBalanceUndecryptable,
+}
+
+/// Decrypt a confidential token account from its raw Token-2022 data
+pub fn decrypt_balance(data: &[u8], keys: &ConfidentialKeys) -> Result<ConfidentialBalance> {
+    let account = StateWithExtensions::<Account>::unpack(data)?;
+    let state = account
+        .get_extension::<ConfidentialTransferAccount>()
+        .map_err(|_| ClientError::AccountNotConfigured)?;
+    decrypt_state(account.base.amount, state, keys)
+}
+
+/// Decrypt the extension of an already unpacked account
+pub fn decrypt_state(
+    public: u64,
+    state: &ConfidentialTransferAccount,
+    keys: &ConfidentialKeys,
+) -> Result<ConfidentialBalance> {
+    if state.elgamal_pubkey != keys.elgamal_pubkey() {
+        return Err(ClientError::Decryption);
+    }
+
+    let mut warnings = Vec::new();
+
+    let decryptable_available_balance = AeCiphertext::try_from(state.decryptable_available_balance)
+        .map_err(|_| ClientError::MalformedCiphertext)?;
+    let available = keys
+        .ae
+        .decrypt(&decryptable_available_balance)
+        .ok_or(ClientError::Decryption)?;
+
+    if let Some(warning) = check_available_balance(state, keys.elgamal.secret(), available)? {
+        warnings.push(warning);
+    }
+
+    let expected = u64::from(state.expected_pending_balance_credit_counter);
+    let actual = u64::from(state.actual_pending_balance_credit_counter);
+    if expected != actual {
+        warnings.push(BalanceWarning::MissedPendingCredits { expected, actual });
+    }
+
+    let pending = decrypt_pending(state, keys.elgamal.secret())?;
+    if pending.is_none() {
+        warnings.push(BalanceWarning::PendingBalanceUndecryptable);
+    }
+
+    Ok(ConfidentialBalance {
+        public,
+        available,
+        pending,
+        pending_balance_credit_counter: state.pending_balance_credit_counter.into(),
+        maximum_pending_balance_credit_counter: state.maximum_pending_balance_credit_counter.into(),
+        expected_pending_balance_credit_counter: expected,
+        actual_pending_balance_credit_counter: actual,
+        allow_confidential_credits: state.allow_confidential_credits.into(),
+        allow_non_confidential_credits: state.allow_non_confidential_credits.into(),
+        warnings,
+    })
+}
+
+/// Decrypt the pending balance, `None` if either half is above 32 bits
+pub fn decrypt_pending(
+    state: &ConfidentialTransferAccount,
+    secret: &ElGamalSecretKey,
+) -> Result<Option<u64>> {
+    let Some(lo) = decrypt_u32(secret, &state.pending_balance_lo)? else {
+        return Ok(None);
+    };
+    let Some(hi) = decrypt_u32(secret, &state.pending_balance_hi)? else {
+        return Ok(None);
+    };
+    Ok((hi << PENDING_BALANCE_LO_BIT_LENGTH).checked_add(lo))
+}
+
+/// Decrypt an ElGamal ciphertext known to hold at most a 32 bit amount
+pub fn decrypt_u32(
+    secret: &ElGamalSecretKey,
+    ciphertext: &pod::ElGamalCiphertext,
+) -> Result<Option<u64>> {
+    let ciphertext =
+        ElGamalCiphertext::try_from(*ciphertext).map_err(|_| ClientError::MalformedCiphertext)?;
+    let mut discrete_log = secret.decrypt(&ciphertext);
+    discrete_log
+        .num_threads(DISCRETE_LOG_THREADS)
+        .map_err(|_| ClientError::Decryption)?;
+    Ok(discrete_log.decode_u32())
+}
+
+/// Subtracting the AE amount from the ElGamal balance has to leave zero.
+/// Anything left over within the solver bound tells by how much the AE copy
+/// is behind.
+fn check_available_balance(
+    state: &ConfidentialTransferAccount,
+    secret: &ElGamalSecretKey,
+    decryptable_available: u64,
+) -> Result<Option<BalanceWarning>> {
+    let difference = ops::subtract_from(&state.available_balance, decryptable_available)
+        .ok_or(ClientError::MalformedCiphertext)?;
+    let elgamal_available = match decrypt_u32(secret, &difference)? {
+        Some(0) => return Ok(None),
+        Some(missing) => decryptable_available.checked_add(missing),
+        None => None,
+    };
+    Ok(Some(BalanceWarning::AvailableBalanceMismatch {
+        decryptable_available,
+        elgamal_available,
+    }))
+}
@@ -0,0 +1,97 @@
+//! Decrypt the confidential transfer history of a mint with its auditor key.
+//!
+//! Usage: spectre-audit --auditor-keypair <elgamal.json> --exports <dir> [--output <ledger.csv>]
+//!
+//! The auditor keypair is an ElGamal keypair JSON file as written by
+//! `solana-zk-keygen`. The exports directory holds `.json` or `.bin`
+//! transaction exports (see `spectre_client::audit::ExportedTransaction`).
+//! The ledger goes to stdout unless `--output` is given; transfers that
+//! could not be decrypted are listed on stderr.
+
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use spectre_client::audit;
+use spl_token_2022::solana_zk_token_sdk::encryption::elgamal::ElGamalKeypair;
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::pod;
+
+const USAGE: &str =
+    "usage: spectre-audit --auditor-keypair <elgamal.json> --exports <dir> [--output <ledger.csv>]";
+
+struct Args {
+    auditor_keypair: PathBuf,
+    exports: PathBuf,
+    output: Option<PathBuf>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut auditor_keypair = None;
+    let mut exports = None;
+    let mut output = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let slot = match arg.as_str() {
+            "--auditor-keypair" => &mut auditor_keypair,
+            "--exports" => &mut exports,
+            "--output" => &mut output,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            other => return Err(format!("unexpected argument {other}\n{USAGE}")),
+        };
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{arg} needs a value\n{USAGE}"))?;
+        *slot = Some(PathBuf::from(value));
+    }
+
+    Ok(Args {
+        auditor_keypair: auditor_keypair.ok_or(USAGE)?,
+        exports: exports.ok_or(USAGE)?,
+        output,
+    })
+}
+
+fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let auditor = ElGamalKeypair::read_json_file(&args.auditor_keypair)?;
+    let auditor_pubkey = pod::ElGamalPubkey::from(*auditor.pubkey());
+
+    let transactions = audit::read_exports(&args.exports)?;
+    let ledger = audit::build_ledger(&transactions, auditor.secret(), &auditor_pubkey)?;
+
+    match args.output {
+        Some(path) => ledger.write_csv(BufWriter::new(File::create(path)?))?,
+        None => ledger.write_csv(io::stdout().lock())?,
+    }
+
+    for skipped in &ledger.skipped {
+        eprintln!(
+            "skipped {} (slot {}): {:?}",
+            skipped.signature, skipped.slot, skipped.reason
+        );
+    }
+    eprintln!(
+        "{} transfers decrypted, {} skipped",
+        ledger.entries.len(),
+        ledger.skipped.len()
+    );
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
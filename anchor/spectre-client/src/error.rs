@@ -0,0 +1,60 @@
+use solana_sdk::program_error::ProgramError;
+use solana_sdk::signer::SignerError;
+use spl_token_2022::error::TokenError;
+use spl_token_2022::solana_zk_token_sdk::errors::ProofGenerationError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("signer failed: {0}")]
+    Signer(#[from] SignerError),
+    #[error("signature cannot be used as key material")]
+    InvalidSignature,
+    #[error("key derivation failed")]
+    KeyDerivation,
+    #[error("token account is not a valid Token-2022 account: {0}")]
+    InvalidAccountData(#[from] ProgramError),
+    #[error("token account is not configured for confidential transfers")]
+    AccountNotConfigured,
+    #[error("mint is not configured for confidential transfers")]
+    MintNotConfigured,
+    #[error("malformed ciphertext or public key")]
+    MalformedCiphertext,
+    #[error("failed to decrypt balance with the given keys")]
+    Decryption,
+    #[error("insufficient confidential balance")]
+    InsufficientFunds,
+    #[error("proof generation failed")]
+    ProofGeneration,
+    #[error("batch must hold between 1 and vault::MAX_BATCH_TRANSFERS transfers")]
+    InvalidBatchSize,
+    #[error("memo is empty or too long")]
+    InvalidMemo,
+    #[error("amount exceeds the vested share of the stream")]
+    StreamAllowanceExceeded,
+    #[error("invoice does not decrypt to the amount being paid")]
+    InvoiceAmountMismatch,
+    #[error("invalid transaction export {0}")]
+    InvalidExport(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+impl From<TokenError> for ClientError {
+    fn from(e: TokenError) -> Self {
+        match e {
+            TokenError::MalformedCiphertext => ClientError::MalformedCiphertext,
+            TokenError::AccountDecryption => ClientError::Decryption,
+            TokenError::InsufficientFunds => ClientError::InsufficientFunds,
+            _ => ClientError::ProofGeneration,
+        }
+    }
+}
+
+impl From<ProofGenerationError> for ClientError {
+    fn from(_: ProofGenerationError) -> Self {
+        ClientError::ProofGeneration
+    }
+}
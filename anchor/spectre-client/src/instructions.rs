@@ -0,0 +1,1407 @@
+//! Instruction builders for the `privacy_payments` instructions.
+//!
+//! Builders that need proofs too large to sit next to the program
+//! instruction verify them into context state accounts first. Those come back
+//! as a struct whose `transactions()` lists the instruction groups in the
+//! order they have to land, each small enough for a single transaction.
+
+use std::mem::size_of;
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::{system_instruction, system_program, sysvar};
+use spl_token_2022::extension::confidential_transfer::ConfidentialTransferAccount;
+use spl_token_2022::solana_zk_token_sdk::instruction::{Pod, ZeroBalanceProofData, ZkProofData};
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::pod;
+use spl_token_2022::solana_zk_token_sdk::zk_token_proof_instruction::{
+    close_context_state, verify_pubkey_validity, verify_zero_balance, ContextStateInfo,
+    ProofInstruction,
+};
+use spl_token_2022::solana_zk_token_sdk::zk_token_proof_program;
+use spl_token_2022::solana_zk_token_sdk::zk_token_proof_state::ProofContextState;
+
+use vault::{
+    batch_transfer_compute_units, BatchTransferEntry, ConfidentialMemo, Invoice, Stream,
+    StreamSchedule, CREDIT_POLICY_SEED, INVOICE_SEED, MAX_BATCH_TRANSFERS, STREAM_ESCROW_SEED,
+    STREAM_SEED,
+};
+
+use crate::error::{ClientError, Result};
+use crate::keys::ConfidentialKeys;
+use crate::proofs::{self, FeeProofParams};
+use crate::stream::{self, AllowanceProofs, StreamSecret};
+
+/// A proof verified into its own context state account
+///
+/// `create` must be signed by the payer and `keypair`. Once the program
+/// instruction that reads the proof has landed, `close` returns the rent to
+/// the context state authority.
+pub struct ProofContextAccount {
+    pub keypair: Keypair,
+    pub create: Instruction,
+    pub verify: Instruction,
+    pub close: Instruction,
+}
+
+impl ProofContextAccount {
+    pub fn new<T, U>(
+        payer: &Pubkey,
+        authority: &Pubkey,
+        proof_instruction: ProofInstruction,
+        proof_data: &T,
+        rent: &Rent,
+    ) -> Self
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        let keypair = Keypair::new();
+        let space = size_of::<ProofContextState<U>>();
+        let create = system_instruction::create_account(
+            payer,
+            &keypair.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        );
+        let context_state_info = ContextStateInfo {
+            context_state_account: &keypair.pubkey(),
+            context_state_authority: authority,
+        };
+        let verify = proof_instruction.encode_verify_proof(Some(context_state_info), proof_data);
+        let close = close_context_state(context_state_info, authority);
+        Self {
+            keypair,
+            create,
+            verify,
+            close,
+        }
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+}
+
+/// Configure a new confidential token account
+///
+/// `confidential_account` is a fresh keypair account and signs along with
+/// `user`. Use its address when deriving `keys`. The pubkey validity proof
+/// goes inline right after the program instruction.
+pub fn initialize_user_account(
+    user: &Pubkey,
+    confidential_account: &Pubkey,
+    mint: &Pubkey,
+    keys: &ConfidentialKeys,
+) -> Result<Vec<Instruction>> {
+    let proof = proofs::configure_account_proof(keys)?;
+    let initialize = Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::InitializeUserAccount {
+            user: *user,
+            confidential_account: *confidential_account,
+            mint: *mint,
+            proof_account: sysvar::instructions::id(),
+            token_program: spl_token_2022::id(),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: vault::instruction::InitializeUserAccount {
+            elgamal_pubkey: keys.elgamal_pubkey().0,
+            decryptable_zero_balance: proof.decryptable_zero_balance,
+            proof_instruction_offset: 1,
+        }
+        .data(),
+    };
+    Ok(vec![initialize, verify_pubkey_validity(None, &proof.proof)])
+}
+
+/// CreditPolicy PDA of a token account, only initialized once the account
+/// is locked to confidential credits
+pub fn credit_policy_address(token_account: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[CREDIT_POLICY_SEED, token_account.as_ref()], &vault::ID).0
+}
+
+/// Move public balance into the pending confidential balance
+pub fn deposit_confidential(
+    authority: &Pubkey,
+    confidential_account: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::DepositConfidential {
+            authority: *authority,
+            confidential_account: *confidential_account,
+            mint: *mint,
+            credit_policy: credit_policy_address(confidential_account),
+            token_program: spl_token_2022::id(),
+        }
+        .to_account_metas(None),
+        data: vault::instruction::DepositConfidential { amount }.data(),
+    }
+}
+
+/// Roll the pending balance into the available balance
+pub fn apply_pending_balance(
+    authority: &Pubkey,
+    confidential_account: &Pubkey,
+    state: &ConfidentialTransferAccount,
+    keys: &ConfidentialKeys,
+) -> Result<Instruction> {
+    let apply = proofs::apply_pending_balance(state, keys)?;
+    Ok(Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::ApplyPendingBalance {
+            authority: *authority,
+            confidential_account: *confidential_account,
+            credit_policy: credit_policy_address(confidential_account),
+            token_program: spl_token_2022::id(),
+        }
+        .to_account_metas(None),
+        data: vault::instruction::ApplyPendingBalance {
+            expected_pending_balance_credit_counter: apply.expected_pending_balance_credit_counter,
+            new_decryptable_available_balance: apply.new_decryptable_available_balance,
+        }
+        .data(),
+    })
+}
+
+/// Fee sigma and fee ciphertext validity proofs of a transfer on a mint with
+/// transfer fees
+pub struct FeeProofAccounts {
+    pub fee_sigma_proof: ProofContextAccount,
+    pub fee_ciphertext_validity_proof: ProofContextAccount,
+}
+
+impl FeeProofAccounts {
+    fn verify_transactions(&self) -> Vec<Vec<Instruction>> {
+        vec![
+            vec![
+                self.fee_sigma_proof.create.clone(),
+                self.fee_sigma_proof.verify.clone(),
+            ],
+            vec![
+                self.fee_ciphertext_validity_proof.create.clone(),
+                self.fee_ciphertext_validity_proof.verify.clone(),
+            ],
+        ]
+    }
+
+    fn close(&self) -> [Instruction; 2] {
+        [
+            self.fee_sigma_proof.close.clone(),
+            self.fee_ciphertext_validity_proof.close.clone(),
+        ]
+    }
+}
+
+/// Compute unit limit for verifying a BatchedRangeProofU256, which costs the
+/// proof program 368,000 units, above the 200,000 an instruction gets by default
+const VERIFY_RANGE_PROOF_U256_COMPUTE_UNITS: u32 = 370_000;
+
+/// Transactions with fees prove the range with a BatchedRangeProofU256, so
+/// its verification needs a higher compute unit limit
+fn range_proof_verify_transaction(
+    range_proof: &ProofContextAccount,
+    with_fee: bool,
+) -> Vec<Instruction> {
+    let mut transaction = Vec::new();
+    if with_fee {
+        transaction.push(ComputeBudgetInstruction::set_compute_unit_limit(
+            VERIFY_RANGE_PROOF_U256_COMPUTE_UNITS,
+        ));
+    }
+    transaction.push(range_proof.verify.clone());
+    transaction
+}
+
+pub struct TransferInstructions {
+    pub equality_proof: ProofContextAccount,
+    pub ciphertext_validity_proof: ProofContextAccount,
+    /// BatchedRangeProofU128, or BatchedRangeProofU256 with fees
+    pub range_proof: ProofContextAccount,
+    /// Only on mints with transfer fees
+    pub fee_proofs: Option<FeeProofAccounts>,
+    pub transfer: Instruction,
+}
+
+impl TransferInstructions {
+    /// The range proof is too large to share a transaction with its account
+    /// creation, so it gets its own
+    pub fn transactions(&self) -> Vec<Vec<Instruction>> {
+        let mut transactions = vec![
+            vec![
+                self.equality_proof.create.clone(),
+                self.equality_proof.verify.clone(),
+            ],
+            vec![
+                self.ciphertext_validity_proof.create.clone(),
+                self.ciphertext_validity_proof.verify.clone(),
+            ],
+        ];
+        let mut close = vec![
+            self.equality_proof.close.clone(),
+            self.ciphertext_validity_proof.close.clone(),
+            self.range_proof.close.clone(),
+        ];
+        if let Some(fee_proofs) = &self.fee_proofs {
+            transactions.extend(fee_proofs.verify_transactions());
+            close.extend(fee_proofs.close());
+        }
+        transactions.push(vec![self.range_proof.create.clone()]);
+        transactions.push(range_proof_verify_transaction(
+            &self.range_proof,
+            self.fee_proofs.is_some(),
+        ));
+        transactions.push(vec![self.transfer.clone()]);
+        transactions.push(close);
+        transactions
+    }
+}
+
+/// Accounts to append to a transfer of a mint whose transfer hook is the
+/// allowlist-hook program: the allowlist entries of both token account
+/// owners, the hook program and the mint's ExtraAccountMetaList
+pub fn allowlist_hook_accounts(
+    mint: &Pubkey,
+    source_owner: &Pubkey,
+    destination_owner: &Pubkey,
+) -> Vec<AccountMeta> {
+    let entry = |owner: &Pubkey| {
+        Pubkey::find_program_address(
+            &[
+                allowlist_hook::ALLOWLIST_ENTRY_SEED,
+                mint.as_ref(),
+                owner.as_ref(),
+            ],
+            &allowlist_hook::ID,
+        )
+        .0
+    };
+    let (extra_account_meta_list, _) = Pubkey::find_program_address(
+        &[allowlist_hook::EXTRA_ACCOUNT_METAS_SEED, mint.as_ref()],
+        &allowlist_hook::ID,
+    );
+    vec![
+        AccountMeta::new_readonly(entry(source_owner), false),
+        AccountMeta::new_readonly(entry(destination_owner), false),
+        AccountMeta::new_readonly(allowlist_hook::ID, false),
+        AccountMeta::new_readonly(extra_account_meta_list, false),
+    ]
+}
+
+/// Confidential transfer on a mint without transfer fees
+///
+/// `sender` is the context state authority and gets the proof account rent
+/// back on cleanup. `memo` comes from [`crate::memo::encrypt_memo`]. On a
+/// mint with the allowlist hook, extend the transfer's accounts with
+/// [`allowlist_hook_accounts`].
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_confidential(
+    sender: &Pubkey,
+    sender_account: &Pubkey,
+    sender_state: &ConfidentialTransferAccount,
+    recipient_account: &Pubkey,
+    recipient_elgamal_pubkey: &pod::ElGamalPubkey,
+    mint: &Pubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+    keys: &ConfidentialKeys,
+    amount: u64,
+    memo: Option<ConfidentialMemo>,
+    rent: &Rent,
+) -> Result<TransferInstructions> {
+    let proven = ProvenTransfer::prove(
+        sender,
+        sender_state,
+        keys,
+        amount,
+        recipient_elgamal_pubkey,
+        auditor_elgamal_pubkey,
+        None,
+        rent,
+    )?;
+    Ok(transfer_instructions(
+        sender,
+        sender_account,
+        recipient_account,
+        mint,
+        proven,
+        memo,
+    ))
+}
+
+/// [`transfer_confidential`] on a mint with transfer fees
+///
+/// `fee` is the mint's current fee, see [`proofs::mint_transfer_fee`]. The
+/// recipient is credited `amount` less the fee.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_confidential_with_fee(
+    sender: &Pubkey,
+    sender_account: &Pubkey,
+    sender_state: &ConfidentialTransferAccount,
+    recipient_account: &Pubkey,
+    recipient_elgamal_pubkey: &pod::ElGamalPubkey,
+    mint: &Pubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+    keys: &ConfidentialKeys,
+    amount: u64,
+    fee: &FeeProofParams,
+    memo: Option<ConfidentialMemo>,
+    rent: &Rent,
+) -> Result<TransferInstructions> {
+    let proven = ProvenTransfer::prove(
+        sender,
+        sender_state,
+        keys,
+        amount,
+        recipient_elgamal_pubkey,
+        auditor_elgamal_pubkey,
+        Some(fee),
+        rent,
+    )?;
+    Ok(transfer_instructions(
+        sender,
+        sender_account,
+        recipient_account,
+        mint,
+        proven,
+        memo,
+    ))
+}
+
+fn transfer_instructions(
+    sender: &Pubkey,
+    sender_account: &Pubkey,
+    recipient_account: &Pubkey,
+    mint: &Pubkey,
+    proven: ProvenTransfer,
+    memo: Option<ConfidentialMemo>,
+) -> TransferInstructions {
+    let transfer = Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::TransferConfidential {
+            sender: *sender,
+            sender_account: *sender_account,
+            recipient_account: *recipient_account,
+            mint: *mint,
+            equality_proof: proven.equality_proof.pubkey(),
+            ciphertext_validity_proof: proven.ciphertext_validity_proof.pubkey(),
+            fee_sigma_proof: proven.fee_sigma_proof(),
+            fee_ciphertext_validity_proof: proven.fee_ciphertext_validity_proof(),
+            range_proof: proven.range_proof.pubkey(),
+            credit_policy: credit_policy_address(sender_account),
+            token_program: spl_token_2022::id(),
+        }
+        .to_account_metas(None),
+        data: vault::instruction::TransferConfidential {
+            new_source_decryptable_available_balance: proven
+                .new_source_decryptable_available_balance,
+            source_decrypt_handles: proven.source_decrypt_handles,
+            memo,
+        }
+        .data(),
+    };
+
+    TransferInstructions {
+        equality_proof: proven.equality_proof,
+        ciphertext_validity_proof: proven.ciphertext_validity_proof,
+        range_proof: proven.range_proof,
+        fee_proofs: proven.fee_proofs,
+        transfer,
+    }
+}
+
+/// Proof context accounts of one transfer in a batch
+pub struct BatchTransferProofs {
+    pub equality_proof: ProofContextAccount,
+    pub ciphertext_validity_proof: ProofContextAccount,
+    /// BatchedRangeProofU128, or BatchedRangeProofU256 with fees
+    pub range_proof: ProofContextAccount,
+    /// Only on mints with transfer fees
+    pub fee_proofs: Option<FeeProofAccounts>,
+}
+
+/// One payment of a batch
+pub struct BatchRecipient {
+    pub account: Pubkey,
+    pub elgamal_pubkey: pod::ElGamalPubkey,
+    pub amount: u64,
+}
+
+pub struct BatchTransferInstructions {
+    pub proofs: Vec<BatchTransferProofs>,
+    pub compute_budget: Instruction,
+    pub transfer: Instruction,
+}
+
+impl BatchTransferInstructions {
+    /// Proof verification for each recipient, then the batch with its
+    /// compute budget, then cleanup. Batches of more than three transfers
+    /// only fit in a versioned transaction with an address lookup table.
+    pub fn transactions(&self) -> Vec<Vec<Instruction>> {
+        let mut transactions = Vec::new();
+        for proofs in &self.proofs {
+            transactions.push(vec![
+                proofs.equality_proof.create.clone(),
+                proofs.equality_proof.verify.clone(),
+            ]);
+            transactions.push(vec![
+                proofs.ciphertext_validity_proof.create.clone(),
+                proofs.ciphertext_validity_proof.verify.clone(),
+            ]);
+            if let Some(fee_proofs) = &proofs.fee_proofs {
+                transactions.extend(fee_proofs.verify_transactions());
+            }
+            transactions.push(vec![proofs.range_proof.create.clone()]);
+            transactions.push(range_proof_verify_transaction(
+                &proofs.range_proof,
+                proofs.fee_proofs.is_some(),
+            ));
+        }
+        transactions.push(vec![self.compute_budget.clone(), self.transfer.clone()]);
+        for proofs in &self.proofs {
+            let mut close = vec![
+                proofs.equality_proof.close.clone(),
+                proofs.ciphertext_validity_proof.close.clone(),
+                proofs.range_proof.close.clone(),
+            ];
+            if let Some(fee_proofs) = &proofs.fee_proofs {
+                close.extend(fee_proofs.close());
+            }
+            transactions.push(close);
+        }
+        transactions
+    }
+}
+
+/// Pay several recipients in one instruction on a mint without transfer fees
+///
+/// Proofs are generated in order against the balance the sender will have
+/// after the previous transfers of the batch.
+#[allow(clippy::too_many_arguments)]
+pub fn batch_transfer_confidential(
+    sender: &Pubkey,
+    sender_account: &Pubkey,
+    sender_state: &ConfidentialTransferAccount,
+    mint: &Pubkey,
+    recipients: &[BatchRecipient],
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+    keys: &ConfidentialKeys,
+    rent: &Rent,
+) -> Result<BatchTransferInstructions> {
+    batch_transfer(
+        sender,
+        sender_account,
+        sender_state,
+        mint,
+        recipients,
+        auditor_elgamal_pubkey,
+        keys,
+        None,
+        rent,
+    )
+}
+
+/// [`batch_transfer_confidential`] on a mint with transfer fees, `fee` being
+/// the mint's current fee (see [`proofs::mint_transfer_fee`])
+#[allow(clippy::too_many_arguments)]
+pub fn batch_transfer_confidential_with_fee(
+    sender: &Pubkey,
+    sender_account: &Pubkey,
+    sender_state: &ConfidentialTransferAccount,
+    mint: &Pubkey,
+    recipients: &[BatchRecipient],
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+    keys: &ConfidentialKeys,
+    fee: &FeeProofParams,
+    rent: &Rent,
+) -> Result<BatchTransferInstructions> {
+    batch_transfer(
+        sender,
+        sender_account,
+        sender_state,
+        mint,
+        recipients,
+        auditor_elgamal_pubkey,
+        keys,
+        Some(fee),
+        rent,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn batch_transfer(
+    sender: &Pubkey,
+    sender_account: &Pubkey,
+    sender_state: &ConfidentialTransferAccount,
+    mint: &Pubkey,
+    recipients: &[BatchRecipient],
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+    keys: &ConfidentialKeys,
+    fee: Option<&FeeProofParams>,
+    rent: &Rent,
+) -> Result<BatchTransferInstructions> {
+    if recipients.is_empty() || recipients.len() > MAX_BATCH_TRANSFERS {
+        return Err(ClientError::InvalidBatchSize);
+    }
+
+    let mut state = *sender_state;
+    let mut entries = Vec::with_capacity(recipients.len());
+    let mut proof_accounts = Vec::with_capacity(recipients.len());
+    let mut remaining_accounts = Vec::with_capacity(recipients.len() * 6);
+    for recipient in recipients {
+        let proven = ProvenTransfer::prove(
+            sender,
+            &state,
+            keys,
+            recipient.amount,
+            &recipient.elgamal_pubkey,
+            auditor_elgamal_pubkey,
+            fee,
+            rent,
+        )?;
+        // The equality proof is over the balance left after this transfer
+        state.available_balance = proven.new_available_balance;
+        state.decryptable_available_balance =
+            pod::AeCiphertext(proven.new_source_decryptable_available_balance);
+
+        remaining_accounts.extend([
+            AccountMeta::new(recipient.account, false),
+            AccountMeta::new_readonly(proven.equality_proof.pubkey(), false),
+            AccountMeta::new_readonly(proven.ciphertext_validity_proof.pubkey(), false),
+            AccountMeta::new_readonly(proven.range_proof.pubkey(), false),
+        ]);
+        if let (Some(fee_sigma_proof), Some(fee_ciphertext_validity_proof)) = (
+            proven.fee_sigma_proof(),
+            proven.fee_ciphertext_validity_proof(),
+        ) {
+            remaining_accounts.extend([
+                AccountMeta::new_readonly(fee_sigma_proof, false),
+                AccountMeta::new_readonly(fee_ciphertext_validity_proof, false),
+            ]);
+        }
+        entries.push(BatchTransferEntry {
+            new_source_decryptable_available_balance: proven
+                .new_source_decryptable_available_balance,
+            source_decrypt_handles: proven.source_decrypt_handles,
+        });
+        proof_accounts.push(BatchTransferProofs {
+            equality_proof: proven.equality_proof,
+            ciphertext_validity_proof: proven.ciphertext_validity_proof,
+            range_proof: proven.range_proof,
+            fee_proofs: proven.fee_proofs,
+        });
+    }
+
+    let mut accounts = vault::accounts::BatchTransferConfidential {
+        sender: *sender,
+        sender_account: *sender_account,
+        mint: *mint,
+        credit_policy: credit_policy_address(sender_account),
+        token_program: spl_token_2022::id(),
+    }
+    .to_account_metas(None);
+    accounts.extend(remaining_accounts);
+
+    Ok(BatchTransferInstructions {
+        proofs: proof_accounts,
+        compute_budget: ComputeBudgetInstruction::set_compute_unit_limit(
+            batch_transfer_compute_units(recipients.len(), fee.is_some()),
+        ),
+        transfer: Instruction {
+            program_id: vault::ID,
+            accounts,
+            data: vault::instruction::BatchTransferConfidential { transfers: entries }.data(),
+        },
+    })
+}
+
+pub struct WithdrawInstructions {
+    pub proof: ProofContextAccount,
+    pub withdraw: Instruction,
+}
+
+impl WithdrawInstructions {
+    pub fn transactions(&self) -> Vec<Vec<Instruction>> {
+        vec![
+            vec![self.proof.create.clone()],
+            vec![self.proof.verify.clone()],
+            vec![self.withdraw.clone(), self.proof.close.clone()],
+        ]
+    }
+}
+
+/// Move confidential available balance back to the public balance
+pub fn withdraw_confidential(
+    authority: &Pubkey,
+    confidential_account: &Pubkey,
+    state: &ConfidentialTransferAccount,
+    mint: &Pubkey,
+    keys: &ConfidentialKeys,
+    amount: u64,
+    rent: &Rent,
+) -> Result<WithdrawInstructions> {
+    let withdraw_proof = proofs::withdraw_proof(state, keys, amount)?;
+    let proof = ProofContextAccount::new(
+        authority,
+        authority,
+        ProofInstruction::VerifyWithdraw,
+        &withdraw_proof.proof,
+        rent,
+    );
+
+    let withdraw = Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::WithdrawConfidential {
+            authority: *authority,
+            confidential_account: *confidential_account,
+            mint: *mint,
+            proof_account: proof.pubkey(),
+            token_program: spl_token_2022::id(),
+        }
+        .to_account_metas(None),
+        data: vault::instruction::WithdrawConfidential {
+            amount,
+            new_decryptable_available_balance: withdraw_proof.new_decryptable_available_balance,
+            proof_instruction_offset: 0,
+        }
+        .data(),
+    };
+
+    Ok(WithdrawInstructions { proof, withdraw })
+}
+
+/// Empty and close a confidential account, returning its rent to `user`
+///
+/// Apply the pending balance and withdraw everything first. The zero
+/// balance proof goes inline right after the program instruction.
+pub fn close_confidential_account(
+    user: &Pubkey,
+    confidential_account: &Pubkey,
+    state: &ConfidentialTransferAccount,
+    keys: &ConfidentialKeys,
+) -> Result<Vec<Instruction>> {
+    let proof = proofs::empty_account_proof(state, keys)?;
+    let close = Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::CloseConfidentialAccount {
+            user: *user,
+            confidential_account: *confidential_account,
+            proof_account: sysvar::instructions::id(),
+            token_program: spl_token_2022::id(),
+        }
+        .to_account_metas(None),
+        data: vault::instruction::CloseConfidentialAccount {
+            proof_instruction_offset: 1,
+        }
+        .data(),
+    };
+    Ok(vec![close, verify_zero_balance(None, &proof)])
+}
+
+pub fn invoice_address(payee: &Pubkey, invoice_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[INVOICE_SEED, payee.as_ref(), &invoice_id.to_le_bytes()],
+        &vault::ID,
+    )
+    .0
+}
+
+/// Invoice `payer_account` for `amount`, encrypted under the payer's key
+#[allow(clippy::too_many_arguments)]
+pub fn create_invoice(
+    payee: &Pubkey,
+    payee_account: &Pubkey,
+    payer_account: &Pubkey,
+    payer_elgamal_pubkey: &pod::ElGamalPubkey,
+    mint: &Pubkey,
+    invoice_id: u64,
+    amount: u64,
+    expires_at: i64,
+    memo: Option<ConfidentialMemo>,
+) -> Result<Instruction> {
+    Ok(Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::CreateInvoice {
+            payee: *payee,
+            invoice: invoice_address(payee, invoice_id),
+            payee_account: *payee_account,
+            payer_account: *payer_account,
+            mint: *mint,
+            token_program: spl_token_2022::id(),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: vault::instruction::CreateInvoice {
+            invoice_id,
+            amount_ciphertext: proofs::encrypt_invoice_amount(amount, payer_elgamal_pubkey)?,
+            expires_at,
+            memo,
+        }
+        .data(),
+    })
+}
+
+pub struct InvoicePaymentInstructions {
+    pub equality_proof: ProofContextAccount,
+    pub ciphertext_validity_proof: ProofContextAccount,
+    /// BatchedRangeProofU128, or BatchedRangeProofU256 with fees
+    pub range_proof: ProofContextAccount,
+    /// Only on mints with transfer fees
+    pub fee_proofs: Option<FeeProofAccounts>,
+    /// `pay_invoice` followed by the inline zero balance proof
+    pub pay: Vec<Instruction>,
+}
+
+impl InvoicePaymentInstructions {
+    pub fn transactions(&self) -> Vec<Vec<Instruction>> {
+        let mut transactions = vec![
+            vec![
+                self.equality_proof.create.clone(),
+                self.equality_proof.verify.clone(),
+            ],
+            vec![
+                self.ciphertext_validity_proof.create.clone(),
+                self.ciphertext_validity_proof.verify.clone(),
+            ],
+        ];
+        let mut close = vec![
+            self.equality_proof.close.clone(),
+            self.ciphertext_validity_proof.close.clone(),
+            self.range_proof.close.clone(),
+        ];
+        if let Some(fee_proofs) = &self.fee_proofs {
+            transactions.extend(fee_proofs.verify_transactions());
+            close.extend(fee_proofs.close());
+        }
+        transactions.push(vec![self.range_proof.create.clone()]);
+        transactions.push(range_proof_verify_transaction(
+            &self.range_proof,
+            self.fee_proofs.is_some(),
+        ));
+        transactions.push(self.pay.clone());
+        transactions.push(close);
+        transactions
+    }
+}
+
+/// Pay `amount` against an invoice, on a mint without transfer fees
+///
+/// Fails with `InvoiceAmountMismatch` if the invoice is for a different
+/// amount. `sender_account` must be the invoice's `payer_account`.
+/// On a mint with the allowlist hook, extend the first instruction of `pay`
+/// with [`allowlist_hook_accounts`].
+#[allow(clippy::too_many_arguments)]
+pub fn pay_invoice(
+    sender: &Pubkey,
+    sender_account: &Pubkey,
+    sender_state: &ConfidentialTransferAccount,
+    invoice_address: &Pubkey,
+    invoice: &Invoice,
+    payee_elgamal_pubkey: &pod::ElGamalPubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+    keys: &ConfidentialKeys,
+    amount: u64,
+    rent: &Rent,
+) -> Result<InvoicePaymentInstructions> {
+    let proofs = proofs::invoice_payment_proofs(
+        sender_state,
+        keys,
+        &invoice.amount_ciphertext,
+        amount,
+        payee_elgamal_pubkey,
+        auditor_elgamal_pubkey,
+    )?;
+    Ok(invoice_payment_instructions(
+        sender,
+        sender_account,
+        invoice_address,
+        invoice,
+        ProvenTransfer::new(sender, &proofs.transfer, rent),
+        &proofs.zero_balance,
+    ))
+}
+
+/// [`pay_invoice`] on a mint with transfer fees, `fee` being the mint's
+/// current fee (see [`proofs::mint_transfer_fee`])
+///
+/// The invoiced amount is what the payer sends, the payee is credited it
+/// less the fee.
+#[allow(clippy::too_many_arguments)]
+pub fn pay_invoice_with_fee(
+    sender: &Pubkey,
+    sender_account: &Pubkey,
+    sender_state: &ConfidentialTransferAccount,
+    invoice_address: &Pubkey,
+    invoice: &Invoice,
+    payee_elgamal_pubkey: &pod::ElGamalPubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+    keys: &ConfidentialKeys,
+    amount: u64,
+    fee: &FeeProofParams,
+    rent: &Rent,
+) -> Result<InvoicePaymentInstructions> {
+    let proofs = proofs::invoice_payment_with_fee_proofs(
+        sender_state,
+        keys,
+        &invoice.amount_ciphertext,
+        amount,
+        payee_elgamal_pubkey,
+        auditor_elgamal_pubkey,
+        fee,
+    )?;
+    Ok(invoice_payment_instructions(
+        sender,
+        sender_account,
+        invoice_address,
+        invoice,
+        ProvenTransfer::with_fee(sender, &proofs.transfer, rent),
+        &proofs.zero_balance,
+    ))
+}
+
+fn invoice_payment_instructions(
+    sender: &Pubkey,
+    sender_account: &Pubkey,
+    invoice_address: &Pubkey,
+    invoice: &Invoice,
+    proven: ProvenTransfer,
+    zero_balance: &ZeroBalanceProofData,
+) -> InvoicePaymentInstructions {
+    let pay = Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::PayInvoice {
+            sender: *sender,
+            sender_account: *sender_account,
+            recipient_account: invoice.payee_account,
+            mint: invoice.mint,
+            invoice: *invoice_address,
+            payee: invoice.payee,
+            equality_proof: proven.equality_proof.pubkey(),
+            ciphertext_validity_proof: proven.ciphertext_validity_proof.pubkey(),
+            fee_sigma_proof: proven.fee_sigma_proof(),
+            fee_ciphertext_validity_proof: proven.fee_ciphertext_validity_proof(),
+            range_proof: proven.range_proof.pubkey(),
+            proof_account: sysvar::instructions::id(),
+            token_program: spl_token_2022::id(),
+        }
+        .to_account_metas(None),
+        data: vault::instruction::PayInvoice {
+            new_source_decryptable_available_balance: proven
+                .new_source_decryptable_available_balance,
+            source_decrypt_handles: proven.source_decrypt_handles,
+            proof_instruction_offset: 1,
+        }
+        .data(),
+    };
+
+    InvoicePaymentInstructions {
+        equality_proof: proven.equality_proof,
+        ciphertext_validity_proof: proven.ciphertext_validity_proof,
+        range_proof: proven.range_proof,
+        fee_proofs: proven.fee_proofs,
+        pay: vec![pay, verify_zero_balance(None, zero_balance)],
+    }
+}
+
+/// Close an invoice past its expiry, returning the rent to its payee
+pub fn close_expired_invoice(invoice_address: &Pubkey, invoice: &Invoice) -> Instruction {
+    Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::CloseExpiredInvoice {
+            invoice: *invoice_address,
+            payee: invoice.payee,
+        }
+        .to_account_metas(None),
+        data: vault::instruction::CloseExpiredInvoice {}.data(),
+    }
+}
+
+/// Addresses of a sender's stream and of its escrow
+pub fn stream_addresses(sender: &Pubkey, stream_id: u64) -> (Pubkey, Pubkey) {
+    let (stream, _) = Pubkey::find_program_address(
+        &[STREAM_SEED, sender.as_ref(), &stream_id.to_le_bytes()],
+        &vault::ID,
+    );
+    let (escrow, _) =
+        Pubkey::find_program_address(&[STREAM_ESCROW_SEED, stream.as_ref()], &vault::ID);
+    (stream, escrow)
+}
+
+pub struct StreamInstructions {
+    pub equality_proof: ProofContextAccount,
+    pub ciphertext_validity_proof: ProofContextAccount,
+    pub range_proof: ProofContextAccount,
+    /// Allowance equality and range proofs, for claims and refunds
+    pub allowance_proofs: Option<(ProofContextAccount, ProofContextAccount)>,
+    /// The stream instruction, followed by the inline escrow key proof for
+    /// `create_stream`
+    pub stream: Vec<Instruction>,
+}
+
+impl StreamInstructions {
+    pub fn transactions(&self) -> Vec<Vec<Instruction>> {
+        let mut proofs = vec![&self.equality_proof, &self.ciphertext_validity_proof];
+        let mut transactions = vec![
+            vec![
+                self.equality_proof.create.clone(),
+                self.equality_proof.verify.clone(),
+            ],
+            vec![
+                self.ciphertext_validity_proof.create.clone(),
+                self.ciphertext_validity_proof.verify.clone(),
+            ],
+            vec![self.range_proof.create.clone()],
+            vec![self.range_proof.verify.clone()],
+        ];
+        proofs.push(&self.range_proof);
+        if let Some((equality_proof, range_proof)) = &self.allowance_proofs {
+            transactions.push(vec![
+                equality_proof.create.clone(),
+                equality_proof.verify.clone(),
+            ]);
+            transactions.push(vec![range_proof.create.clone()]);
+            transactions.push(vec![range_proof.verify.clone()]);
+            proofs.extend([equality_proof, range_proof]);
+        }
+        transactions.push(self.stream.clone());
+        transactions.push(proofs.iter().map(|proof| proof.close.clone()).collect());
+        transactions
+    }
+}
+
+/// Lock `secret.total` in a new stream for `recipient`, on a mint without
+/// transfer fees
+///
+/// The escrow keys come from `secret` ([`StreamSecret::new`]), which is
+/// published sealed to the recipient's confidential account key.
+#[allow(clippy::too_many_arguments)]
+pub fn create_stream(
+    sender: &Pubkey,
+    sender_account: &Pubkey,
+    sender_state: &ConfidentialTransferAccount,
+    recipient: &Pubkey,
+    recipient_elgamal_pubkey: &pod::ElGamalPubkey,
+    mint: &Pubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+    keys: &ConfidentialKeys,
+    schedule: StreamSchedule,
+    secret: &StreamSecret,
+    rent: &Rent,
+) -> Result<StreamInstructions> {
+    let escrow_keys = secret.escrow_keys()?;
+    let escrow_proof = proofs::configure_account_proof(&escrow_keys)?;
+    let proofs = proofs::transfer_proofs(
+        sender_state,
+        keys,
+        secret.total,
+        &escrow_keys.elgamal_pubkey(),
+        auditor_elgamal_pubkey,
+    )?;
+    let stream_secret =
+        stream::encrypt_stream_secret(secret, recipient_elgamal_pubkey, auditor_elgamal_pubkey)?;
+
+    let (equality_proof, ciphertext_validity_proof, range_proof) =
+        transfer_proof_accounts(sender, &proofs, rent);
+    let (stream, escrow) = stream_addresses(sender, schedule.stream_id);
+
+    let create = Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::CreateStream {
+            sender: *sender,
+            sender_account: *sender_account,
+            recipient: *recipient,
+            mint: *mint,
+            stream,
+            escrow,
+            escrow_proof_account: sysvar::instructions::id(),
+            equality_proof: equality_proof.pubkey(),
+            ciphertext_validity_proof: ciphertext_validity_proof.pubkey(),
+            range_proof: range_proof.pubkey(),
+            token_program: spl_token_2022::id(),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: vault::instruction::CreateStream {
+            schedule,
+            escrow_elgamal_pubkey: escrow_keys.elgamal_pubkey().0,
+            escrow_decryptable_zero_balance: escrow_proof.decryptable_zero_balance,
+            escrow_proof_instruction_offset: 1,
+            new_source_decryptable_available_balance: proofs
+                .new_source_decryptable_available_balance,
+            source_decrypt_handles: proofs.source_decrypt_handles,
+            escrow_decryptable_available_balance: pod::AeCiphertext::from(
+                escrow_keys.ae.encrypt(secret.total),
+            )
+            .0,
+            stream_secret,
+        }
+        .data(),
+    };
+
+    Ok(StreamInstructions {
+        equality_proof,
+        ciphertext_validity_proof,
+        range_proof,
+        allowance_proofs: None,
+        stream: vec![create, verify_pubkey_validity(None, &escrow_proof.proof)],
+    })
+}
+
+/// Claim `amount` of the vested share of a stream
+///
+/// `vested_periods` is [`Stream::vested_periods`] at the time the claim is
+/// expected to land, `escrow_state` the escrow's confidential transfer
+/// extension.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_from_stream(
+    recipient: &Pubkey,
+    recipient_account: &Pubkey,
+    recipient_elgamal_pubkey: &pod::ElGamalPubkey,
+    stream_address: &Pubkey,
+    stream: &Stream,
+    escrow_state: &ConfidentialTransferAccount,
+    secret: &StreamSecret,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+    vested_periods: u16,
+    amount: u64,
+    rent: &Rent,
+) -> Result<StreamInstructions> {
+    let proofs = stream::claim_proofs(
+        stream,
+        escrow_state,
+        secret,
+        vested_periods,
+        amount,
+        recipient_elgamal_pubkey,
+        auditor_elgamal_pubkey,
+    )?;
+    let (equality_proof, ciphertext_validity_proof, range_proof) =
+        transfer_proof_accounts(recipient, &proofs.transfer, rent);
+    let (allowance_equality_proof, allowance_range_proof) =
+        allowance_proof_accounts(recipient, &proofs.allowance, rent);
+
+    let withdraw = Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::WithdrawFromStream {
+            recipient: *recipient,
+            stream: *stream_address,
+            escrow: stream.escrow,
+            recipient_account: *recipient_account,
+            mint: stream.mint,
+            equality_proof: equality_proof.pubkey(),
+            ciphertext_validity_proof: ciphertext_validity_proof.pubkey(),
+            range_proof: range_proof.pubkey(),
+            allowance_equality_proof: allowance_equality_proof.pubkey(),
+            allowance_range_proof: allowance_range_proof.pubkey(),
+            token_program: spl_token_2022::id(),
+        }
+        .to_account_metas(None),
+        data: vault::instruction::WithdrawFromStream {
+            new_escrow_decryptable_available_balance: proofs
+                .transfer
+                .new_source_decryptable_available_balance,
+            source_decrypt_handles: proofs.transfer.source_decrypt_handles,
+            new_decryptable_withdrawn_amount: proofs.new_decryptable_withdrawn_amount,
+        }
+        .data(),
+    };
+
+    Ok(StreamInstructions {
+        equality_proof,
+        ciphertext_validity_proof,
+        range_proof,
+        allowance_proofs: Some((allowance_equality_proof, allowance_range_proof)),
+        stream: vec![withdraw],
+    })
+}
+
+/// Cancel a stream and refund `amount` of the unvested share to the sender
+#[allow(clippy::too_many_arguments)]
+pub fn cancel_stream(
+    sender: &Pubkey,
+    sender_account: &Pubkey,
+    sender_elgamal_pubkey: &pod::ElGamalPubkey,
+    stream_address: &Pubkey,
+    stream: &Stream,
+    escrow_state: &ConfidentialTransferAccount,
+    secret: &StreamSecret,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+    vested_periods: u16,
+    amount: u64,
+    rent: &Rent,
+) -> Result<StreamInstructions> {
+    let proofs = stream::refund_proofs(
+        stream,
+        escrow_state,
+        secret,
+        vested_periods,
+        amount,
+        sender_elgamal_pubkey,
+        auditor_elgamal_pubkey,
+    )?;
+    let (equality_proof, ciphertext_validity_proof, range_proof) =
+        transfer_proof_accounts(sender, &proofs.transfer, rent);
+    let (allowance_equality_proof, allowance_range_proof) =
+        allowance_proof_accounts(sender, &proofs.allowance, rent);
+
+    let cancel = Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::CancelStream {
+            sender: *sender,
+            stream: *stream_address,
+            escrow: stream.escrow,
+            sender_account: *sender_account,
+            mint: stream.mint,
+            equality_proof: equality_proof.pubkey(),
+            ciphertext_validity_proof: ciphertext_validity_proof.pubkey(),
+            range_proof: range_proof.pubkey(),
+            allowance_equality_proof: allowance_equality_proof.pubkey(),
+            allowance_range_proof: allowance_range_proof.pubkey(),
+            token_program: spl_token_2022::id(),
+        }
+        .to_account_metas(None),
+        data: vault::instruction::CancelStream {
+            new_escrow_decryptable_available_balance: proofs
+                .transfer
+                .new_source_decryptable_available_balance,
+            source_decrypt_handles: proofs.transfer.source_decrypt_handles,
+        }
+        .data(),
+    };
+
+    Ok(StreamInstructions {
+        equality_proof,
+        ciphertext_validity_proof,
+        range_proof,
+        allowance_proofs: Some((allowance_equality_proof, allowance_range_proof)),
+        stream: vec![cancel],
+    })
+}
+
+/// Close a canceled or fully vested stream once its escrow is empty,
+/// returning the rent of the stream and the escrow to the sender
+///
+/// The zero balance proof, made with the escrow keys, goes inline right
+/// after the program instruction.
+pub fn close_stream(
+    stream_address: &Pubkey,
+    stream: &Stream,
+    escrow_state: &ConfidentialTransferAccount,
+    secret: &StreamSecret,
+) -> Result<Vec<Instruction>> {
+    let proof = proofs::empty_account_proof(escrow_state, &secret.escrow_keys()?)?;
+    let close = Instruction {
+        program_id: vault::ID,
+        accounts: vault::accounts::CloseStream {
+            sender: stream.sender,
+            stream: *stream_address,
+            escrow: stream.escrow,
+            proof_account: sysvar::instructions::id(),
+            token_program: spl_token_2022::id(),
+        }
+        .to_account_metas(None),
+        data: vault::instruction::CloseStream {
+            proof_instruction_offset: 1,
+        }
+        .data(),
+    };
+    Ok(vec![close, verify_zero_balance(None, &proof)])
+}
+
+fn allowance_proof_accounts(
+    authority: &Pubkey,
+    proofs: &AllowanceProofs,
+    rent: &Rent,
+) -> (ProofContextAccount, ProofContextAccount) {
+    let equality_proof = ProofContextAccount::new(
+        authority,
+        authority,
+        ProofInstruction::VerifyCiphertextCommitmentEquality,
+        &proofs.equality,
+        rent,
+    );
+    let range_proof = ProofContextAccount::new(
+        authority,
+        authority,
+        ProofInstruction::VerifyBatchedRangeProofU64,
+        &proofs.range,
+        rent,
+    );
+    (equality_proof, range_proof)
+}
+
+/// Context state accounts for the split proofs of a transfer, paid for and
+/// closed by `sender`
+fn transfer_proof_accounts(
+    sender: &Pubkey,
+    proofs: &proofs::TransferProofs,
+    rent: &Rent,
+) -> (
+    ProofContextAccount,
+    ProofContextAccount,
+    ProofContextAccount,
+) {
+    let equality_proof = ProofContextAccount::new(
+        sender,
+        sender,
+        ProofInstruction::VerifyCiphertextCommitmentEquality,
+        &proofs.equality,
+        rent,
+    );
+    let ciphertext_validity_proof = ProofContextAccount::new(
+        sender,
+        sender,
+        ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity,
+        &proofs.ciphertext_validity,
+        rent,
+    );
+    let range_proof = ProofContextAccount::new(
+        sender,
+        sender,
+        ProofInstruction::VerifyBatchedRangeProofU128,
+        &proofs.range,
+        rent,
+    );
+    (equality_proof, ciphertext_validity_proof, range_proof)
+}
+
+/// Context state accounts of a transfer's proofs, with the program inputs
+/// that go along with them
+struct ProvenTransfer {
+    equality_proof: ProofContextAccount,
+    ciphertext_validity_proof: ProofContextAccount,
+    range_proof: ProofContextAccount,
+    fee_proofs: Option<FeeProofAccounts>,
+    source_decrypt_handles: [u8; 64],
+    new_source_decryptable_available_balance: [u8; 36],
+    /// Sender's available balance once the transfer lands
+    new_available_balance: pod::ElGamalCiphertext,
+}
+
+impl ProvenTransfer {
+    fn new(sender: &Pubkey, proofs: &proofs::TransferProofs, rent: &Rent) -> Self {
+        let (equality_proof, ciphertext_validity_proof, range_proof) =
+            transfer_proof_accounts(sender, proofs, rent);
+        Self {
+            equality_proof,
+            ciphertext_validity_proof,
+            range_proof,
+            fee_proofs: None,
+            source_decrypt_handles: proofs.source_decrypt_handles,
+            new_source_decryptable_available_balance: proofs
+                .new_source_decryptable_available_balance,
+            new_available_balance: proofs.equality.context_data().ciphertext,
+        }
+    }
+
+    fn with_fee(sender: &Pubkey, proofs: &proofs::TransferWithFeeProofs, rent: &Rent) -> Self {
+        let equality_proof = ProofContextAccount::new(
+            sender,
+            sender,
+            ProofInstruction::VerifyCiphertextCommitmentEquality,
+            &proofs.equality,
+            rent,
+        );
+        let ciphertext_validity_proof = ProofContextAccount::new(
+            sender,
+            sender,
+            ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity,
+            &proofs.ciphertext_validity,
+            rent,
+        );
+        let range_proof = ProofContextAccount::new(
+            sender,
+            sender,
+            ProofInstruction::VerifyBatchedRangeProofU256,
+            &proofs.range,
+            rent,
+        );
+        let fee_sigma_proof = ProofContextAccount::new(
+            sender,
+            sender,
+            ProofInstruction::VerifyFeeSigma,
+            &proofs.fee_sigma,
+            rent,
+        );
+        let fee_ciphertext_validity_proof = ProofContextAccount::new(
+            sender,
+            sender,
+            ProofInstruction::VerifyBatchedGroupedCiphertext2HandlesValidity,
+            &proofs.fee_ciphertext_validity,
+            rent,
+        );
+        Self {
+            equality_proof,
+            ciphertext_validity_proof,
+            range_proof,
+            fee_proofs: Some(FeeProofAccounts {
+                fee_sigma_proof,
+                fee_ciphertext_validity_proof,
+            }),
+            source_decrypt_handles: proofs.source_decrypt_handles,
+            new_source_decryptable_available_balance: proofs
+                .new_source_decryptable_available_balance,
+            new_available_balance: proofs.equality.context_data().ciphertext,
+        }
+    }
+
+    /// Prove a transfer of `amount` out of `state`, with the fee proofs when
+    /// the mint has a transfer `fee`
+    #[allow(clippy::too_many_arguments)]
+    fn prove(
+        sender: &Pubkey,
+        state: &ConfidentialTransferAccount,
+        keys: &ConfidentialKeys,
+        amount: u64,
+        recipient_elgamal_pubkey: &pod::ElGamalPubkey,
+        auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+        fee: Option<&FeeProofParams>,
+        rent: &Rent,
+    ) -> Result<Self> {
+        Ok(match fee {
+            None => Self::new(
+                sender,
+                &proofs::transfer_proofs(
+                    state,
+                    keys,
+                    amount,
+                    recipient_elgamal_pubkey,
+                    auditor_elgamal_pubkey,
+                )?,
+                rent,
+            ),
+            Some(fee) => Self::with_fee(
+                sender,
+                &proofs::transfer_with_fee_proofs(
+                    state,
+                    keys,
+                    amount,
+                    recipient_elgamal_pubkey,
+                    auditor_elgamal_pubkey,
+                    fee,
+                )?,
+                rent,
+            ),
+        })
+    }
+
+    fn fee_sigma_proof(&self) -> Option<Pubkey> {
+        self.fee_proofs
+            .as_ref()
+            .map(|fee_proofs| fee_proofs.fee_sigma_proof.pubkey())
+    }
+
+    fn fee_ciphertext_validity_proof(&self) -> Option<Pubkey> {
+        self.fee_proofs
+            .as_ref()
+            .map(|fee_proofs| fee_proofs.fee_ciphertext_validity_proof.pubkey())
+    }
+}
@@ -0,0 +1,67 @@
+//! Deterministic confidential transfer keys.
+//!
+//! Keys are derived per token account from a wallet signature, the same way
+//! the spl-token CLI does it, so a user never stores them: signing the same
+//! message with the same wallet always gives the same keys back. Browser
+//! wallets that only expose `signMessage` can sign [`elgamal_message`] and
+//! [`ae_message`] and hand the signatures to [`ConfidentialKeys::from_signatures`].
+
+use sha3::{Digest, Sha3_512};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::{SeedDerivable, Signer};
+use spl_token_2022::solana_zk_token_sdk::encryption::auth_encryption::AeKey;
+use spl_token_2022::solana_zk_token_sdk::encryption::elgamal::ElGamalKeypair;
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::pod;
+
+use crate::error::{ClientError, Result};
+
+/// Message the wallet signs to derive the ElGamal key of `token_account`
+pub fn elgamal_message(token_account: &Pubkey) -> Vec<u8> {
+    [b"ElGamalSecretKey".as_slice(), token_account.as_ref()].concat()
+}
+
+/// Message the wallet signs to derive the AE key of `token_account`
+pub fn ae_message(token_account: &Pubkey) -> Vec<u8> {
+    [b"AeKey".as_slice(), token_account.as_ref()].concat()
+}
+
+/// ElGamal keypair (encrypted balances, proofs) and AE key (the owner's fast
+/// decryptable copy of the available balance) for one token account
+pub struct ConfidentialKeys {
+    pub elgamal: ElGamalKeypair,
+    pub ae: AeKey,
+}
+
+impl ConfidentialKeys {
+    /// Derive the keys of `token_account` by signing with `signer`
+    pub fn from_signer(signer: &dyn Signer, token_account: &Pubkey) -> Result<Self> {
+        let elgamal_signature = signer.try_sign_message(&elgamal_message(token_account))?;
+        let ae_signature = signer.try_sign_message(&ae_message(token_account))?;
+        Self::from_signatures(&elgamal_signature, &ae_signature)
+    }
+
+    /// Derive the keys from signatures over [`elgamal_message`] and
+    /// [`ae_message`] made by the token account owner
+    pub fn from_signatures(elgamal_signature: &Signature, ae_signature: &Signature) -> Result<Self> {
+        let elgamal = ElGamalKeypair::from_seed(&signature_seed(elgamal_signature)?)
+            .map_err(|_| ClientError::KeyDerivation)?;
+        let ae = AeKey::from_seed(&signature_seed(ae_signature)?)
+            .map_err(|_| ClientError::KeyDerivation)?;
+        Ok(Self { elgamal, ae })
+    }
+
+    /// ElGamal public key in the form the program instructions take
+    pub fn elgamal_pubkey(&self) -> pod::ElGamalPubkey {
+        pod::ElGamalPubkey::from(*self.elgamal.pubkey())
+    }
+}
+
+/// Some wallets return the default signature instead of failing, which is
+/// not suitable as key material
+fn signature_seed(signature: &Signature) -> Result<Vec<u8>> {
+    if *signature == Signature::default() {
+        return Err(ClientError::InvalidSignature);
+    }
+    Ok(Sha3_512::digest(signature.as_ref()).to_vec())
+}
@@ -0,0 +1,28 @@
+//! Off-chain client for Spectre confidential payments.
+//!
+//! Derives the confidential transfer keys of a token account from a wallet
+//! signature, decrypts its balances and transfer memos, generates the
+//! zero-knowledge proofs the `privacy_payments` instructions check, and builds
+//! the instructions ready to sign. Auditors can decrypt the transfer history
+//! of a mint from exported transactions with the `spectre-audit` binary.
+//! Streams of vesting payments are covered by the `stream` module, invoices
+//! by the invoice builders in `instructions`.
+//!
+//! Mints with a transfer fee need the transfer-with-fee proofs: read the fee
+//! with `proofs::mint_transfer_fee` and use the `_with_fee` builders.
+
+pub mod audit;
+pub mod balance;
+pub mod error;
+pub mod instructions;
+pub mod keys;
+pub mod memo;
+pub mod proofs;
+pub mod stream;
+
+pub use balance::{decrypt_balance, BalanceWarning, ConfidentialBalance};
+pub use error::{ClientError, Result};
+pub use keys::ConfidentialKeys;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,99 @@
+//! Encrypted memos for confidential transfers.
+//!
+//! A memo is sealed under a one-time key derived from a fresh Pedersen
+//! opening `r`: the key material is `r·H`, and each reader gets an ElGamal
+//! decrypt handle `r·P` for their public key `P = s⁻¹·H`. Multiplying the
+//! handle by the ElGamal secret `s` gives `r·H` back, so the recipient and
+//! the auditor open the memo with the keys they already hold for balances
+//! and transfer amounts. Since the key is never reused, the AES-GCM-SIV
+//! nonce is fixed.
+
+use aes_gcm_siv::aead::generic_array::GenericArray;
+use aes_gcm_siv::aead::{Aead, NewAead};
+use aes_gcm_siv::Aes256GcmSiv;
+use sha3::{Digest, Sha3_256};
+use spl_token_2022::solana_zk_token_sdk::encryption::elgamal::{
+    DecryptHandle, ElGamalPubkey, ElGamalSecretKey,
+};
+use spl_token_2022::solana_zk_token_sdk::encryption::pedersen::{PedersenOpening, H};
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::pod;
+use vault::{ConfidentialMemo, MAX_MEMO_CIPHERTEXT_LEN};
+
+use crate::error::{ClientError, Result};
+use crate::keys::ConfidentialKeys;
+
+const MEMO_KEY_DOMAIN: &[u8] = b"SpectreConfidentialMemo";
+const MEMO_NONCE: [u8; 12] = [0u8; 12];
+const MEMO_TAG_LEN: usize = 16;
+
+/// Longest memo plaintext that fits in a transfer
+pub const MAX_MEMO_LEN: usize = MAX_MEMO_CIPHERTEXT_LEN - MEMO_TAG_LEN;
+
+/// Encrypt `memo` to the recipient and, optionally, the mint's auditor
+pub fn encrypt_memo(
+    memo: &[u8],
+    recipient_elgamal_pubkey: &pod::ElGamalPubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+) -> Result<ConfidentialMemo> {
+    if memo.is_empty() || memo.len() > MAX_MEMO_LEN {
+        return Err(ClientError::InvalidMemo);
+    }
+
+    let opening = PedersenOpening::new_rand();
+    let handle = |pubkey: &pod::ElGamalPubkey| -> Result<[u8; 32]> {
+        let pubkey =
+            ElGamalPubkey::try_from(*pubkey).map_err(|_| ClientError::MalformedCiphertext)?;
+        Ok(pubkey.decrypt_handle(&opening).to_bytes())
+    };
+    let recipient_handle = handle(recipient_elgamal_pubkey)?;
+    let auditor_handle = auditor_elgamal_pubkey.map(handle).transpose()?;
+
+    let shared = (opening.get_scalar() * *H).compress().to_bytes();
+    let ciphertext = cipher(&shared)
+        .encrypt(GenericArray::from_slice(&MEMO_NONCE), memo)
+        .map_err(|_| ClientError::InvalidMemo)?;
+
+    Ok(ConfidentialMemo {
+        recipient_handle,
+        auditor_handle,
+        ciphertext,
+    })
+}
+
+/// Decrypt a memo received on the account `keys` belong to
+pub fn decrypt_memo(memo: &ConfidentialMemo, keys: &ConfidentialKeys) -> Result<Vec<u8>> {
+    open(memo, &memo.recipient_handle, keys.elgamal.secret())
+}
+
+/// Decrypt a memo with the mint's auditor key
+pub fn decrypt_memo_as_auditor(
+    memo: &ConfidentialMemo,
+    auditor_secret: &ElGamalSecretKey,
+) -> Result<Vec<u8>> {
+    let handle = memo
+        .auditor_handle
+        .as_ref()
+        .ok_or(ClientError::Decryption)?;
+    open(memo, handle, auditor_secret)
+}
+
+fn open(memo: &ConfidentialMemo, handle: &[u8; 32], secret: &ElGamalSecretKey) -> Result<Vec<u8>> {
+    let handle = DecryptHandle::from_bytes(handle).ok_or(ClientError::MalformedCiphertext)?;
+    let shared = (secret.get_scalar() * handle.get_point())
+        .compress()
+        .to_bytes();
+    cipher(&shared)
+        .decrypt(
+            GenericArray::from_slice(&MEMO_NONCE),
+            memo.ciphertext.as_slice(),
+        )
+        .map_err(|_| ClientError::Decryption)
+}
+
+fn cipher(shared: &[u8; 32]) -> Aes256GcmSiv {
+    let key = Sha3_256::new()
+        .chain(MEMO_KEY_DOMAIN)
+        .chain(shared)
+        .finalize();
+    Aes256GcmSiv::new(&key)
+}
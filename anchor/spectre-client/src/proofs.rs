@@ -0,0 +1,561 @@
+//! Zero-knowledge proof generation for the `privacy_payments` instructions.
+//!
+//! Proofs are generated against the account state as it is on chain, so
+//! callers fetch the token account (and mint) data first and pass the raw
+//! bytes to [`account_state`] / [`mint_auditor`].
+
+use bytemuck::Zeroable;
+use curve25519_dalek::scalar::Scalar;
+use spl_token_2022::extension::confidential_transfer::account_info::{
+    ApplyPendingBalanceAccountInfo, EmptyAccountAccountInfo, TransferAccountInfo,
+    WithdrawAccountInfo,
+};
+use spl_token_2022::extension::confidential_transfer::ciphertext_extraction::SourceDecryptHandles;
+use spl_token_2022::extension::confidential_transfer::{
+    ConfidentialTransferAccount, ConfidentialTransferMint,
+};
+use spl_token_2022::extension::confidential_transfer_fee::ConfidentialTransferFeeConfig;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::solana_zk_token_sdk::encryption::elgamal::{
+    DecryptHandle, ElGamalCiphertext, ElGamalPubkey,
+};
+use spl_token_2022::solana_zk_token_sdk::encryption::grouped_elgamal::GroupedElGamal;
+use spl_token_2022::solana_zk_token_sdk::encryption::pedersen::{Pedersen, PedersenOpening};
+use spl_token_2022::solana_zk_token_sdk::instruction::transfer::{
+    combine_lo_hi_commitments, combine_lo_hi_openings, split_u64, TransferAmountCiphertext,
+};
+use spl_token_2022::solana_zk_token_sdk::instruction::{
+    BatchedGroupedCiphertext2HandlesValidityProofData, BatchedRangeProofU128Data,
+    BatchedRangeProofU256Data, CiphertextCommitmentEqualityProofData, FeeSigmaProofData,
+    PubkeyValidityData, WithdrawData, ZeroBalanceProofData, ZkProofData,
+};
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::{ops, pod};
+use spl_token_2022::state::{Account, Mint};
+
+use crate::balance;
+use crate::error::{ClientError, Result};
+use crate::keys::ConfidentialKeys;
+
+// Bit lengths of the transfer with fee range proof, as Token-2022 checks them
+const AMOUNT_LO_BITS: usize = 16;
+const AMOUNT_HI_BITS: usize = 32;
+const REMAINING_BALANCE_BITS: usize = 64;
+const DELTA_BITS: usize = 48;
+const MAX_FEE_BASIS_POINTS: u64 = 10_000;
+
+/// Reads the ConfidentialTransferAccount extension from token account data
+pub fn account_state(data: &[u8]) -> Result<ConfidentialTransferAccount> {
+    let account = StateWithExtensions::<Account>::unpack(data)?;
+    account
+        .get_extension::<ConfidentialTransferAccount>()
+        .copied()
+        .map_err(|_| ClientError::AccountNotConfigured)
+}
+
+/// Reads the auditor ElGamal key from mint data, if the mint has one
+pub fn mint_auditor(data: &[u8]) -> Result<Option<pod::ElGamalPubkey>> {
+    let mint = StateWithExtensions::<Mint>::unpack(data)?;
+    let extension = mint
+        .get_extension::<ConfidentialTransferMint>()
+        .map_err(|_| ClientError::MintNotConfigured)?;
+    Ok(extension.auditor_elgamal_pubkey.into())
+}
+
+/// Transfer fee of a mint, as the sender proves it
+#[derive(Clone, Copy, Debug)]
+pub struct FeeProofParams {
+    pub transfer_fee_basis_points: u16,
+    pub maximum_fee: u64,
+    pub withdraw_withheld_authority_elgamal_pubkey: pod::ElGamalPubkey,
+}
+
+impl FeeProofParams {
+    /// Fee withheld from a transfer of `amount`
+    pub fn fee(&self, amount: u64) -> u64 {
+        self.fee_and_delta(amount).0
+    }
+
+    /// Fee capped at the maximum fee, and how far the uncapped fee is
+    /// rounded up from the exact basis point share (scaled by 10 000)
+    fn fee_and_delta(&self, amount: u64) -> (u64, u64) {
+        let numerator = u128::from(amount) * u128::from(self.transfer_fee_basis_points);
+        let fee = numerator.div_ceil(u128::from(MAX_FEE_BASIS_POINTS));
+        let delta = fee * u128::from(MAX_FEE_BASIS_POINTS) - numerator;
+        (fee.min(u128::from(self.maximum_fee)) as u64, delta as u64)
+    }
+}
+
+/// Reads the transfer fee in force at `epoch` from mint data, if the mint
+/// has transfer fees
+pub fn mint_transfer_fee(data: &[u8], epoch: u64) -> Result<Option<FeeProofParams>> {
+    let mint = StateWithExtensions::<Mint>::unpack(data)?;
+    let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(None);
+    };
+    let confidential_fee = mint
+        .get_extension::<ConfidentialTransferFeeConfig>()
+        .map_err(|_| ClientError::MintNotConfigured)?;
+    let transfer_fee = transfer_fee_config.get_epoch_fee(epoch);
+    Ok(Some(FeeProofParams {
+        transfer_fee_basis_points: transfer_fee.transfer_fee_basis_points.into(),
+        maximum_fee: transfer_fee.maximum_fee.into(),
+        withdraw_withheld_authority_elgamal_pubkey: confidential_fee
+            .withdraw_withheld_authority_elgamal_pubkey,
+    }))
+}
+
+/// Inputs for `initialize_user_account`
+pub struct ConfigureAccountProof {
+    pub proof: PubkeyValidityData,
+    pub decryptable_zero_balance: [u8; 36],
+}
+
+pub fn configure_account_proof(keys: &ConfidentialKeys) -> Result<ConfigureAccountProof> {
+    Ok(ConfigureAccountProof {
+        proof: PubkeyValidityData::new(&keys.elgamal)?,
+        decryptable_zero_balance: pod::AeCiphertext::from(keys.ae.encrypt(0)).0,
+    })
+}
+
+/// Inputs for `apply_pending_balance`
+pub struct ApplyPendingBalance {
+    pub expected_pending_balance_credit_counter: u64,
+    pub new_decryptable_available_balance: [u8; 36],
+}
+
+pub fn apply_pending_balance(
+    state: &ConfidentialTransferAccount,
+    keys: &ConfidentialKeys,
+) -> Result<ApplyPendingBalance> {
+    let info = ApplyPendingBalanceAccountInfo::new(state);
+    let new_decryptable_available_balance =
+        info.new_decryptable_available_balance(keys.elgamal.secret(), &keys.ae)?;
+    Ok(ApplyPendingBalance {
+        expected_pending_balance_credit_counter: info.pending_balance_credit_counter(),
+        new_decryptable_available_balance: pod::AeCiphertext::from(
+            new_decryptable_available_balance,
+        )
+        .0,
+    })
+}
+
+/// Split proofs and inputs for `transfer_confidential` on a mint without fees
+pub struct TransferProofs {
+    pub equality: CiphertextCommitmentEqualityProofData,
+    pub ciphertext_validity: BatchedGroupedCiphertext2HandlesValidityProofData,
+    pub range: BatchedRangeProofU128Data,
+    pub source_decrypt_handles: [u8; 64],
+    pub new_source_decryptable_available_balance: [u8; 36],
+}
+
+pub fn transfer_proofs(
+    source: &ConfidentialTransferAccount,
+    keys: &ConfidentialKeys,
+    amount: u64,
+    destination_elgamal_pubkey: &pod::ElGamalPubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+) -> Result<TransferProofs> {
+    let destination = decode_pubkey(destination_elgamal_pubkey)?;
+    let auditor = auditor_elgamal_pubkey.map(decode_pubkey).transpose()?;
+
+    let info = TransferAccountInfo::new(source);
+    let new_source_decryptable_available_balance =
+        info.new_decryptable_available_balance(amount, &keys.ae)?;
+    let (equality, ciphertext_validity, range, source_decrypt_handles) = info
+        .generate_split_transfer_proof_data(
+            amount,
+            &keys.elgamal,
+            &keys.ae,
+            &destination,
+            auditor.as_ref(),
+        )?;
+
+    Ok(TransferProofs {
+        equality,
+        ciphertext_validity,
+        range,
+        source_decrypt_handles: source_decrypt_handles_bytes(&source_decrypt_handles),
+        new_source_decryptable_available_balance: pod::AeCiphertext::from(
+            new_source_decryptable_available_balance,
+        )
+        .0,
+    })
+}
+
+/// Split proofs and inputs for `transfer_confidential` on a mint with
+/// transfer fees
+pub struct TransferWithFeeProofs {
+    pub equality: CiphertextCommitmentEqualityProofData,
+    pub ciphertext_validity: BatchedGroupedCiphertext2HandlesValidityProofData,
+    pub fee_sigma: FeeSigmaProofData,
+    pub fee_ciphertext_validity: BatchedGroupedCiphertext2HandlesValidityProofData,
+    /// Covers the fee along with the remaining balance and the amount
+    pub range: BatchedRangeProofU256Data,
+    pub source_decrypt_handles: [u8; 64],
+    pub new_source_decryptable_available_balance: [u8; 36],
+}
+
+/// Token-2022 3.0 only splits the proofs of transfers without fees, so these
+/// are built from the SDK primitives the same way
+pub fn transfer_with_fee_proofs(
+    source: &ConfidentialTransferAccount,
+    keys: &ConfidentialKeys,
+    amount: u64,
+    destination_elgamal_pubkey: &pod::ElGamalPubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+    fee: &FeeProofParams,
+) -> Result<TransferWithFeeProofs> {
+    let destination = decode_pubkey(destination_elgamal_pubkey)?;
+    let auditor = auditor_elgamal_pubkey
+        .map(decode_pubkey)
+        .transpose()?
+        .unwrap_or_default();
+    let withdraw_withheld_authority =
+        decode_pubkey(&fee.withdraw_withheld_authority_elgamal_pubkey)?;
+
+    let info = TransferAccountInfo::new(source);
+    let new_source_decryptable_available_balance =
+        info.new_decryptable_available_balance(amount, &keys.ae)?;
+    let decryptable_available_balance = source
+        .decryptable_available_balance
+        .try_into()
+        .map_err(|_| ClientError::MalformedCiphertext)?;
+    let balance = keys
+        .ae
+        .decrypt(&decryptable_available_balance)
+        .ok_or(ClientError::Decryption)?;
+    let available_balance: ElGamalCiphertext = source
+        .available_balance
+        .try_into()
+        .map_err(|_| ClientError::MalformedCiphertext)?;
+
+    let (amount_lo, amount_hi) = split_u64(amount, AMOUNT_LO_BITS);
+    let (ciphertext_lo, opening_lo) =
+        TransferAmountCiphertext::new(amount_lo, keys.elgamal.pubkey(), &destination, &auditor);
+    let (ciphertext_hi, opening_hi) =
+        TransferAmountCiphertext::new(amount_hi, keys.elgamal.pubkey(), &destination, &auditor);
+
+    let source_part = |ciphertext: &TransferAmountCiphertext| ElGamalCiphertext {
+        commitment: *ciphertext.get_commitment(),
+        handle: *ciphertext.get_source_handle(),
+    };
+    let new_balance = balance - amount;
+    let new_available_balance = available_balance
+        - (source_part(&ciphertext_lo)
+            + source_part(&ciphertext_hi) * Scalar::from(1u64 << AMOUNT_LO_BITS));
+    let (new_balance_commitment, new_balance_opening) = Pedersen::new(new_balance);
+    let equality = CiphertextCommitmentEqualityProofData::new(
+        &keys.elgamal,
+        &new_available_balance,
+        &new_balance_commitment,
+        &new_balance_opening,
+        new_balance,
+    )?;
+
+    let ciphertext_validity = BatchedGroupedCiphertext2HandlesValidityProofData::new(
+        &destination,
+        &auditor,
+        &GroupedElGamal::encrypt_with([&destination, &auditor], amount_lo, &opening_lo),
+        &GroupedElGamal::encrypt_with([&destination, &auditor], amount_hi, &opening_hi),
+        amount_lo,
+        amount_hi,
+        &opening_lo,
+        &opening_hi,
+    )?;
+
+    let (fee_amount, delta) = fee.fee_and_delta(amount);
+    let (fee_lo, fee_hi) = split_u64(fee_amount, AMOUNT_LO_BITS);
+    let fee_opening_lo = PedersenOpening::new_rand();
+    let fee_opening_hi = PedersenOpening::new_rand();
+    let fee_commitment_lo = Pedersen::with(fee_lo, &fee_opening_lo);
+    let fee_commitment_hi = Pedersen::with(fee_hi, &fee_opening_hi);
+    let fee_commitment =
+        combine_lo_hi_commitments(&fee_commitment_lo, &fee_commitment_hi, AMOUNT_LO_BITS);
+    let fee_opening = combine_lo_hi_openings(&fee_opening_lo, &fee_opening_hi, AMOUNT_LO_BITS);
+
+    // The fee rounds the basis point share up by `delta`, which the sender
+    // claims in a separate commitment
+    let amount_commitment = combine_lo_hi_commitments(
+        ciphertext_lo.get_commitment(),
+        ciphertext_hi.get_commitment(),
+        AMOUNT_LO_BITS,
+    );
+    let amount_opening = combine_lo_hi_openings(&opening_lo, &opening_hi, AMOUNT_LO_BITS);
+    let bps = Scalar::from(fee.transfer_fee_basis_points);
+    let max_bps = Scalar::from(MAX_FEE_BASIS_POINTS);
+    let delta_commitment = fee_commitment * max_bps - amount_commitment * bps;
+    let delta_opening = &fee_opening * max_bps - &amount_opening * bps;
+    let (claimed_commitment, claimed_opening) = Pedersen::new(delta);
+    let claimed_complement_opening = &PedersenOpening::default() - &claimed_opening;
+    let claimed_complement_commitment =
+        Pedersen::with(MAX_FEE_BASIS_POINTS - delta, &claimed_complement_opening);
+
+    let fee_sigma = FeeSigmaProofData::new(
+        &fee_commitment,
+        &delta_commitment,
+        &claimed_commitment,
+        &fee_opening,
+        &delta_opening,
+        &claimed_opening,
+        fee_amount,
+        delta,
+        fee.maximum_fee,
+    )?;
+
+    let fee_keys = [&destination, &withdraw_withheld_authority];
+    let fee_ciphertext_validity = BatchedGroupedCiphertext2HandlesValidityProofData::new(
+        &destination,
+        &withdraw_withheld_authority,
+        &GroupedElGamal::encrypt_with(fee_keys, fee_lo, &fee_opening_lo),
+        &GroupedElGamal::encrypt_with(fee_keys, fee_hi, &fee_opening_hi),
+        fee_lo,
+        fee_hi,
+        &fee_opening_lo,
+        &fee_opening_hi,
+    )?;
+
+    let range = BatchedRangeProofU256Data::new(
+        vec![
+            &new_balance_commitment,
+            ciphertext_lo.get_commitment(),
+            ciphertext_hi.get_commitment(),
+            &claimed_commitment,
+            &claimed_complement_commitment,
+            &fee_commitment_lo,
+            &fee_commitment_hi,
+        ],
+        vec![
+            new_balance,
+            amount_lo,
+            amount_hi,
+            delta,
+            MAX_FEE_BASIS_POINTS - delta,
+            fee_lo,
+            fee_hi,
+        ],
+        vec![
+            REMAINING_BALANCE_BITS,
+            AMOUNT_LO_BITS,
+            AMOUNT_HI_BITS,
+            DELTA_BITS,
+            DELTA_BITS,
+            AMOUNT_LO_BITS,
+            AMOUNT_HI_BITS,
+        ],
+        vec![
+            &new_balance_opening,
+            &opening_lo,
+            &opening_hi,
+            &claimed_opening,
+            &claimed_complement_opening,
+            &fee_opening_lo,
+            &fee_opening_hi,
+        ],
+    )?;
+
+    let source_decrypt_handles = SourceDecryptHandles {
+        lo: DecryptHandle::new(keys.elgamal.pubkey(), &opening_lo).into(),
+        hi: DecryptHandle::new(keys.elgamal.pubkey(), &opening_hi).into(),
+    };
+    Ok(TransferWithFeeProofs {
+        equality,
+        ciphertext_validity,
+        fee_sigma,
+        fee_ciphertext_validity,
+        range,
+        source_decrypt_handles: source_decrypt_handles_bytes(&source_decrypt_handles),
+        new_source_decryptable_available_balance: pod::AeCiphertext::from(
+            new_source_decryptable_available_balance,
+        )
+        .0,
+    })
+}
+
+/// Proof and inputs for `withdraw_confidential`
+pub struct WithdrawProof {
+    pub proof: WithdrawData,
+    pub new_decryptable_available_balance: [u8; 36],
+}
+
+pub fn withdraw_proof(
+    state: &ConfidentialTransferAccount,
+    keys: &ConfidentialKeys,
+    amount: u64,
+) -> Result<WithdrawProof> {
+    let info = WithdrawAccountInfo::new(state);
+    // Checks the balance first so an overdraft is reported as such rather
+    // than as a proof generation failure
+    let new_decryptable_available_balance =
+        info.new_decryptable_available_balance(amount, &keys.ae)?;
+    let proof = info.generate_proof_data(amount, &keys.elgamal, &keys.ae)?;
+    Ok(WithdrawProof {
+        proof,
+        new_decryptable_available_balance: pod::AeCiphertext::from(
+            new_decryptable_available_balance,
+        )
+        .0,
+    })
+}
+
+/// Proof for `close_confidential_account` and `close_stream` - the available
+/// balance is zero
+pub fn empty_account_proof(
+    state: &ConfidentialTransferAccount,
+    keys: &ConfidentialKeys,
+) -> Result<ZeroBalanceProofData> {
+    Ok(EmptyAccountAccountInfo::new(state).generate_proof_data(&keys.elgamal)?)
+}
+
+/// Transfer amount under the source key, recombined from the low and high
+/// ciphertexts the same way the program does
+pub fn transfer_source_ciphertext(transfer: &TransferProofs) -> Result<pod::ElGamalCiphertext> {
+    source_ciphertext(
+        &transfer.ciphertext_validity,
+        &transfer.source_decrypt_handles,
+    )
+}
+
+/// [`transfer_source_ciphertext`] of a transfer with fee
+pub fn transfer_with_fee_source_ciphertext(
+    transfer: &TransferWithFeeProofs,
+) -> Result<pod::ElGamalCiphertext> {
+    source_ciphertext(
+        &transfer.ciphertext_validity,
+        &transfer.source_decrypt_handles,
+    )
+}
+
+fn source_ciphertext(
+    ciphertext_validity: &BatchedGroupedCiphertext2HandlesValidityProofData,
+    source_decrypt_handles: &[u8; 64],
+) -> Result<pod::ElGamalCiphertext> {
+    let context = ciphertext_validity.context_data();
+    let part = |grouped: &pod::GroupedElGamalCiphertext2Handles, handle: &[u8]| {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&bytemuck::bytes_of(grouped)[..32]);
+        bytes[32..].copy_from_slice(handle);
+        pod::ElGamalCiphertext(bytes)
+    };
+    let (handle_lo, handle_hi) = source_decrypt_handles.split_at(32);
+    ops::add_with_lo_hi(
+        &pod::ElGamalCiphertext::zeroed(),
+        &part(&context.grouped_ciphertext_lo, handle_lo),
+        &part(&context.grouped_ciphertext_hi, handle_hi),
+    )
+    .ok_or(ClientError::MalformedCiphertext)
+}
+
+/// Invoice amount for `create_invoice`, encrypted under the payer's key
+pub fn encrypt_invoice_amount(
+    amount: u64,
+    payer_elgamal_pubkey: &pod::ElGamalPubkey,
+) -> Result<[u8; 64]> {
+    let ciphertext = decode_pubkey(payer_elgamal_pubkey)?.encrypt(amount);
+    Ok(pod::ElGamalCiphertext::from(ciphertext).0)
+}
+
+/// Proofs and inputs for `pay_invoice`, `T` being the transfer proofs of
+/// the mint ([`TransferProofs`] or [`TransferWithFeeProofs`])
+pub struct InvoicePaymentProofs<T = TransferProofs> {
+    pub transfer: T,
+    /// The invoice ciphertext minus the transferred amount encrypts 0
+    pub zero_balance: ZeroBalanceProofData,
+}
+
+/// Pay `amount` against an invoice addressed to the account in `state`
+///
+/// Fails with `InvoiceAmountMismatch` before generating any proof if the
+/// invoice ciphertext does not decrypt to `amount`.
+pub fn invoice_payment_proofs(
+    state: &ConfidentialTransferAccount,
+    keys: &ConfidentialKeys,
+    invoice_ciphertext: &[u8; 64],
+    amount: u64,
+    payee_elgamal_pubkey: &pod::ElGamalPubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+) -> Result<InvoicePaymentProofs> {
+    check_invoice_amount(keys, invoice_ciphertext, amount)?;
+    let transfer = transfer_proofs(
+        state,
+        keys,
+        amount,
+        payee_elgamal_pubkey,
+        auditor_elgamal_pubkey,
+    )?;
+    let zero_balance = invoice_zero_balance_proof(
+        keys,
+        invoice_ciphertext,
+        &transfer_source_ciphertext(&transfer)?,
+    )?;
+    Ok(InvoicePaymentProofs {
+        transfer,
+        zero_balance,
+    })
+}
+
+/// [`invoice_payment_proofs`] on a mint with transfer fees. The invoice is
+/// for the amount the payer sends, the fee comes out of what the payee gets.
+pub fn invoice_payment_with_fee_proofs(
+    state: &ConfidentialTransferAccount,
+    keys: &ConfidentialKeys,
+    invoice_ciphertext: &[u8; 64],
+    amount: u64,
+    payee_elgamal_pubkey: &pod::ElGamalPubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+    fee: &FeeProofParams,
+) -> Result<InvoicePaymentProofs<TransferWithFeeProofs>> {
+    check_invoice_amount(keys, invoice_ciphertext, amount)?;
+    let transfer = transfer_with_fee_proofs(
+        state,
+        keys,
+        amount,
+        payee_elgamal_pubkey,
+        auditor_elgamal_pubkey,
+        fee,
+    )?;
+    let zero_balance = invoice_zero_balance_proof(
+        keys,
+        invoice_ciphertext,
+        &transfer_with_fee_source_ciphertext(&transfer)?,
+    )?;
+    Ok(InvoicePaymentProofs {
+        transfer,
+        zero_balance,
+    })
+}
+
+fn check_invoice_amount(
+    keys: &ConfidentialKeys,
+    invoice_ciphertext: &[u8; 64],
+    amount: u64,
+) -> Result<()> {
+    let invoice = pod::ElGamalCiphertext(*invoice_ciphertext);
+    let remaining = ops::subtract_from(&invoice, amount).ok_or(ClientError::MalformedCiphertext)?;
+    if balance::decrypt_u32(keys.elgamal.secret(), &remaining)? != Some(0) {
+        return Err(ClientError::InvoiceAmountMismatch);
+    }
+    Ok(())
+}
+
+fn invoice_zero_balance_proof(
+    keys: &ConfidentialKeys,
+    invoice_ciphertext: &[u8; 64],
+    transferred: &pod::ElGamalCiphertext,
+) -> Result<ZeroBalanceProofData> {
+    let difference = ops::subtract(&pod::ElGamalCiphertext(*invoice_ciphertext), transferred)
+        .ok_or(ClientError::MalformedCiphertext)?;
+    let difference =
+        ElGamalCiphertext::try_from(difference).map_err(|_| ClientError::MalformedCiphertext)?;
+    Ok(ZeroBalanceProofData::new(&keys.elgamal, &difference)?)
+}
+
+fn decode_pubkey(pubkey: &pod::ElGamalPubkey) -> Result<ElGamalPubkey> {
+    ElGamalPubkey::try_from(*pubkey).map_err(|_| ClientError::MalformedCiphertext)
+}
+
+fn source_decrypt_handles_bytes(handles: &SourceDecryptHandles) -> [u8; 64] {
+    let mut bytes = [0u8; 64];
+    bytes.copy_from_slice(bytemuck::bytes_of(handles));
+    bytes
+}
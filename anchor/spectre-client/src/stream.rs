@@ -0,0 +1,257 @@
+//! Confidential payment streams.
+//!
+//! The escrow of a stream has its own ElGamal and AE keys, derived from a
+//! random seed the sender picks. The seed and the streamed total travel to
+//! the recipient in the `StreamCreated` event, sealed like a transfer memo,
+//! so both sides can decrypt the escrow and prove claims or refunds against
+//! it. A claim (or refund) has to land in the vesting period it was built
+//! for: the program checks the allowance proof against the periods vested
+//! at execution time. Once a canceled or fully vested stream's escrow is
+//! empty, the sender closes it with a zero balance proof under the escrow
+//! key and gets the rent back.
+
+use sha3::{Digest, Sha3_512};
+use solana_sdk::signer::SeedDerivable;
+use spl_token_2022::extension::confidential_transfer::ConfidentialTransferAccount;
+use spl_token_2022::solana_zk_token_sdk::encryption::auth_encryption::{AeCiphertext, AeKey};
+use spl_token_2022::solana_zk_token_sdk::encryption::elgamal::{ElGamalCiphertext, ElGamalKeypair};
+use spl_token_2022::solana_zk_token_sdk::encryption::pedersen::{Pedersen, PedersenOpening};
+use spl_token_2022::solana_zk_token_sdk::instruction::{
+    BatchedRangeProofU64Data, CiphertextCommitmentEqualityProofData,
+};
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::{ops, pod};
+use vault::{stream_allowance_ciphertext, ConfidentialMemo, Stream, STREAM_ALLOWANCE_BIT_LENGTH};
+
+use crate::error::{ClientError, Result};
+use crate::keys::ConfidentialKeys;
+use crate::memo;
+use crate::proofs::{self, TransferProofs};
+
+const STREAM_SECRET_LEN: usize = 40;
+
+/// What the recipient needs to claim from a stream
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamSecret {
+    /// Seed of the escrow keys
+    pub seed: [u8; 32],
+    /// Streamed total
+    pub total: u64,
+}
+
+impl StreamSecret {
+    /// Fresh escrow keys for a stream of `total` tokens
+    pub fn new(total: u64) -> Self {
+        Self {
+            seed: rand::random(),
+            total,
+        }
+    }
+
+    pub fn escrow_keys(&self) -> Result<ConfidentialKeys> {
+        let elgamal_seed = Sha3_512::new()
+            .chain(b"ElGamalSecretKey")
+            .chain(self.seed)
+            .finalize();
+        let ae_seed = Sha3_512::new().chain(b"AeKey").chain(self.seed).finalize();
+        Ok(ConfidentialKeys {
+            elgamal: ElGamalKeypair::from_seed(&elgamal_seed)
+                .map_err(|_| ClientError::KeyDerivation)?,
+            ae: AeKey::from_seed(&ae_seed).map_err(|_| ClientError::KeyDerivation)?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; STREAM_SECRET_LEN] {
+        let mut bytes = [0u8; STREAM_SECRET_LEN];
+        bytes[..32].copy_from_slice(&self.seed);
+        bytes[32..].copy_from_slice(&self.total.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != STREAM_SECRET_LEN {
+            return Err(ClientError::Decryption);
+        }
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(&bytes[..32]);
+        let mut total = [0u8; 8];
+        total.copy_from_slice(&bytes[32..]);
+        Ok(Self {
+            seed,
+            total: u64::from_le_bytes(total),
+        })
+    }
+}
+
+/// Seal `secret` for the recipient and, optionally, the mint's auditor
+pub fn encrypt_stream_secret(
+    secret: &StreamSecret,
+    recipient_elgamal_pubkey: &pod::ElGamalPubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+) -> Result<ConfidentialMemo> {
+    memo::encrypt_memo(
+        &secret.to_bytes(),
+        recipient_elgamal_pubkey,
+        auditor_elgamal_pubkey,
+    )
+}
+
+/// Open the `stream_secret` of a `StreamCreated` event with the keys of
+/// the recipient's confidential account
+pub fn decrypt_stream_secret(
+    stream_secret: &ConfidentialMemo,
+    keys: &ConfidentialKeys,
+) -> Result<StreamSecret> {
+    StreamSecret::from_bytes(&memo::decrypt_memo(stream_secret, keys)?)
+}
+
+/// Equality and range proof showing the allowance ciphertext holds a value
+/// below 2^64
+pub struct AllowanceProofs {
+    pub equality: CiphertextCommitmentEqualityProofData,
+    pub range: BatchedRangeProofU64Data,
+}
+
+pub fn allowance_proofs(
+    escrow_keys: &ConfidentialKeys,
+    allowance: &pod::ElGamalCiphertext,
+    amount: u64,
+) -> Result<AllowanceProofs> {
+    let ciphertext =
+        ElGamalCiphertext::try_from(*allowance).map_err(|_| ClientError::MalformedCiphertext)?;
+    let opening = PedersenOpening::new_rand();
+    let commitment = Pedersen::with(amount, &opening);
+    Ok(AllowanceProofs {
+        equality: CiphertextCommitmentEqualityProofData::new(
+            &escrow_keys.elgamal,
+            &ciphertext,
+            &commitment,
+            &opening,
+            amount,
+        )?,
+        range: BatchedRangeProofU64Data::new(
+            vec![&commitment],
+            vec![amount],
+            vec![STREAM_ALLOWANCE_BIT_LENGTH.into()],
+            vec![&opening],
+        )?,
+    })
+}
+
+/// Proofs and inputs for `withdraw_from_stream` or `cancel_stream`
+pub struct StreamTransferProofs {
+    pub transfer: TransferProofs,
+    pub allowance: AllowanceProofs,
+    /// Claimed total under the escrow AE key, unchanged by a refund
+    pub new_decryptable_withdrawn_amount: [u8; 36],
+}
+
+/// Claim `amount` from the escrow into an account of the recipient
+///
+/// `vested_periods` is [`Stream::vested_periods`] at the time the claim will
+/// land. Fails with `StreamAllowanceExceeded` if the amount is more than has
+/// vested and not been claimed yet.
+#[allow(clippy::too_many_arguments)]
+pub fn claim_proofs(
+    stream: &Stream,
+    escrow_state: &ConfidentialTransferAccount,
+    secret: &StreamSecret,
+    vested_periods: u16,
+    amount: u64,
+    recipient_elgamal_pubkey: &pod::ElGamalPubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+) -> Result<StreamTransferProofs> {
+    let keys = secret.escrow_keys()?;
+    let withdrawn = keys
+        .ae
+        .decrypt(&decode_ae(stream.decryptable_withdrawn_amount)?)
+        .ok_or(ClientError::Decryption)?;
+    let new_withdrawn = withdrawn
+        .checked_add(amount)
+        .ok_or(ClientError::StreamAllowanceExceeded)?;
+    let allowance_amount = allowance(secret.total, new_withdrawn, vested_periods, stream.periods)?;
+
+    let transfer = proofs::transfer_proofs(
+        escrow_state,
+        &keys,
+        amount,
+        recipient_elgamal_pubkey,
+        auditor_elgamal_pubkey,
+    )?;
+    let claimed = ops::add(
+        &pod::ElGamalCiphertext(stream.withdrawn_amount),
+        &proofs::transfer_source_ciphertext(&transfer)?,
+    )
+    .ok_or(ClientError::MalformedCiphertext)?;
+    let allowance_ciphertext = stream_allowance_ciphertext(
+        &pod::ElGamalCiphertext(stream.total_amount),
+        &claimed,
+        vested_periods,
+        stream.periods,
+    )
+    .ok_or(ClientError::MalformedCiphertext)?;
+
+    Ok(StreamTransferProofs {
+        transfer,
+        allowance: allowance_proofs(&keys, &allowance_ciphertext, allowance_amount)?,
+        new_decryptable_withdrawn_amount: pod::AeCiphertext::from(keys.ae.encrypt(new_withdrawn)).0,
+    })
+}
+
+/// Refund `amount` from the escrow to the sender when canceling
+///
+/// At most the share of the periods that have not vested by the time the
+/// cancel lands can be refunded.
+pub fn refund_proofs(
+    stream: &Stream,
+    escrow_state: &ConfidentialTransferAccount,
+    secret: &StreamSecret,
+    vested_periods: u16,
+    amount: u64,
+    sender_elgamal_pubkey: &pod::ElGamalPubkey,
+    auditor_elgamal_pubkey: Option<&pod::ElGamalPubkey>,
+) -> Result<StreamTransferProofs> {
+    let keys = secret.escrow_keys()?;
+    let unvested_periods = stream
+        .periods
+        .checked_sub(vested_periods)
+        .ok_or(ClientError::StreamAllowanceExceeded)?;
+    let allowance_amount = allowance(secret.total, amount, unvested_periods, stream.periods)?;
+
+    let transfer = proofs::transfer_proofs(
+        escrow_state,
+        &keys,
+        amount,
+        sender_elgamal_pubkey,
+        auditor_elgamal_pubkey,
+    )?;
+    let allowance_ciphertext = stream_allowance_ciphertext(
+        &pod::ElGamalCiphertext(stream.total_amount),
+        &proofs::transfer_source_ciphertext(&transfer)?,
+        unvested_periods,
+        stream.periods,
+    )
+    .ok_or(ClientError::MalformedCiphertext)?;
+
+    Ok(StreamTransferProofs {
+        transfer,
+        allowance: allowance_proofs(&keys, &allowance_ciphertext, allowance_amount)?,
+        new_decryptable_withdrawn_amount: stream.decryptable_withdrawn_amount,
+    })
+}
+
+/// `allowed_periods·total - periods·claimed`, the value the allowance
+/// ciphertext decrypts to
+fn allowance(total: u64, claimed: u64, allowed_periods: u16, periods: u16) -> Result<u64> {
+    let allowed = u128::from(allowed_periods) * u128::from(total);
+    let claimed = u128::from(periods) * u128::from(claimed);
+    allowed
+        .checked_sub(claimed)
+        .and_then(|allowance| u64::try_from(allowance).ok())
+        .ok_or(ClientError::StreamAllowanceExceeded)
+}
+
+fn decode_ae(ciphertext: [u8; 36]) -> Result<AeCiphertext> {
+    pod::AeCiphertext(ciphertext)
+        .try_into()
+        .map_err(|_| ClientError::MalformedCiphertext)
+}
@@ -0,0 +1,1074 @@
+use crate::balance::{self, BalanceWarning};
+use crate::instructions;
+use crate::keys::ConfidentialKeys;
+use crate::memo;
+use crate::proofs;
+use crate::stream::{self, StreamSecret};
+use crate::ClientError;
+use bytemuck::Zeroable;
+use solana_sdk::{pubkey::Pubkey, rent::Rent, signature::Keypair, signer::Signer};
+use spl_token_2022::extension::confidential_transfer::ConfidentialTransferAccount;
+use spl_token_2022::solana_zk_token_sdk::encryption::elgamal::ElGamalKeypair;
+use spl_token_2022::solana_zk_token_sdk::instruction::ZkProofData;
+use spl_token_2022::solana_zk_token_sdk::zk_token_elgamal::pod;
+
+fn account_with_balance(keys: &ConfidentialKeys, balance: u64) -> ConfidentialTransferAccount {
+    let mut state = ConfidentialTransferAccount::zeroed();
+    state.approved = true.into();
+    state.elgamal_pubkey = keys.elgamal_pubkey();
+    state.available_balance = keys.elgamal.pubkey().encrypt(balance).into();
+    state.decryptable_available_balance = keys.ae.encrypt(balance).into();
+    state
+}
+
+#[test]
+fn test_keys_are_deterministic() {
+    let wallet = Keypair::new();
+    let token_account = Pubkey::new_unique();
+
+    let first = ConfidentialKeys::from_signer(&wallet, &token_account).unwrap();
+    let second = ConfidentialKeys::from_signer(&wallet, &token_account).unwrap();
+    assert_eq!(first.elgamal_pubkey(), second.elgamal_pubkey());
+    assert_eq!(second.ae.decrypt(&first.ae.encrypt(7)), Some(7));
+
+    // Same keys as the spl-token CLI derives for this account
+    let cli = ElGamalKeypair::new_from_signer(&wallet, token_account.as_ref()).unwrap();
+    assert_eq!(
+        first.elgamal_pubkey(),
+        pod::ElGamalPubkey::from(*cli.pubkey())
+    );
+
+    // Other accounts of the same wallet get other keys
+    let other = ConfidentialKeys::from_signer(&wallet, &Pubkey::new_unique()).unwrap();
+    assert_ne!(first.elgamal_pubkey(), other.elgamal_pubkey());
+}
+
+#[test]
+fn test_keys_from_wallet_signatures() {
+    let wallet = Keypair::new();
+    let token_account = Pubkey::new_unique();
+
+    let elgamal_signature = wallet.sign_message(&crate::keys::elgamal_message(&token_account));
+    let ae_signature = wallet.sign_message(&crate::keys::ae_message(&token_account));
+    let from_signatures =
+        ConfidentialKeys::from_signatures(&elgamal_signature, &ae_signature).unwrap();
+    let from_signer = ConfidentialKeys::from_signer(&wallet, &token_account).unwrap();
+
+    assert_eq!(
+        from_signatures.elgamal_pubkey(),
+        from_signer.elgamal_pubkey()
+    );
+    assert_eq!(
+        from_signer.ae.decrypt(&from_signatures.ae.encrypt(42)),
+        Some(42)
+    );
+
+    let default_signature = solana_sdk::signature::Signature::default();
+    assert!(matches!(
+        ConfidentialKeys::from_signatures(&default_signature, &ae_signature),
+        Err(ClientError::InvalidSignature)
+    ));
+}
+
+#[test]
+fn test_transfer_proofs_verify() {
+    let sender = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let recipient = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let auditor = ElGamalKeypair::new_rand();
+    let auditor_pubkey = pod::ElGamalPubkey::from(*auditor.pubkey());
+    let state = account_with_balance(&sender, 100);
+
+    let transfer = proofs::transfer_proofs(
+        &state,
+        &sender,
+        40,
+        &recipient.elgamal_pubkey(),
+        Some(&auditor_pubkey),
+    )
+    .unwrap();
+
+    transfer.equality.verify_proof().unwrap();
+    transfer.ciphertext_validity.verify_proof().unwrap();
+    transfer.range.verify_proof().unwrap();
+
+    let validity = transfer.ciphertext_validity.context_data();
+    assert_eq!(validity.destination_pubkey, recipient.elgamal_pubkey());
+    assert_eq!(validity.auditor_pubkey, auditor_pubkey);
+
+    let new_balance = pod::AeCiphertext(transfer.new_source_decryptable_available_balance);
+    assert_eq!(
+        sender.ae.decrypt(&new_balance.try_into().unwrap()),
+        Some(60)
+    );
+}
+
+#[test]
+fn test_transfer_with_fee_proofs_verify() {
+    let sender = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let recipient = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let withdraw_withheld_authority = ElGamalKeypair::new_rand();
+    let state = account_with_balance(&sender, 100_000);
+
+    // 1% of 12 345 rounds up to 124, unless the maximum fee caps it
+    for (maximum_fee, expected_fee) in [(1_000, 124), (100, 100)] {
+        let fee = proofs::FeeProofParams {
+            transfer_fee_basis_points: 100,
+            maximum_fee,
+            withdraw_withheld_authority_elgamal_pubkey: (*withdraw_withheld_authority.pubkey())
+                .into(),
+        };
+        assert_eq!(fee.fee(12_345), expected_fee);
+
+        let transfer = proofs::transfer_with_fee_proofs(
+            &state,
+            &sender,
+            12_345,
+            &recipient.elgamal_pubkey(),
+            None,
+            &fee,
+        )
+        .unwrap();
+        transfer.equality.verify_proof().unwrap();
+        transfer.ciphertext_validity.verify_proof().unwrap();
+        transfer.fee_sigma.verify_proof().unwrap();
+        transfer.fee_ciphertext_validity.verify_proof().unwrap();
+        transfer.range.verify_proof().unwrap();
+
+        let fee_validity = transfer.fee_ciphertext_validity.context_data();
+        assert_eq!(fee_validity.destination_pubkey, recipient.elgamal_pubkey());
+        assert_eq!(
+            fee_validity.auditor_pubkey,
+            fee.withdraw_withheld_authority_elgamal_pubkey
+        );
+        assert_eq!(
+            u64::from(transfer.fee_sigma.context_data().max_fee),
+            maximum_fee
+        );
+
+        let new_balance = pod::AeCiphertext(transfer.new_source_decryptable_available_balance);
+        assert_eq!(
+            sender.ae.decrypt(&new_balance.try_into().unwrap()),
+            Some(100_000 - 12_345)
+        );
+        let transferred = proofs::transfer_with_fee_source_ciphertext(&transfer).unwrap();
+        assert_eq!(
+            sender
+                .elgamal
+                .secret()
+                .decrypt_u32(&transferred.try_into().unwrap()),
+            Some(12_345)
+        );
+    }
+}
+
+#[test]
+fn test_withdraw_proof() {
+    let keys = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let state = account_with_balance(&keys, 100);
+
+    let withdraw = proofs::withdraw_proof(&state, &keys, 100).unwrap();
+    withdraw.proof.verify_proof().unwrap();
+    let new_balance = pod::AeCiphertext(withdraw.new_decryptable_available_balance);
+    assert_eq!(keys.ae.decrypt(&new_balance.try_into().unwrap()), Some(0));
+
+    assert!(matches!(
+        proofs::withdraw_proof(&state, &keys, 101),
+        Err(ClientError::InsufficientFunds)
+    ));
+}
+
+#[test]
+fn test_apply_pending_balance() {
+    let keys = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let mut state = account_with_balance(&keys, 10);
+    state.pending_balance_lo = keys.elgamal.pubkey().encrypt(5u64).into();
+    state.pending_balance_hi = keys.elgamal.pubkey().encrypt(1u64).into();
+    state.pending_balance_credit_counter = 2u64.into();
+
+    let apply = proofs::apply_pending_balance(&state, &keys).unwrap();
+    assert_eq!(apply.expected_pending_balance_credit_counter, 2);
+    // hi holds the bits above the low 16
+    let new_balance = pod::AeCiphertext(apply.new_decryptable_available_balance);
+    assert_eq!(
+        keys.ae.decrypt(&new_balance.try_into().unwrap()),
+        Some(10 + 5 + (1 << 16))
+    );
+}
+
+#[test]
+fn test_transfer_instructions() {
+    let sender = Keypair::new();
+    let sender_account = Pubkey::new_unique();
+    let keys = ConfidentialKeys::from_signer(&sender, &sender_account).unwrap();
+    let recipient = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let state = account_with_balance(&keys, 100);
+
+    let transfer = instructions::transfer_confidential(
+        &sender.pubkey(),
+        &sender_account,
+        &state,
+        &Pubkey::new_unique(),
+        &recipient.elgamal_pubkey(),
+        &Pubkey::new_unique(),
+        None,
+        &keys,
+        25,
+        None,
+        &Rent::default(),
+    )
+    .unwrap();
+
+    let transactions = transfer.transactions();
+    assert_eq!(transactions.len(), 6);
+    assert_eq!(transfer.transfer.program_id, vault::ID);
+
+    let accounts: Vec<Pubkey> = transfer
+        .transfer
+        .accounts
+        .iter()
+        .map(|a| a.pubkey)
+        .collect();
+    assert!(accounts.contains(&transfer.equality_proof.pubkey()));
+    assert!(accounts.contains(&transfer.ciphertext_validity_proof.pubkey()));
+    assert!(accounts.contains(&transfer.range_proof.pubkey()));
+    // Optional fee proof accounts are filled with the program id
+    assert_eq!(accounts.iter().filter(|k| **k == vault::ID).count(), 2);
+}
+
+#[test]
+fn test_close_requires_zero_balance() {
+    let keys = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+
+    let empty = account_with_balance(&keys, 0);
+    let close = instructions::close_confidential_account(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &empty,
+        &keys,
+    )
+    .unwrap();
+    assert_eq!(close.len(), 2);
+
+    let proof = proofs::empty_account_proof(&account_with_balance(&keys, 1), &keys).unwrap();
+    assert!(proof.verify_proof().is_err());
+}
+
+fn token_account_data(state: &ConfidentialTransferAccount, amount: u64) -> Vec<u8> {
+    use spl_token_2022::extension::{
+        BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+    };
+    use spl_token_2022::state::{Account, AccountState};
+
+    let len = ExtensionType::try_calculate_account_len::<Account>(&[
+        ExtensionType::ConfidentialTransferAccount,
+    ])
+    .unwrap();
+    let mut data = vec![0u8; len];
+    let mut account = StateWithExtensionsMut::<Account>::unpack_uninitialized(&mut data).unwrap();
+    account.base = Account {
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount,
+        state: AccountState::Initialized,
+        ..Account::default()
+    };
+    account.pack_base();
+    account.init_account_type().unwrap();
+    *account
+        .init_extension::<ConfidentialTransferAccount>(true)
+        .unwrap() = *state;
+    data
+}
+
+#[test]
+fn test_decrypt_balance() {
+    let keys = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let mut state = account_with_balance(&keys, 500);
+    state.pending_balance_lo = keys.elgamal.pubkey().encrypt(7u64).into();
+    state.pending_balance_hi = keys.elgamal.pubkey().encrypt(2u64).into();
+    state.pending_balance_credit_counter = 3u64.into();
+    state.maximum_pending_balance_credit_counter = 65536u64.into();
+    state.allow_confidential_credits = true.into();
+
+    let data = token_account_data(&state, 25);
+    let view = balance::decrypt_balance(&data, &keys).unwrap();
+    assert_eq!(view.public, 25);
+    assert_eq!(view.available, 500);
+    assert_eq!(view.pending, Some(7 + (2 << 16)));
+    assert_eq!(view.total_confidential(), Some(500 + 7 + (2 << 16)));
+    assert_eq!(view.pending_balance_credit_counter, 3);
+    assert!(view.allow_confidential_credits);
+    assert!(!view.allow_non_confidential_credits);
+    assert!(!view.pending_credits_full());
+    assert!(view.warnings.is_empty());
+
+    // Someone else's keys cannot read the account
+    let other = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    assert!(matches!(
+        balance::decrypt_balance(&data, &other),
+        Err(ClientError::Decryption)
+    ));
+}
+
+#[test]
+fn test_decrypt_balance_reports_stale_ae_balance() {
+    let keys = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let mut state = account_with_balance(&keys, 100);
+    // A credit applied on chain that the AE balance did not account for
+    state.available_balance = keys.elgamal.pubkey().encrypt(130u64).into();
+    state.expected_pending_balance_credit_counter = 1u64.into();
+    state.actual_pending_balance_credit_counter = 2u64.into();
+
+    let view = balance::decrypt_state(0, &state, &keys).unwrap();
+    assert_eq!(view.available, 100);
+    assert_eq!(view.pending, Some(0));
+    assert_eq!(
+        view.warnings,
+        vec![
+            BalanceWarning::AvailableBalanceMismatch {
+                decryptable_available: 100,
+                elgamal_available: Some(130),
+            },
+            BalanceWarning::MissedPendingCredits {
+                expected: 1,
+                actual: 2,
+            },
+        ]
+    );
+
+    // ElGamal balance below the AE value cannot be solved for
+    state.available_balance = keys.elgamal.pubkey().encrypt(90u64).into();
+    let view = balance::decrypt_state(0, &state, &keys).unwrap();
+    assert!(view
+        .warnings
+        .contains(&BalanceWarning::AvailableBalanceMismatch {
+            decryptable_available: 100,
+            elgamal_available: None,
+        }));
+}
+
+#[test]
+fn test_decrypt_pending_outside_bound() {
+    let keys = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let mut state = account_with_balance(&keys, 0);
+    state.pending_balance_hi = keys.elgamal.pubkey().encrypt(1u64 << 33).into();
+
+    let view = balance::decrypt_state(0, &state, &keys).unwrap();
+    assert_eq!(view.pending, None);
+    assert_eq!(view.total_confidential(), None);
+    assert_eq!(
+        view.warnings,
+        vec![BalanceWarning::PendingBalanceUndecryptable]
+    );
+}
+
+#[test]
+fn test_audit_ledger_from_exports() {
+    use crate::audit::{self, ExportedTransaction, SkipReason};
+
+    let auditor = ElGamalKeypair::new_rand();
+    let auditor_pubkey = pod::ElGamalPubkey::from(*auditor.pubkey());
+    let sender = Keypair::new();
+    let sender_account = Pubkey::new_unique();
+    let keys = ConfidentialKeys::from_signer(&sender, &sender_account).unwrap();
+    let recipient = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let recipient_account = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+
+    let transfer = instructions::transfer_confidential(
+        &sender.pubkey(),
+        &sender_account,
+        &account_with_balance(&keys, 1 << 20),
+        &recipient_account,
+        &recipient.elgamal_pubkey(),
+        &mint,
+        Some(&auditor_pubkey),
+        &keys,
+        (3 << 16) + 12,
+        None,
+        &Rent::default(),
+    )
+    .unwrap();
+    let mut transactions: Vec<ExportedTransaction> = transfer
+        .transactions()
+        .iter()
+        .enumerate()
+        .map(|(i, group)| ExportedTransaction {
+            slot: 100 + i as u64,
+            signature: format!("sig{i}"),
+            instructions: group.iter().map(Into::into).collect(),
+        })
+        .collect();
+
+    // Exports round-trip through both formats
+    let json = serde_json::to_vec(&transactions).unwrap();
+    assert_eq!(
+        serde_json::from_slice::<Vec<ExportedTransaction>>(&json).unwrap(),
+        transactions
+    );
+    let bytes = bincode::serialize(&transactions).unwrap();
+    assert_eq!(
+        bincode::deserialize::<Vec<ExportedTransaction>>(&bytes).unwrap(),
+        transactions
+    );
+
+    let ledger = audit::build_ledger(&transactions, auditor.secret(), &auditor_pubkey).unwrap();
+    assert!(ledger.skipped.is_empty());
+    assert_eq!(ledger.entries.len(), 1);
+    let entry = &ledger.entries[0];
+    assert_eq!(entry.amount, (3 << 16) + 12);
+    assert_eq!(entry.sender, sender.pubkey());
+    assert_eq!(entry.sender_account, sender_account);
+    assert_eq!(entry.recipient_account, recipient_account);
+    assert_eq!(entry.mint, mint);
+    assert_eq!(entry.slot, 104);
+
+    let mut csv = Vec::new();
+    ledger.write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(
+        csv.lines().nth(1).unwrap(),
+        format!(
+            "104,sig4,{mint},{},{sender_account},{recipient_account},{}",
+            sender.pubkey(),
+            (3 << 16) + 12
+        )
+    );
+
+    // Another auditor's key only sees that the transfer happened
+    let other = ElGamalKeypair::new_rand();
+    let other_pubkey = pod::ElGamalPubkey::from(*other.pubkey());
+    let ledger = audit::build_ledger(&transactions, other.secret(), &other_pubkey).unwrap();
+    assert!(ledger.entries.is_empty());
+    assert_eq!(ledger.skipped[0].reason, SkipReason::OtherAuditor);
+
+    // Without the proof verification in the export there is nothing to decrypt
+    transactions.remove(1);
+    let ledger = audit::build_ledger(&transactions, auditor.secret(), &auditor_pubkey).unwrap();
+    assert_eq!(ledger.skipped[0].reason, SkipReason::MissingProof);
+}
+
+#[test]
+fn test_memo_round_trip() {
+    let recipient = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let auditor = ElGamalKeypair::new_rand();
+    let auditor_pubkey = pod::ElGamalPubkey::from(*auditor.pubkey());
+
+    let memo = memo::encrypt_memo(
+        b"INV-2024-0042",
+        &recipient.elgamal_pubkey(),
+        Some(&auditor_pubkey),
+    )
+    .unwrap();
+    assert!(!memo.ciphertext.windows(3).any(|w| w == b"INV"));
+    assert_eq!(
+        memo::decrypt_memo(&memo, &recipient).unwrap(),
+        b"INV-2024-0042"
+    );
+    assert_eq!(
+        memo::decrypt_memo_as_auditor(&memo, auditor.secret()).unwrap(),
+        b"INV-2024-0042"
+    );
+
+    // Anyone else holds the wrong secret for both handles
+    let other = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    assert!(matches!(
+        memo::decrypt_memo(&memo, &other),
+        Err(ClientError::Decryption)
+    ));
+    assert!(matches!(
+        memo::decrypt_memo_as_auditor(&memo, other.elgamal.secret()),
+        Err(ClientError::Decryption)
+    ));
+
+    // Without an auditor handle only the recipient can read it
+    let private = memo::encrypt_memo(b"note", &recipient.elgamal_pubkey(), None).unwrap();
+    assert_eq!(private.auditor_handle, None);
+    assert!(memo::decrypt_memo_as_auditor(&private, auditor.secret()).is_err());
+
+    assert!(matches!(
+        memo::encrypt_memo(
+            &[0u8; memo::MAX_MEMO_LEN + 1],
+            &recipient.elgamal_pubkey(),
+            None
+        ),
+        Err(ClientError::InvalidMemo)
+    ));
+    let longest = memo::encrypt_memo(
+        &[7u8; memo::MAX_MEMO_LEN],
+        &recipient.elgamal_pubkey(),
+        None,
+    )
+    .unwrap();
+    assert_eq!(longest.ciphertext.len(), vault::MAX_MEMO_CIPHERTEXT_LEN);
+}
+
+#[test]
+fn test_batch_transfer_instructions() {
+    use anchor_lang::AnchorDeserialize;
+    use solana_sdk::compute_budget::ComputeBudgetInstruction;
+    use spl_token_2022::solana_zk_token_sdk::instruction::{
+        CiphertextCommitmentEqualityProofContext, CiphertextCommitmentEqualityProofData,
+    };
+    use spl_token_2022::solana_zk_token_sdk::zk_token_proof_instruction::ProofInstruction;
+
+    let sender = Keypair::new();
+    let sender_account = Pubkey::new_unique();
+    let keys = ConfidentialKeys::from_signer(&sender, &sender_account).unwrap();
+    let state = account_with_balance(&keys, 1000);
+    let recipients: Vec<instructions::BatchRecipient> = [100, 250, 50]
+        .into_iter()
+        .map(|amount| instructions::BatchRecipient {
+            account: Pubkey::new_unique(),
+            elgamal_pubkey: ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique())
+                .unwrap()
+                .elgamal_pubkey(),
+            amount,
+        })
+        .collect();
+
+    let batch = instructions::batch_transfer_confidential(
+        &sender.pubkey(),
+        &sender_account,
+        &state,
+        &Pubkey::new_unique(),
+        &recipients,
+        None,
+        &keys,
+        &Rent::default(),
+    )
+    .unwrap();
+
+    // Each transfer is proven against the balance left by the previous one
+    let data =
+        vault::instruction::BatchTransferConfidential::deserialize(&mut &batch.transfer.data[8..])
+            .unwrap();
+    for (i, expected) in [900, 650, 600].into_iter().enumerate() {
+        let entry = &data.transfers[i];
+        let balance = pod::AeCiphertext(entry.new_source_decryptable_available_balance);
+        assert_eq!(
+            keys.ae.decrypt(&balance.try_into().unwrap()),
+            Some(expected)
+        );
+
+        let equality = ProofInstruction::proof_data::<
+            CiphertextCommitmentEqualityProofData,
+            CiphertextCommitmentEqualityProofContext,
+        >(&batch.proofs[i].equality_proof.verify.data)
+        .unwrap();
+        equality.verify_proof().unwrap();
+        let remaining = equality.context_data().ciphertext.try_into().unwrap();
+        assert_eq!(
+            keys.elgamal.secret().decrypt_u32(&remaining),
+            Some(expected)
+        );
+    }
+
+    // Fixed accounts, then recipient and three proofs per transfer
+    let accounts = &batch.transfer.accounts;
+    assert_eq!(accounts.len(), 5 + 3 * 4);
+    assert_eq!(
+        accounts[3].pubkey,
+        instructions::credit_policy_address(&sender_account)
+    );
+    for (i, recipient) in recipients.iter().enumerate() {
+        let chunk = &accounts[5 + i * 4..9 + i * 4];
+        assert_eq!(chunk[0].pubkey, recipient.account);
+        assert!(chunk[0].is_writable);
+        assert_eq!(chunk[1].pubkey, batch.proofs[i].equality_proof.pubkey());
+        assert_eq!(chunk[3].pubkey, batch.proofs[i].range_proof.pubkey());
+    }
+
+    assert_eq!(
+        batch.compute_budget,
+        ComputeBudgetInstruction::set_compute_unit_limit(vault::batch_transfer_compute_units(
+            3, false
+        ))
+    );
+    let transactions = batch.transactions();
+    assert_eq!(transactions.len(), 3 * 4 + 1 + 3);
+    assert_eq!(
+        transactions[12],
+        vec![batch.compute_budget.clone(), batch.transfer.clone()]
+    );
+}
+
+#[test]
+fn test_batch_transfer_compute_budget() {
+    // Transaction compute limit of the runtime
+    const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+    let full = vault::batch_transfer_compute_units(vault::MAX_BATCH_TRANSFERS, false);
+    let full_with_fee = vault::batch_transfer_compute_units(vault::MAX_BATCH_TRANSFERS, true);
+    assert!(full < full_with_fee);
+    assert!(full_with_fee <= MAX_COMPUTE_UNIT_LIMIT);
+    assert!(
+        vault::batch_transfer_compute_units(1, false)
+            < vault::batch_transfer_compute_units(2, false)
+    );
+}
+
+#[test]
+fn test_batch_transfer_rejects_invalid_batches() {
+    let sender = Keypair::new();
+    let sender_account = Pubkey::new_unique();
+    let keys = ConfidentialKeys::from_signer(&sender, &sender_account).unwrap();
+    let recipient = |amount| instructions::BatchRecipient {
+        account: Pubkey::new_unique(),
+        elgamal_pubkey: keys.elgamal_pubkey(),
+        amount,
+    };
+    let batch = |recipients: &[instructions::BatchRecipient], balance| {
+        instructions::batch_transfer_confidential(
+            &sender.pubkey(),
+            &sender_account,
+            &account_with_balance(&keys, balance),
+            &Pubkey::new_unique(),
+            recipients,
+            None,
+            &keys,
+            &Rent::default(),
+        )
+    };
+
+    assert!(matches!(
+        batch(&[], 100),
+        Err(ClientError::InvalidBatchSize)
+    ));
+    let too_many: Vec<_> = (0..=vault::MAX_BATCH_TRANSFERS)
+        .map(|_| recipient(1))
+        .collect();
+    assert!(matches!(
+        batch(&too_many, 100),
+        Err(ClientError::InvalidBatchSize)
+    ));
+    // The second payment overdraws what the first one left
+    assert!(matches!(
+        batch(&[recipient(60), recipient(60)], 100),
+        Err(ClientError::InsufficientFunds)
+    ));
+}
+
+#[test]
+fn test_audit_ledger_from_batch_transfer() {
+    use crate::audit::{self, ExportedTransaction};
+
+    let auditor = ElGamalKeypair::new_rand();
+    let auditor_pubkey = pod::ElGamalPubkey::from(*auditor.pubkey());
+    let sender = Keypair::new();
+    let sender_account = Pubkey::new_unique();
+    let keys = ConfidentialKeys::from_signer(&sender, &sender_account).unwrap();
+    let recipients: Vec<instructions::BatchRecipient> = [70_000, 5]
+        .into_iter()
+        .map(|amount| instructions::BatchRecipient {
+            account: Pubkey::new_unique(),
+            elgamal_pubkey: keys.elgamal_pubkey(),
+            amount,
+        })
+        .collect();
+
+    let batch = instructions::batch_transfer_confidential(
+        &sender.pubkey(),
+        &sender_account,
+        &account_with_balance(&keys, 100_000),
+        &Pubkey::new_unique(),
+        &recipients,
+        Some(&auditor_pubkey),
+        &keys,
+        &Rent::default(),
+    )
+    .unwrap();
+    let transactions: Vec<ExportedTransaction> = batch
+        .transactions()
+        .iter()
+        .enumerate()
+        .map(|(i, group)| ExportedTransaction {
+            slot: i as u64,
+            signature: format!("sig{i}"),
+            instructions: group.iter().map(Into::into).collect(),
+        })
+        .collect();
+
+    let ledger = audit::build_ledger(&transactions, auditor.secret(), &auditor_pubkey).unwrap();
+    assert!(ledger.skipped.is_empty());
+    let paid: Vec<(Pubkey, u64)> = ledger
+        .entries
+        .iter()
+        .map(|entry| (entry.recipient_account, entry.amount))
+        .collect();
+    assert_eq!(
+        paid,
+        vec![(recipients[0].account, 70_000), (recipients[1].account, 5)]
+    );
+    assert!(ledger.entries.iter().all(|entry| entry.signature == "sig8"));
+}
+
+fn stream_with_escrow(
+    secret: &StreamSecret,
+    periods: u16,
+) -> (vault::Stream, ConfidentialTransferAccount) {
+    let escrow_keys = secret.escrow_keys().unwrap();
+    let stream = vault::Stream {
+        sender: Pubkey::new_unique(),
+        recipient: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        escrow: Pubkey::new_unique(),
+        stream_id: 1,
+        start_time: 0,
+        cliff_time: 100,
+        end_time: 1_000,
+        periods,
+        total_amount: pod::ElGamalCiphertext::from(
+            escrow_keys.elgamal.pubkey().encrypt(secret.total),
+        )
+        .0,
+        withdrawn_amount: pod::ElGamalCiphertext::zeroed().0,
+        decryptable_withdrawn_amount: pod::AeCiphertext::from(escrow_keys.ae.encrypt(0)).0,
+        canceled: false,
+        canceled_at_period: 0,
+        bump: 0,
+        escrow_bump: 0,
+    };
+    let escrow = account_with_balance(&escrow_keys, secret.total);
+    (stream, escrow)
+}
+
+#[test]
+fn test_stream_secret_round_trip() {
+    let recipient = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let secret = StreamSecret::new(1_000);
+
+    let sealed = stream::encrypt_stream_secret(&secret, &recipient.elgamal_pubkey(), None).unwrap();
+    let opened = stream::decrypt_stream_secret(&sealed, &recipient).unwrap();
+    assert_eq!(opened, secret);
+
+    // Both sides derive the same escrow keys from the secret
+    assert_eq!(
+        opened.escrow_keys().unwrap().elgamal_pubkey(),
+        secret.escrow_keys().unwrap().elgamal_pubkey()
+    );
+    assert_ne!(
+        StreamSecret::new(1_000)
+            .escrow_keys()
+            .unwrap()
+            .elgamal_pubkey(),
+        secret.escrow_keys().unwrap().elgamal_pubkey()
+    );
+
+    let outsider = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    assert!(stream::decrypt_stream_secret(&sealed, &outsider).is_err());
+}
+
+#[test]
+fn test_stream_vested_periods() {
+    let (mut stream, _) = stream_with_escrow(&StreamSecret::new(1_000), 10);
+
+    assert_eq!(stream.vested_periods(99), 0);
+    assert_eq!(stream.vested_periods(100), 1);
+    assert_eq!(stream.vested_periods(550), 5);
+    assert_eq!(stream.vested_periods(1_000), 10);
+    assert_eq!(stream.vested_periods(5_000), 10);
+
+    stream.canceled = true;
+    stream.canceled_at_period = 3;
+    assert_eq!(stream.vested_periods(550), 3);
+    assert_eq!(stream.vested_periods(5_000), 3);
+}
+
+#[test]
+fn test_stream_claim_proofs() {
+    let secret = StreamSecret::new(1_000);
+    let escrow_keys = secret.escrow_keys().unwrap();
+    let recipient = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let (mut stream, escrow) = stream_with_escrow(&secret, 10);
+
+    // 4 of 10 periods vested: 400 can be claimed
+    let claim = stream::claim_proofs(
+        &stream,
+        &escrow,
+        &secret,
+        4,
+        300,
+        &recipient.elgamal_pubkey(),
+        None,
+    )
+    .unwrap();
+    claim.transfer.equality.verify_proof().unwrap();
+    claim.transfer.ciphertext_validity.verify_proof().unwrap();
+    claim.transfer.range.verify_proof().unwrap();
+    claim.allowance.equality.verify_proof().unwrap();
+    claim.allowance.range.verify_proof().unwrap();
+
+    // The allowance the program computes holds 4·1000 - 10·300
+    let allowance = claim.allowance.equality.context_data().ciphertext;
+    assert_eq!(
+        balance::decrypt_u32(escrow_keys.elgamal.secret(), &allowance).unwrap(),
+        Some(1_000)
+    );
+    let withdrawn = pod::AeCiphertext(claim.new_decryptable_withdrawn_amount);
+    assert_eq!(
+        escrow_keys.ae.decrypt(&withdrawn.try_into().unwrap()),
+        Some(300)
+    );
+
+    // After the claim only 100 of the vested share is left
+    stream.decryptable_withdrawn_amount = claim.new_decryptable_withdrawn_amount;
+    assert!(matches!(
+        stream::claim_proofs(
+            &stream,
+            &escrow,
+            &secret,
+            4,
+            101,
+            &recipient.elgamal_pubkey(),
+            None,
+        ),
+        Err(ClientError::StreamAllowanceExceeded)
+    ));
+}
+
+#[test]
+fn test_stream_refund_proofs() {
+    let secret = StreamSecret::new(1_000);
+    let escrow_keys = secret.escrow_keys().unwrap();
+    let sender = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let (stream, escrow) = stream_with_escrow(&secret, 10);
+
+    // Canceling at 4 of 10 periods returns at most the unvested 600
+    let refund = stream::refund_proofs(
+        &stream,
+        &escrow,
+        &secret,
+        4,
+        600,
+        &sender.elgamal_pubkey(),
+        None,
+    )
+    .unwrap();
+    refund.allowance.equality.verify_proof().unwrap();
+    refund.allowance.range.verify_proof().unwrap();
+    let allowance = refund.allowance.equality.context_data().ciphertext;
+    assert_eq!(
+        balance::decrypt_u32(escrow_keys.elgamal.secret(), &allowance).unwrap(),
+        Some(0)
+    );
+
+    assert!(matches!(
+        stream::refund_proofs(
+            &stream,
+            &escrow,
+            &secret,
+            4,
+            601,
+            &sender.elgamal_pubkey(),
+            None
+        ),
+        Err(ClientError::StreamAllowanceExceeded)
+    ));
+}
+
+#[test]
+fn test_stream_instructions() {
+    let sender = Keypair::new();
+    let sender_account = Pubkey::new_unique();
+    let keys = ConfidentialKeys::from_signer(&sender, &sender_account).unwrap();
+    let recipient = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let secret = StreamSecret::new(1_000);
+    let schedule = vault::StreamSchedule {
+        stream_id: 7,
+        start_time: 0,
+        cliff_time: 100,
+        end_time: 1_000,
+        periods: 10,
+    };
+
+    let create = instructions::create_stream(
+        &sender.pubkey(),
+        &sender_account,
+        &account_with_balance(&keys, 5_000),
+        &Pubkey::new_unique(),
+        &recipient.elgamal_pubkey(),
+        &Pubkey::new_unique(),
+        None,
+        &keys,
+        schedule,
+        &secret,
+        &Rent::default(),
+    )
+    .unwrap();
+    let (stream_address, escrow) = instructions::stream_addresses(&sender.pubkey(), 7);
+    // Escrow key proof goes inline right after create_stream
+    assert_eq!(create.stream.len(), 2);
+    let accounts: Vec<Pubkey> = create.stream[0].accounts.iter().map(|a| a.pubkey).collect();
+    assert!(accounts.contains(&stream_address) && accounts.contains(&escrow));
+    assert_eq!(create.transactions().len(), 6);
+
+    let (stream, escrow_state) = stream_with_escrow(&secret, 10);
+    let withdraw = instructions::withdraw_from_stream(
+        &stream.recipient,
+        &Pubkey::new_unique(),
+        &recipient.elgamal_pubkey(),
+        &stream_address,
+        &stream,
+        &escrow_state,
+        &secret,
+        None,
+        10,
+        1_000,
+        &Rent::default(),
+    )
+    .unwrap();
+    let transactions = withdraw.transactions();
+    assert_eq!(transactions.len(), 9);
+    // All five proof accounts are closed in the last transaction
+    assert_eq!(transactions.last().unwrap().len(), 5);
+}
+
+#[test]
+fn test_invoice_payment_proofs() {
+    let payer = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let payee = ConfidentialKeys::from_signer(&Keypair::new(), &Pubkey::new_unique()).unwrap();
+    let state = account_with_balance(&payer, 500);
+    let invoice = proofs::encrypt_invoice_amount(120, &payer.elgamal_pubkey()).unwrap();
+
+    let payment = proofs::invoice_payment_proofs(
+        &state,
+        &payer,
+        &invoice,
+        120,
+        &payee.elgamal_pubkey(),
+        None,
+    )
+    .unwrap();
+    payment.zero_balance.verify_proof().unwrap();
+    let context = payment.zero_balance.context_data();
+    assert_eq!(context.pubkey, payer.elgamal_pubkey());
+    assert_eq!(
+        balance::decrypt_u32(payer.elgamal.secret(), &context.ciphertext).unwrap(),
+        Some(0)
+    );
+
+    // The program compares against the transferred amount, recombined the
+    // same way
+    let transferred = proofs::transfer_source_ciphertext(&payment.transfer).unwrap();
+    assert_eq!(
+        balance::decrypt_u32(payer.elgamal.secret(), &transferred).unwrap(),
+        Some(120)
+    );
+
+    assert!(matches!(
+        proofs::invoice_payment_proofs(
+            &state,
+            &payer,
+            &invoice,
+            100,
+            &payee.elgamal_pubkey(),
+            None,
+        ),
+        Err(ClientError::InvoiceAmountMismatch)
+    ));
+}
+
+#[test]
+fn test_invoice_instructions() {
+    let payee = Keypair::new();
+    let payee_account = Pubkey::new_unique();
+    let payee_keys = ConfidentialKeys::from_signer(&payee, &payee_account).unwrap();
+    let payer = Keypair::new();
+    let payer_account = Pubkey::new_unique();
+    let payer_keys = ConfidentialKeys::from_signer(&payer, &payer_account).unwrap();
+    let mint = Pubkey::new_unique();
+
+    let create = instructions::create_invoice(
+        &payee.pubkey(),
+        &payee_account,
+        &payer_account,
+        &payer_keys.elgamal_pubkey(),
+        &mint,
+        3,
+        75,
+        1_000,
+        None,
+    )
+    .unwrap();
+    let invoice_address = instructions::invoice_address(&payee.pubkey(), 3);
+    assert!(create.accounts.iter().any(|a| a.pubkey == invoice_address));
+
+    let invoice = vault::Invoice {
+        payee: payee.pubkey(),
+        payee_account,
+        payer_account,
+        mint,
+        invoice_id: 3,
+        amount_ciphertext: proofs::encrypt_invoice_amount(75, &payer_keys.elgamal_pubkey())
+            .unwrap(),
+        created_at: 0,
+        expires_at: 1_000,
+        bump: 255,
+    };
+    let pay = instructions::pay_invoice(
+        &payer.pubkey(),
+        &payer_account,
+        &account_with_balance(&payer_keys, 100),
+        &invoice_address,
+        &invoice,
+        &payee_keys.elgamal_pubkey(),
+        None,
+        &payer_keys,
+        75,
+        &Rent::default(),
+    )
+    .unwrap();
+    // Zero balance proof goes inline right after pay_invoice
+    assert_eq!(pay.pay.len(), 2);
+    assert_eq!(pay.transactions().len(), 6);
+    let accounts: Vec<Pubkey> = pay.pay[0].accounts.iter().map(|a| a.pubkey).collect();
+    assert!(accounts.contains(&payee_account) && accounts.contains(&payee.pubkey()));
+
+    let close = instructions::close_expired_invoice(&invoice_address, &invoice);
+    assert_eq!(close.accounts.len(), 2);
+}
+
+#[test]
+fn test_allowlist_hook_accounts_match_meta_list() {
+    let mint = Pubkey::new_unique();
+    let source_owner = Pubkey::new_unique();
+    let destination_owner = Pubkey::new_unique();
+    let token_account = |owner: &Pubkey| {
+        let mut data = vec![0u8; 165];
+        data[32..64].copy_from_slice(owner.as_ref());
+        data
+    };
+    let source_data = token_account(&source_owner);
+    let destination_data = token_account(&destination_owner);
+    let (source, destination) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    // Resolve the meta list the hook program writes the way Token-2022 does
+    let metas = allowlist_hook::extra_account_metas().unwrap();
+    let resolved: Vec<Pubkey> = metas
+        .iter()
+        .map(|meta| {
+            meta.resolve(&[], &allowlist_hook::ID, |index| match index {
+                0 => Some((&source, Some(source_data.as_slice()))),
+                1 => Some((&mint, None)),
+                2 => Some((&destination, Some(destination_data.as_slice()))),
+                _ => None,
+            })
+            .unwrap()
+            .pubkey
+        })
+        .collect();
+
+    let accounts = instructions::allowlist_hook_accounts(&mint, &source_owner, &destination_owner);
+    let keys: Vec<Pubkey> = accounts.iter().map(|a| a.pubkey).collect();
+    assert_eq!(keys[..2], resolved[..]);
+    assert_eq!(keys[2], allowlist_hook::ID);
+    assert_eq!(
+        keys[3],
+        Pubkey::find_program_address(
+            &[b"extra-account-metas", mint.as_ref()],
+            &allowlist_hook::ID,
+        )
+        .0
+    );
+}
@@ -1,19 +1,30 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
 import { Vault } from "../target/types/vault";
+import { ConstantProductAmm } from "../target/types/constant_product_amm";
 import { expect } from "chai";
 import { PublicKey, LAMPORTS_PER_SOL, Keypair } from "@solana/web3.js";
+import {
+  createMint,
+  getAccount,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
 
 describe("Spectre Protocol - Trading Strategy Platform", () => {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
 
   const program = anchor.workspace.Vault as Program<Vault>;
+  const amm = anchor.workspace.ConstantProductAmm as Program<ConstantProductAmm>;
   
   // Test accounts
   const trader = provider.wallet;
   const user = Keypair.generate();
   const user2 = Keypair.generate();
+  const user3 = Keypair.generate();
+  const user4 = Keypair.generate();
   
   // Test data
   const STRATEGY_NAME = "Test Momentum Strategy";
@@ -21,9 +32,32 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
   const PERFORMANCE_FEE_BPS = 2000; // 20%
   const INITIAL_DEPOSIT = new BN(10 * LAMPORTS_PER_SOL); // 10 SOL
 
+  let traderProfilePDA: PublicKey;
   let strategyPDA: PublicKey;
   let positionPDA: PublicKey;
   let position2PDA: PublicKey;
+  let position3PDA: PublicKey;
+  let position4PDA: PublicKey;
+
+  // Strategy custody: subscribers deposit base tokens, the trader buys the asset
+  let baseMint: PublicKey;
+  let assetMint: PublicKey;
+  let baseVault: PublicKey;
+  let assetVault: PublicKey;
+  let userBase: PublicKey;
+  let userAsset: PublicKey;
+  let user2Base: PublicKey;
+  let user3Base: PublicKey;
+  let user4Base: PublicKey;
+  let traderBase: PublicKey;
+  let traderAsset: PublicKey;
+  let poolPDA: PublicKey;
+  let poolBaseVault: PublicKey;
+  let poolAssetVault: PublicKey;
+  let venuePDA: PublicKey;
+
+  const BPF_LOADER_UPGRADEABLE_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+  const MAX_DEVIATION_BPS = 300; // 3%
 
   // Helper function to airdrop SOL
   async function airdrop(pubkey: PublicKey, amount: number) {
@@ -34,17 +68,48 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
     await provider.connection.confirmTransaction(signature);
   }
 
+  // Helper function to derive a trader's strategy PDA by index
+  function strategyAddress(traderKey: PublicKey, index: number): PublicKey {
+    const indexBytes = Buffer.alloc(4);
+    indexBytes.writeUInt32LE(index);
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy"), traderKey.toBuffer(), indexBytes],
+      program.programId
+    )[0];
+  }
+
+  // Helper function to read a token account balance
+  async function tokenBalance(account: PublicKey): Promise<BN> {
+    const info = await getAccount(provider.connection, account);
+    return new BN(info.amount.toString());
+  }
+
+  // Helper function to set the venue's reference price to the pool's spot price
+  async function acceptPoolPrice() {
+    await program.methods
+      .setReferencePrice(await tokenBalance(poolBaseVault), await tokenBalance(poolAssetVault))
+      .accounts({
+        admin: trader.publicKey,
+        venue: venuePDA,
+      } as any)
+      .rpc();
+  }
+
   before(async () => {
     // Airdrop to test users
     await airdrop(user.publicKey, 20);
     await airdrop(user2.publicKey, 15);
+    await airdrop(user3.publicKey, 5);
+    await airdrop(user4.publicKey, 5);
 
     // Derive PDAs
-    [strategyPDA] = PublicKey.findProgramAddressSync(
-      [Buffer.from("strategy"), trader.publicKey.toBuffer()],
+    [traderProfilePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("trader_profile"), trader.publicKey.toBuffer()],
       program.programId
     );
 
+    strategyPDA = strategyAddress(trader.publicKey, 0);
+
     [positionPDA] = PublicKey.findProgramAddressSync(
       [
         Buffer.from("position"),
@@ -62,9 +127,142 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
       ],
       program.programId
     );
+
+    [position3PDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("position"),
+        user3.publicKey.toBuffer(),
+        strategyPDA.toBuffer(),
+      ],
+      program.programId
+    );
+
+    [position4PDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("position"),
+        user4.publicKey.toBuffer(),
+        strategyPDA.toBuffer(),
+      ],
+      program.programId
+    );
+
+    // Base and asset tokens, both with 9 decimals like SOL
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    baseMint = await createMint(provider.connection, payer, trader.publicKey, null, 9);
+    assetMint = await createMint(provider.connection, payer, trader.publicKey, null, 9);
+
+    const ata = async (mint: PublicKey, owner: PublicKey) =>
+      (await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, owner)).address;
+    userBase = await ata(baseMint, user.publicKey);
+    userAsset = await ata(assetMint, user.publicKey);
+    user2Base = await ata(baseMint, user2.publicKey);
+    user3Base = await ata(baseMint, user3.publicKey);
+    user4Base = await ata(baseMint, user4.publicKey);
+    traderBase = await ata(baseMint, trader.publicKey);
+    traderAsset = await ata(assetMint, trader.publicKey);
+    await mintTo(provider.connection, payer, baseMint, userBase, payer, 20 * LAMPORTS_PER_SOL);
+    await mintTo(provider.connection, payer, baseMint, user2Base, payer, 15 * LAMPORTS_PER_SOL);
+    await mintTo(provider.connection, payer, baseMint, user3Base, payer, 5 * LAMPORTS_PER_SOL);
+    await mintTo(provider.connection, payer, baseMint, user4Base, payer, 5 * LAMPORTS_PER_SOL);
+    await mintTo(provider.connection, payer, baseMint, traderBase, payer, 1000 * LAMPORTS_PER_SOL);
+    await mintTo(provider.connection, payer, assetMint, traderAsset, payer, 1000 * LAMPORTS_PER_SOL);
+
+    [baseVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy_vault"), strategyPDA.toBuffer(), baseMint.toBuffer()],
+      program.programId
+    );
+    [assetVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("strategy_vault"), strategyPDA.toBuffer(), assetMint.toBuffer()],
+      program.programId
+    );
+
+    // Seed a 1:1 constant-product pool as the strategy's swap venue
+    const [mintA, mintB] =
+      Buffer.compare(baseMint.toBuffer(), assetMint.toBuffer()) < 0
+        ? [baseMint, assetMint]
+        : [assetMint, baseMint];
+    [poolPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool"), mintA.toBuffer(), mintB.toBuffer()],
+      amm.programId
+    );
+    const poolVault = (mint: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("pool_vault"), poolPDA.toBuffer(), mint.toBuffer()],
+        amm.programId
+      )[0];
+    poolBaseVault = poolVault(baseMint);
+    poolAssetVault = poolVault(assetMint);
+
+    await amm.methods
+      .initializePool(30)
+      .accounts({
+        payer: trader.publicKey,
+        mintA,
+        mintB,
+      } as any)
+      .rpc();
+
+    const [providerA, providerB] =
+      mintA.equals(baseMint) ? [traderBase, traderAsset] : [traderAsset, traderBase];
+    await amm.methods
+      .addLiquidity(new BN(500 * LAMPORTS_PER_SOL), new BN(500 * LAMPORTS_PER_SOL))
+      .accounts({
+        provider: trader.publicKey,
+        pool: poolPDA,
+        vaultA: poolVault(mintA),
+        vaultB: poolVault(mintB),
+        providerA,
+        providerB,
+      } as any)
+      .rpc();
+
+    // The wallet deploys the program, so it names itself the trading admin
+    // and allowlists the pool at its 1:1 price
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      BPF_LOADER_UPGRADEABLE_ID
+    );
+    await program.methods
+      .initializeTradingConfig(trader.publicKey)
+      .accounts({
+        authority: trader.publicKey,
+        program: program.programId,
+        programData,
+      } as any)
+      .rpc();
+
+    [venuePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("swap_venue"), baseMint.toBuffer(), assetMint.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .addSwapVenue(new BN(1), new BN(1), MAX_DEVIATION_BPS)
+      .accounts({
+        admin: trader.publicKey,
+        baseMint,
+        assetMint,
+        swapProgram: amm.programId,
+        pool: poolPDA,
+        poolBaseVault,
+        poolAssetVault,
+      } as any)
+      .rpc();
   });
 
   describe("Strategy Management", () => {
+    it("Initializes the trader profile", async () => {
+      await program.methods
+        .initializeTraderProfile()
+        .accounts({
+          trader: trader.publicKey,
+        } as any)
+        .rpc();
+
+      const profile = await program.account.traderProfile.fetch(traderProfilePDA);
+      expect(profile.trader.toBase58()).to.equal(trader.publicKey.toBase58());
+      expect(profile.strategyCount).to.equal(0);
+    });
+
     it("Initializes a new trading strategy", async () => {
       const tx = await program.methods
         .initializeStrategy(
@@ -86,6 +284,50 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
       expect(strategy.performanceFeeBps).to.equal(PERFORMANCE_FEE_BPS);
       expect(strategy.totalSubscribers).to.equal(0);
       expect(strategy.isActive).to.be.true;
+      expect(strategy.index).to.equal(0);
+    });
+
+    it("Initializes strategy vaults and pins the venue's swap program", async () => {
+      await program.methods
+        .initializeStrategyVaults()
+        .accounts({
+          trader: trader.publicKey,
+          strategy: strategyPDA,
+          baseMint,
+          assetMint,
+          venue: venuePDA,
+          baseVault,
+          assetVault,
+        } as any)
+        .rpc();
+
+      const strategy = await program.account.strategy.fetch(strategyPDA);
+      expect(strategy.baseMint.toBase58()).to.equal(baseMint.toBase58());
+      expect(strategy.assetMint.toBase58()).to.equal(assetMint.toBase58());
+      expect(strategy.baseVault.toBase58()).to.equal(baseVault.toBase58());
+      expect(strategy.assetVault.toBase58()).to.equal(assetVault.toBase58());
+      expect(strategy.swapProgram.toBase58()).to.equal(amm.programId.toBase58());
+      // One whole token of the 9-decimal base mint
+      expect(strategy.minDeposit.toString()).to.equal(LAMPORTS_PER_SOL.toString());
+    });
+
+    it("Initializes a second strategy for the same trader", async () => {
+      await program.methods
+        .initializeStrategy("Mean Reversion Book", STRATEGY_DESCRIPTION, 1000)
+        .accounts({
+          trader: trader.publicKey,
+          strategy: strategyAddress(trader.publicKey, 1),
+        } as any)
+        .rpc();
+
+      const strategy = await program.account.strategy.fetch(
+        strategyAddress(trader.publicKey, 1)
+      );
+      expect(strategy.index).to.equal(1);
+      expect(strategy.name).to.equal("Mean Reversion Book");
+
+      const profile = await program.account.traderProfile.fetch(traderProfilePDA);
+      expect(profile.strategyCount).to.equal(2);
     });
 
     it("Updates strategy metadata", async () => {
@@ -157,11 +399,16 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
     it("Subscribes user to strategy with initial deposit", async () => {
       const strategyBefore = await program.account.strategy.fetch(strategyPDA);
       const subscribersBefore = strategyBefore.totalSubscribers;
+      const vaultBefore = await tokenBalance(baseVault);
 
       const tx = await program.methods
         .subscribeToStrategy(INITIAL_DEPOSIT)
         .accounts({
           user: user.publicKey,
+          strategy: strategyPDA,
+          venue: venuePDA,
+          baseVault,
+          userBaseAccount: userBase,
         } as any)
         .signers([user])
         .rpc();
@@ -173,12 +420,20 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
       expect(position.user.toBase58()).to.equal(user.publicKey.toBase58());
       expect(position.strategy.toBase58()).to.equal(strategyPDA.toBase58());
       expect(position.initialBalance.toString()).to.equal(INITIAL_DEPOSIT.toString());
-      expect(position.currentBalance.toString()).to.equal(INITIAL_DEPOSIT.toString());
       expect(position.isActive).to.be.true;
 
+      // First deposit mints shares 1:1
+      expect(position.shares.toString()).to.equal(INITIAL_DEPOSIT.toString());
+
+      // Verify the deposit is held by the strategy vault
+      const vaultAfter = await tokenBalance(baseVault);
+      expect(vaultAfter.sub(vaultBefore).toString()).to.equal(INITIAL_DEPOSIT.toString());
+
       // Verify strategy subscriber count increased
       const strategyAfter = await program.account.strategy.fetch(strategyPDA);
       expect(strategyAfter.totalSubscribers).to.equal(subscribersBefore + 1);
+      expect(strategyAfter.totalShares.toString()).to.equal(INITIAL_DEPOSIT.toString());
+      expect(strategyAfter.baseBalance.toString()).to.equal(INITIAL_DEPOSIT.toString());
     });
 
     it("Subscribes second user to strategy", async () => {
@@ -188,6 +443,10 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
         .subscribeToStrategy(deposit)
         .accounts({
           user: user2.publicKey,
+          strategy: strategyPDA,
+          venue: venuePDA,
+          baseVault,
+          userBaseAccount: user2Base,
         } as any)
         .signers([user2])
         .rpc();
@@ -196,6 +455,10 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
 
       const strategy = await program.account.strategy.fetch(strategyPDA);
       expect(strategy.totalSubscribers).to.equal(2);
+
+      // No trades yet, so NAV is still 1
+      const position2 = await program.account.userPosition.fetch(position2PDA);
+      expect(position2.shares.toString()).to.equal(deposit.toString());
     });
 
     it("Fails to subscribe with insufficient deposit", async () => {
@@ -206,6 +469,10 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
           .subscribeToStrategy(tinyDeposit)
           .accounts({
             user: user.publicKey,
+            strategy: strategyPDA,
+            venue: venuePDA,
+            baseVault,
+            userBaseAccount: userBase,
           } as any)
           .signers([user])
           .rpc();
@@ -218,64 +485,252 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
   });
 
   describe("Trade Execution", () => {
-    it("Executes profitable trade", async () => {
-      const tradeAmount = new BN(2 * LAMPORTS_PER_SOL);
-      const profit = new BN(0.5 * LAMPORTS_PER_SOL);
+    it("Buys the asset with the pool's base tokens", async () => {
+      const tradeAmount = new BN(3 * LAMPORTS_PER_SOL);
 
-      const positionBefore = await program.account.userPosition.fetch(positionPDA);
-      const balanceBefore = positionBefore.currentBalance;
+      const strategyBefore = await program.account.strategy.fetch(strategyPDA);
+      const baseBefore = await tokenBalance(baseVault);
+      const assetBefore = await tokenBalance(assetVault);
 
       const tx = await program.methods
-        .executeTrade(tradeAmount, profit)
+        .executeTrade({ buy: {} }, tradeAmount)
         .accounts({
           trader: trader.publicKey,
           strategy: strategyPDA,
-          position: positionPDA,
+          baseVault,
+          assetVault,
+          venue: venuePDA,
+          pool: poolPDA,
+          poolBaseVault,
+          poolAssetVault,
+          swapProgram: amm.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .rpc();
 
-      console.log("  ✓ Profitable trade executed, tx:", tx);
+      console.log("  ✓ Buy executed, tx:", tx);
 
-      const positionAfter = await program.account.userPosition.fetch(positionPDA);
-      const expectedBalance = balanceBefore.add(profit);
-      expect(positionAfter.currentBalance.toString()).to.equal(expectedBalance.toString());
+      // Pool balances move exactly as the vaults did, once for all subscribers
+      const spent = baseBefore.sub(await tokenBalance(baseVault));
+      const received = (await tokenBalance(assetVault)).sub(assetBefore);
+      expect(spent.toString()).to.equal(tradeAmount.toString());
 
-      // Verify strategy volume increased
-      const strategy = await program.account.strategy.fetch(strategyPDA);
-      expect(strategy.totalVolumeTraded.gte(tradeAmount)).to.be.true;
+      const strategyAfter = await program.account.strategy.fetch(strategyPDA);
+      expect(strategyAfter.baseBalance.toString()).to.equal(
+        strategyBefore.baseBalance.sub(spent).toString()
+      );
+      expect(strategyAfter.assetBalance.toString()).to.equal(received.toString());
+      expect(strategyAfter.totalShares.toString()).to.equal(
+        strategyBefore.totalShares.toString()
+      );
+      expect(strategyAfter.totalVolumeTraded.gte(tradeAmount)).to.be.true;
     });
 
-    it("Executes losing trade", async () => {
-      const tradeAmount = new BN(1 * LAMPORTS_PER_SOL);
-      const loss = new BN(-0.2 * LAMPORTS_PER_SOL);
+    it("Subscribes at NAV while the strategy holds the asset", async () => {
+      const deposit = new BN(2 * LAMPORTS_PER_SOL);
+      const strategyBefore = await program.account.strategy.fetch(strategyPDA);
+      const venue = await program.account.swapVenue.fetch(venuePDA);
 
-      const positionBefore = await program.account.userPosition.fetch(positionPDA);
-      const balanceBefore = positionBefore.currentBalance;
+      await program.methods
+        .subscribeToStrategy(deposit)
+        .accounts({
+          user: user3.publicKey,
+          strategy: strategyPDA,
+          venue: venuePDA,
+          baseVault,
+          userBaseAccount: user3Base,
+        } as any)
+        .signers([user3])
+        .rpc();
+
+      // NAV values the asset at the venue's reference price, not the pool's
+      const nav = strategyBefore.baseBalance.add(
+        strategyBefore.assetBalance.mul(venue.referenceBase).div(venue.referenceAsset)
+      );
+      const expectedShares = deposit.mul(strategyBefore.totalShares).div(nav);
+      const position3 = await program.account.userPosition.fetch(position3PDA);
+      expect(position3.shares.toString()).to.equal(expectedShares.toString());
+    });
+
+    it("Rejects a trade after the pool price is pushed away from the reference", async () => {
+      // The trader (or anyone) buys the asset straight from the pool, pushing
+      // its price up before the strategy buys at it
+      await amm.methods
+        .swap(new BN(100 * LAMPORTS_PER_SOL), new BN(0))
+        .accounts({
+          source: traderBase,
+          destination: traderAsset,
+          authority: trader.publicKey,
+          pool: poolPDA,
+          poolSourceVault: poolBaseVault,
+          poolDestinationVault: poolAssetVault,
+        } as any)
+        .rpc();
+
+      try {
+        await program.methods
+          .executeTrade({ buy: {} }, new BN(1 * LAMPORTS_PER_SOL))
+          .accounts({
+            trader: trader.publicKey,
+            strategy: strategyPDA,
+            baseVault,
+            assetVault,
+            venue: venuePDA,
+            pool: poolPDA,
+            poolBaseVault,
+            poolAssetVault,
+            swapProgram: amm.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (error: any) {
+        // The minimum output follows the reference price, not the pool's
+        expect(error.toString()).to.include("SlippageExceeded");
+      }
+    });
+
+    it("Fails to move the reference price as a non-admin", async () => {
+      try {
+        await program.methods
+          .setReferencePrice(new BN(1), new BN(2))
+          .accounts({
+            admin: user.publicKey,
+            venue: venuePDA,
+          } as any)
+          .signers([user])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("UnauthorizedAdmin");
+      }
+    });
+
+    it("Sells the asset back at a profit once the admin accepts the new price", async () => {
+      await acceptPoolPrice();
+
+      const strategyBefore = await program.account.strategy.fetch(strategyPDA);
+      const baseBefore = await tokenBalance(baseVault);
 
       const tx = await program.methods
-        .executeTrade(tradeAmount, loss)
+        .executeTrade({ sell: {} }, strategyBefore.assetBalance)
         .accounts({
           trader: trader.publicKey,
           strategy: strategyPDA,
-          position: positionPDA,
+          baseVault,
+          assetVault,
+          venue: venuePDA,
+          pool: poolPDA,
+          poolBaseVault,
+          poolAssetVault,
+          swapProgram: amm.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .rpc();
 
-      console.log("  ✓ Losing trade executed, tx:", tx);
+      console.log("  ✓ Sell executed, tx:", tx);
 
-      const positionAfter = await program.account.userPosition.fetch(positionPDA);
-      const expectedBalance = balanceBefore.sub(new BN(Math.abs(loss.toNumber())));
-      expect(positionAfter.currentBalance.toString()).to.equal(expectedBalance.toString());
+      const received = (await tokenBalance(baseVault)).sub(baseBefore);
+      const strategyAfter = await program.account.strategy.fetch(strategyPDA);
+      expect(strategyAfter.assetBalance.toNumber()).to.equal(0);
+      expect(strategyAfter.baseBalance.toString()).to.equal(
+        strategyBefore.baseBalance.add(received).toString()
+      );
+
+      // NAV per share is now above 1
+      expect(strategyAfter.baseBalance.gt(strategyAfter.totalShares)).to.be.true;
+    });
+
+    it("Mints fewer shares to a late subscriber after a profit", async () => {
+      const deposit = new BN(2 * LAMPORTS_PER_SOL);
+      const strategyBefore = await program.account.strategy.fetch(strategyPDA);
+
+      await program.methods
+        .subscribeToStrategy(deposit)
+        .accounts({
+          user: user4.publicKey,
+          strategy: strategyPDA,
+          venue: venuePDA,
+          baseVault,
+          userBaseAccount: user4Base,
+        } as any)
+        .signers([user4])
+        .rpc();
+
+      // The pool is flat again, so NAV is its base balance
+      const expectedShares = deposit
+        .mul(strategyBefore.totalShares)
+        .div(strategyBefore.baseBalance);
+      const position4 = await program.account.userPosition.fetch(position4PDA);
+      expect(position4.shares.toString()).to.equal(expectedShares.toString());
+      expect(position4.shares.lt(deposit)).to.be.true;
+    });
+
+    it("Fails to sell more than the pool holds", async () => {
+      try {
+        await program.methods
+          .executeTrade({ sell: {} }, new BN(1))
+          .accounts({
+            trader: trader.publicKey,
+            strategy: strategyPDA,
+            baseVault,
+            assetVault,
+            venue: venuePDA,
+            pool: poolPDA,
+            poolBaseVault,
+            poolAssetVault,
+            swapProgram: amm.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InsufficientBalance");
+      }
+    });
+
+    it("Fails when the pool vaults don't match the strategy's mints", async () => {
+      try {
+        await program.methods
+          .executeTrade({ buy: {} }, new BN(1 * LAMPORTS_PER_SOL))
+          .accounts({
+            trader: trader.publicKey,
+            strategy: strategyPDA,
+            baseVault,
+            assetVault,
+            venue: venuePDA,
+            pool: poolPDA,
+            poolBaseVault: poolAssetVault,
+            poolAssetVault: poolBaseVault,
+            swapProgram: amm.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (error: any) {
+        expect(error.toString()).to.include("InvalidSwapPool");
+      }
     });
 
     it("Fails when unauthorized user tries to execute trade", async () => {
       try {
         await program.methods
-          .executeTrade(new BN(1 * LAMPORTS_PER_SOL), new BN(0))
+          .executeTrade({ buy: {} }, new BN(1 * LAMPORTS_PER_SOL))
           .accounts({
             trader: user.publicKey,
             strategy: strategyPDA,
-            position: positionPDA,
+            baseVault,
+            assetVault,
+            venue: venuePDA,
+            pool: poolPDA,
+            poolBaseVault,
+            poolAssetVault,
+            swapProgram: amm.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([user])
           .rpc();
@@ -290,12 +745,16 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
   describe("Fee Settlement", () => {
     it("Settles performance fees on profit", async () => {
       const positionBefore = await program.account.userPosition.fetch(positionPDA);
-      const profit = positionBefore.currentBalance.sub(positionBefore.initialBalance);
+      const strategyBefore = await program.account.strategy.fetch(strategyPDA);
+      const value = positionBefore.shares
+        .mul(strategyBefore.baseBalance)
+        .div(strategyBefore.totalShares);
+      const profit = value.sub(positionBefore.initialBalance);
       
       // Expected fee: profit * 20% (2000 bps)
       const expectedFee = profit.mul(new BN(PERFORMANCE_FEE_BPS)).div(new BN(10000));
 
-      const traderBalanceBefore = await provider.connection.getBalance(trader.publicKey);
+      const traderBalanceBefore = await tokenBalance(traderBase);
 
       const tx = await program.methods
         .settleFees()
@@ -303,9 +762,11 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
           user: user.publicKey,
           trader: trader.publicKey,
           strategy: strategyPDA,
+          venue: venuePDA,
           position: positionPDA,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
+          baseVault,
+          traderBaseAccount: traderBase,
+        } as any)
         .signers([user])
         .rpc();
 
@@ -313,16 +774,19 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
 
       const positionAfter = await program.account.userPosition.fetch(positionPDA);
       
-      // Verify fee was deducted from position
-      expect(positionAfter.totalFeesPaid.gte(expectedFee)).to.be.true;
+      // Verify fee was paid by burning the position's shares
+      expect(positionAfter.totalFeesPaid.toString()).to.equal(expectedFee.toString());
+      expect(positionAfter.shares.lt(positionBefore.shares)).to.be.true;
       
-      // Verify trader received fee
-      const traderBalanceAfter = await provider.connection.getBalance(trader.publicKey);
-      expect(traderBalanceAfter).to.be.greaterThan(traderBalanceBefore);
+      // Verify trader received fee out of the strategy vault
+      const traderBalanceAfter = await tokenBalance(traderBase);
+      expect(traderBalanceAfter.sub(traderBalanceBefore).toString()).to.equal(
+        expectedFee.toString()
+      );
 
-      // Verify initial balance was reset
+      // Verify initial balance was reset to the value after the fee
       expect(positionAfter.initialBalance.toString()).to.equal(
-        positionAfter.currentBalance.toString()
+        value.sub(expectedFee).toString()
       );
     });
 
@@ -334,9 +798,11 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
             user: user.publicKey,
             trader: trader.publicKey,
             strategy: strategyPDA,
+            venue: venuePDA,
             position: positionPDA,
-            systemProgram: anchor.web3.SystemProgram.programId,
-          })
+            baseVault,
+            traderBaseAccount: traderBase,
+          } as any)
           .signers([user])
           .rpc();
         
@@ -350,8 +816,11 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
   describe("Unsubscribe & Withdrawal", () => {
     it("Unsubscribes user and withdraws funds", async () => {
       const positionBefore = await program.account.userPosition.fetch(positionPDA);
-      const withdrawAmount = positionBefore.currentBalance;
-      const userBalanceBefore = await provider.connection.getBalance(user.publicKey);
+      const strategyBefore = await program.account.strategy.fetch(strategyPDA);
+      const withdrawAmount = positionBefore.shares
+        .mul(strategyBefore.baseBalance)
+        .div(strategyBefore.totalShares);
+      const userBalanceBefore = await tokenBalance(userBase);
 
       const tx = await program.methods
         .unsubscribe()
@@ -359,27 +828,34 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
           user: user.publicKey,
           strategy: strategyPDA,
           position: positionPDA,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
+          baseVault,
+          userBaseAccount: userBase,
+          assetVault,
+          userAssetAccount: userAsset,
+        } as any)
         .signers([user])
         .rpc();
 
       console.log("  ✓ User unsubscribed, tx:", tx);
 
-      const positionAfter = await program.account.userPosition.fetch(positionPDA);
-      expect(positionAfter.isActive).to.be.false;
-      expect(positionAfter.currentBalance.toNumber()).to.equal(0);
+      const positionAfter = await program.account.userPosition.fetchNullable(positionPDA);
+      expect(positionAfter).to.be.null;
 
-      // Verify user received funds
-      const userBalanceAfter = await provider.connection.getBalance(user.publicKey);
-      expect(userBalanceAfter).to.be.greaterThan(userBalanceBefore);
+      // Verify user received funds from the strategy vault
+      const userBalanceAfter = await tokenBalance(userBase);
+      expect(userBalanceAfter.sub(userBalanceBefore).toString()).to.equal(
+        withdrawAmount.toString()
+      );
 
-      // Verify strategy subscriber count decreased
+      // Verify strategy subscriber count and shares decreased
       const strategy = await program.account.strategy.fetch(strategyPDA);
-      expect(strategy.totalSubscribers).to.equal(1);
+      expect(strategy.totalSubscribers).to.equal(strategyBefore.totalSubscribers - 1);
+      expect(strategy.totalShares.toString()).to.equal(
+        strategyBefore.totalShares.sub(positionBefore.shares).toString()
+      );
     });
 
-    it("Fails to unsubscribe inactive position", async () => {
+    it("Fails to unsubscribe a closed position", async () => {
       try {
         await program.methods
           .unsubscribe()
@@ -387,14 +863,17 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
             user: user.publicKey,
             strategy: strategyPDA,
             position: positionPDA,
-            systemProgram: anchor.web3.SystemProgram.programId,
-          })
+            baseVault,
+            userBaseAccount: userBase,
+            assetVault,
+            userAssetAccount: userAsset,
+          } as any)
           .signers([user])
           .rpc();
         
         expect.fail("Should have thrown error");
       } catch (error: any) {
-        expect(error.toString()).to.include("PositionInactive");
+        expect(error.toString()).to.include("AccountNotInitialized");
       }
     });
   });
@@ -405,8 +884,8 @@ describe("Spectre Protocol - Trading Strategy Platform", () => {
       
       console.log("\n  📊 Strategy Statistics:");
       console.log("    • Total Subscribers:", strategy.totalSubscribers);
-      console.log("    • Total Volume Traded:", strategy.totalVolumeTraded.toString(), "lamports");
-      console.log("    • Total Fees Earned:", strategy.totalFeesEarned.toString(), "lamports");
+      console.log("    • Total Volume Traded:", strategy.totalVolumeTraded.toString(), "base units");
+      console.log("    • Total Fees Earned:", strategy.totalFeesEarned.toString(), "base units");
       console.log("    • Performance Fee:", (strategy.performanceFeeBps / 100) + "%");
       console.log("    • Status:", strategy.isActive ? "Active" : "Inactive");
 
@@ -102,7 +102,7 @@ export default function StrategyDetailPage() {
       } else if (error.message?.includes('StrategyInactive')) {
         errorMsg = 'This strategy is currently inactive and not accepting new subscribers.';
       } else if (error.message?.includes('InsufficientDeposit')) {
-        errorMsg = 'Minimum deposit is 1 SOL. Please increase your deposit amount.';
+        errorMsg = 'Minimum deposit is one base token. Please increase your deposit amount.';
       } else if (error.message) {
         errorMsg += error.message;
       } else {
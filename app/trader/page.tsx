@@ -15,6 +15,7 @@ import {
   Clock,
   Activity
 } from 'lucide-react';
+import { BN } from '@coral-xyz/anchor';
 import { SpectreSDK } from '@/lib/spectre-sdk';
 import { formatSOL, shortenAddress } from '@/lib/helpers';
 import { useRouter } from 'next/navigation';
@@ -123,6 +124,12 @@ export default function TraderDashboard() {
     }
   };
 
+  // Base tokens behind a subscriber's pool shares
+  const positionValue = (position: any): number =>
+    myStrategy.totalShares.isZero()
+      ? 0
+      : position.shares.mul(myStrategy.baseBalance).div(myStrategy.totalShares).toNumber();
+
   const handleExecuteTrade = async (e: React.FormEvent) => {
     e.preventDefault();
     
@@ -150,31 +157,18 @@ export default function TraderDashboard() {
     setExecuting(true);
     try {
       const sdk = new SpectreSDK(connection, wallet);
-      const amountLamports = Math.floor(amountNum * 1_000_000_000); // Convert SOL to lamports
-      
-      // For demo purposes, simulate profit/loss
-      const profitOrLoss = direction === 'long' 
-        ? Math.floor(amountLamports * 0.05) // 5% profit for long
-        : Math.floor(amountLamports * -0.03); // 3% loss for short
-      
-      // Execute trade for each active position (subscriber)
-      let successCount = 0;
-      let failCount = 0;
+      const amountIn = new BN(Math.floor(amountNum * 1_000_000_000)); // Convert to base units
 
-      for (const position of strategyPositions) {
-        try {
-          await sdk.executeTrade(
-            wallet.publicKey,
-            position.publicKey, // Pass the position PDA, not the strategy
-            amountLamports,
-            profitOrLoss
-          );
-          successCount++;
-        } catch (error) {
-          console.error(`Failed to execute trade for position ${position.publicKey.toBase58()}:`, error);
-          failCount++;
-        }
-      }
+      // Long buys the strategy asset with the pool's base tokens, short sells it back
+      const side = direction === 'long' ? 'buy' : 'sell';
+
+      // One trade moves the whole pool; every subscriber's shares follow it
+      await sdk.executeTrade(
+        wallet.publicKey,
+        myStrategy.publicKey,
+        side,
+        amountIn
+      );
 
       // Add to local history
       setTradeHistory([
@@ -184,16 +178,12 @@ export default function TraderDashboard() {
           direction,
           timestamp: Date.now(),
           status: 'executed',
-          positionsUpdated: successCount
+          positionsUpdated: strategyPositions.length
         },
         ...tradeHistory
       ]);
 
-      if (failCount > 0) {
-        alert(`Trade executed for ${successCount} positions. ${failCount} failed.`);
-      } else {
-        alert(`Trade executed successfully for ${successCount} position(s)!`);
-      }
+      alert(`Trade executed successfully for ${strategyPositions.length} subscriber(s)!`);
       
       setAmount('');
       await loadTraderStrategy();
@@ -318,7 +308,7 @@ export default function TraderDashboard() {
                             {shortenAddress(position.user.toBase58())}
                           </p>
                           <p className="text-sm text-neutral-500">
-                            Balance: {formatSOL(position.currentBalance.toNumber())} SOL
+                            Balance: {formatSOL(positionValue(position))} SOL
                           </p>
                         </div>
                         <div className="text-right">
@@ -47,21 +47,22 @@ export function useUserPositions() {
       // Fetch strategy details for each position
       const mappedPositions: PositionData[] = await Promise.all(
         fetchedPositions.map(async (p) => {
-          const initial = p.initialBalance.toNumber();
-          const current = p.currentBalance.toNumber();
-          const profitLoss = current - initial;
-          const profitLossPercent = initial > 0 ? (profitLoss / initial) * 100 : 0;
-          
-          // Fetch strategy to get name
+          // Fetch strategy for its name and the pool balances behind the shares
           let strategyName = 'Unknown Strategy';
+          let current = 0;
           try {
             const strategy = await sdk.getStrategy(p.strategy);
             if (strategy) {
               strategyName = strategy.name;
+              current = sdk.getPositionValue(p, strategy).base.toNumber();
             }
           } catch (err) {
             console.error('Failed to fetch strategy details:', err);
           }
+
+          const initial = p.initialBalance.toNumber();
+          const profitLoss = current - initial;
+          const profitLossPercent = initial > 0 ? (profitLoss / initial) * 100 : 0;
           
           return {
             publicKey: p.publicKey.toString(),
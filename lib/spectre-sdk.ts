@@ -6,12 +6,16 @@ import {
   LAMPORTS_PER_SOL 
 } from '@solana/web3.js';
 import { AnchorProvider, Program, web3, BN, Idl } from '@coral-xyz/anchor';
+import { getAssociatedTokenAddressSync, getMint, TOKEN_PROGRAM_ID } from '@solana/spl-token';
 import { WalletContextState } from '@solana/wallet-adapter-react';
 import vaultIdl from './vault-idl.json';
 
 // Program ID - Deployed on Solana Devnet
 export const PROGRAM_ID = new PublicKey('75GwXPYmQSpfSWBg6awrancWsejB19o1AfiGerdbrbtS');
 
+// Constant-product AMM used as the local swap venue for strategies
+export const AMM_PROGRAM_ID = new PublicKey('H8S1ptyQdodEcWnkp25V9B6LjarmE5sizy7CwmrMkYjN');
+
 export interface Strategy {
   publicKey: PublicKey;
   trader: PublicKey;
@@ -23,6 +27,39 @@ export interface Strategy {
   totalFeesEarned: BN;
   isActive: boolean;
   createdAt: BN;
+  index: number;
+  migratedFrom: PublicKey | null;
+  baseMint: PublicKey;
+  assetMint: PublicKey;
+  baseVault: PublicKey;
+  assetVault: PublicKey;
+  swapProgram: PublicKey;
+  minDeposit: BN;
+  totalShares: BN;
+  baseBalance: BN;
+  assetBalance: BN;
+  bump: number;
+}
+
+export interface SwapVenue {
+  publicKey: PublicKey;
+  baseMint: PublicKey;
+  assetMint: PublicKey;
+  swapProgram: PublicKey;
+  pool: PublicKey;
+  poolBaseVault: PublicKey;
+  poolAssetVault: PublicKey;
+  referenceBase: BN;
+  referenceAsset: BN;
+  maxDeviationBps: number;
+  priceUpdatedAt: BN;
+  bump: number;
+}
+
+export interface TraderProfile {
+  publicKey: PublicKey;
+  trader: PublicKey;
+  strategyCount: number;
   bump: number;
 }
 
@@ -30,8 +67,8 @@ export interface UserPosition {
   publicKey: PublicKey;
   user: PublicKey;
   strategy: PublicKey;
+  shares: BN;
   initialBalance: BN;
-  currentBalance: BN;
   totalFeesPaid: BN;
   lastFeeSettlement: BN;
   subscribedAt: BN;
@@ -58,9 +95,31 @@ export class SpectreSDK {
   }
 
   /**
-   * Get strategy PDA address
+   * Get trader profile PDA address
+   */
+  getTraderProfileAddress(trader: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('trader_profile'), trader.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
+  /**
+   * Get strategy PDA address for the trader's strategy at `index`
+   */
+  getStrategyAddress(trader: PublicKey, index: number): [PublicKey, number] {
+    const indexBytes = Buffer.alloc(4);
+    indexBytes.writeUInt32LE(index);
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('strategy'), trader.toBuffer(), indexBytes],
+      PROGRAM_ID
+    );
+  }
+
+  /**
+   * Get the strategy PDA used before strategies were indexed per trader
    */
-  getStrategyAddress(trader: PublicKey): [PublicKey, number] {
+  getLegacyStrategyAddress(trader: PublicKey): [PublicKey, number] {
     return PublicKey.findProgramAddressSync(
       [Buffer.from('strategy'), trader.toBuffer()],
       PROGRAM_ID
@@ -77,6 +136,26 @@ export class SpectreSDK {
     );
   }
 
+  /**
+   * Get the strategy-owned token vault for `mint`
+   */
+  getStrategyVaultAddress(strategy: PublicKey, mint: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('strategy_vault'), strategy.toBuffer(), mint.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
+  /**
+   * Get the allowlisted swap venue PDA for a base/asset pair
+   */
+  getSwapVenueAddress(baseMint: PublicKey, assetMint: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('swap_venue'), baseMint.toBuffer(), assetMint.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
   /**
    * Initialize a new trading strategy
    */
@@ -90,15 +169,33 @@ export class SpectreSDK {
       throw new Error('Wallet not connected');
     }
 
-    const [strategyPDA] = this.getStrategyAddress(trader);
+    const [traderProfilePDA] = this.getTraderProfileAddress(trader);
+    const profile = await this.getTraderProfile(trader);
+    const [strategyPDA] = this.getStrategyAddress(trader, profile ? profile.strategyCount : 0);
+
+    // First strategy for this trader also creates the profile
+    const preInstructions = profile
+      ? []
+      : [
+          await this.program.methods
+            .initializeTraderProfile()
+            .accounts({
+              trader,
+              traderProfile: traderProfilePDA,
+              systemProgram: SystemProgram.programId,
+            })
+            .instruction(),
+        ];
 
     const tx = await this.program.methods
       .initializeStrategy(name, description, performanceFeeBps)
       .accounts({
         trader,
+        traderProfile: traderProfilePDA,
         strategy: strategyPDA,
         systemProgram: SystemProgram.programId,
       })
+      .preInstructions(preInstructions)
       .rpc();
 
     return tx;
@@ -109,6 +206,7 @@ export class SpectreSDK {
    */
   async updateStrategy(
     trader: PublicKey,
+    strategyKey: PublicKey,
     updates: {
       name?: string;
       description?: string;
@@ -119,8 +217,6 @@ export class SpectreSDK {
       throw new Error('Wallet not connected');
     }
 
-    const [strategyPDA] = this.getStrategyAddress(trader);
-
     const tx = await this.program.methods
       .updateStrategy(
         updates.name || null,
@@ -129,7 +225,111 @@ export class SpectreSDK {
       )
       .accounts({
         trader,
+        strategy: strategyKey,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Move a legacy single-strategy account to the trader's next strategy index
+   */
+  async migrateStrategy(trader: PublicKey): Promise<string> {
+    if (!this.provider || !this.program) {
+      throw new Error('Wallet not connected');
+    }
+
+    const [traderProfilePDA] = this.getTraderProfileAddress(trader);
+    const [legacyStrategyPDA] = this.getLegacyStrategyAddress(trader);
+    const profile = await this.getTraderProfile(trader);
+    const [strategyPDA] = this.getStrategyAddress(trader, profile ? profile.strategyCount : 0);
+
+    const preInstructions = profile
+      ? []
+      : [
+          await this.program.methods
+            .initializeTraderProfile()
+            .accounts({
+              trader,
+              traderProfile: traderProfilePDA,
+              systemProgram: SystemProgram.programId,
+            })
+            .instruction(),
+        ];
+
+    const tx = await this.program.methods
+      .migrateStrategy()
+      .accounts({
+        trader,
+        traderProfile: traderProfilePDA,
+        legacyStrategy: legacyStrategyPDA,
         strategy: strategyPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions(preInstructions)
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Refund and close a position on a legacy strategy. The user subscribes to
+   * the migrated strategy afterwards like any new subscriber
+   */
+  async migratePosition(user: PublicKey, strategyKey: PublicKey): Promise<string> {
+    if (!this.provider || !this.program) {
+      throw new Error('Wallet not connected');
+    }
+
+    const strategy = await this.getStrategy(strategyKey);
+    if (!strategy || !strategy.migratedFrom) {
+      throw new Error('Strategy was not migrated from a legacy account');
+    }
+
+    const [legacyPositionPDA] = this.getPositionAddress(user, strategy.migratedFrom);
+
+    const tx = await this.program.methods
+      .migratePosition()
+      .accounts({
+        user,
+        strategy: strategyKey,
+        legacyPosition: legacyPositionPDA,
+      })
+      .rpc();
+
+    return tx;
+  }
+
+  /**
+   * Create the strategy's token vaults on a pair with an allowlisted swap
+   * venue, pinning the venue's swap program
+   */
+  async initializeStrategyVaults(
+    trader: PublicKey,
+    strategyKey: PublicKey,
+    baseMint: PublicKey,
+    assetMint: PublicKey
+  ): Promise<string> {
+    if (!this.provider || !this.program) {
+      throw new Error('Wallet not connected');
+    }
+
+    const [baseVault] = this.getStrategyVaultAddress(strategyKey, baseMint);
+    const [assetVault] = this.getStrategyVaultAddress(strategyKey, assetMint);
+
+    const tx = await this.program.methods
+      .initializeStrategyVaults()
+      .accounts({
+        trader,
+        strategy: strategyKey,
+        baseMint,
+        assetMint,
+        venue: this.getSwapVenueAddress(baseMint, assetMint)[0],
+        baseVault,
+        assetVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
       .rpc();
 
@@ -137,12 +337,13 @@ export class SpectreSDK {
   }
 
   /**
-   * Subscribe to a trading strategy
+   * Subscribe to a trading strategy. Shares are minted at NAV, which values
+   * any asset the pool holds at the swap venue's reference price
    */
   async subscribeToStrategy(
     user: PublicKey,
     strategyKey: PublicKey,
-    initialDeposit: number // in SOL
+    initialDeposit: number // in whole base tokens
   ): Promise<string> {
     if (!this.provider || !this.program) {
       throw new Error('Wallet not connected');
@@ -154,15 +355,25 @@ export class SpectreSDK {
       throw new Error('You are already subscribed to this strategy');
     }
 
+    const strategy = await this.getStrategy(strategyKey);
+    if (!strategy) {
+      throw new Error('Strategy not found');
+    }
+
     const [positionPDA] = this.getPositionAddress(user, strategyKey);
-    const depositLamports = new BN(initialDeposit * LAMPORTS_PER_SOL);
+    const { decimals } = await getMint(this.connection, strategy.baseMint);
+    const depositAmount = new BN(Math.round(initialDeposit * 10 ** decimals));
 
     const tx = await this.program.methods
-      .subscribeToStrategy(depositLamports)
+      .subscribeToStrategy(depositAmount)
       .accounts({
         user,
         strategy: strategyKey,
+        venue: this.getSwapVenueAddress(strategy.baseMint, strategy.assetMint)[0],
         position: positionPDA,
+        baseVault: strategy.baseVault,
+        userBaseAccount: getAssociatedTokenAddressSync(strategy.baseMint, user),
+        tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
@@ -171,26 +382,43 @@ export class SpectreSDK {
   }
 
   /**
-   * Execute a trade (trader only)
+   * Execute a trade for the whole strategy pool through the pair's swap venue
+   * (trader only). The minimum output is enforced on-chain from the venue's
+   * reference price, which only the trading admin sets
    */
   async executeTrade(
     trader: PublicKey,
-    positionKey: PublicKey,
-    amount: number, // in lamports
-    profitOrLoss: number // in lamports, can be negative
+    strategyKey: PublicKey,
+    side: 'buy' | 'sell',
+    amountIn: BN
   ): Promise<string> {
     if (!this.provider || !this.program) {
       throw new Error('Wallet not connected');
     }
 
-    const [strategyPDA] = this.getStrategyAddress(trader);
+    const strategy = await this.getStrategy(strategyKey);
+    if (!strategy) {
+      throw new Error('Strategy not found');
+    }
+
+    const venue = await this.getSwapVenue(strategy.baseMint, strategy.assetMint);
+    if (!venue) {
+      throw new Error('The strategy\'s mint pair has no allowlisted swap venue');
+    }
 
     const tx = await this.program.methods
-      .executeTrade(new BN(amount), new BN(profitOrLoss))
+      .executeTrade(side === 'buy' ? { buy: {} } : { sell: {} }, amountIn)
       .accounts({
         trader,
-        strategy: strategyPDA,
-        position: positionKey,
+        strategy: strategyKey,
+        baseVault: strategy.baseVault,
+        assetVault: strategy.assetVault,
+        venue: venue.publicKey,
+        pool: venue.pool,
+        poolBaseVault: venue.poolBaseVault,
+        poolAssetVault: venue.poolAssetVault,
+        swapProgram: venue.swapProgram,
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .rpc();
 
@@ -198,7 +426,8 @@ export class SpectreSDK {
   }
 
   /**
-   * Settle performance fees
+   * Settle performance fees on the position's growth at NAV. The fee is paid
+   * from the pool's base tokens
    */
   async settleFees(
     user: PublicKey,
@@ -222,8 +451,11 @@ export class SpectreSDK {
         user,
         trader: strategy.trader,
         strategy: strategyKey,
+        venue: this.getSwapVenueAddress(strategy.baseMint, strategy.assetMint)[0],
         position: positionPDA,
-        systemProgram: SystemProgram.programId,
+        baseVault: strategy.baseVault,
+        traderBaseAccount: getAssociatedTokenAddressSync(strategy.baseMint, strategy.trader),
+        tokenProgram: TOKEN_PROGRAM_ID,
       })
       .rpc();
 
@@ -242,6 +474,10 @@ export class SpectreSDK {
     }
 
     const [positionPDA] = this.getPositionAddress(user, strategyKey);
+    const strategy = await this.getStrategy(strategyKey);
+    if (!strategy) {
+      throw new Error('Strategy not found');
+    }
 
     const tx = await this.program.methods
       .unsubscribe()
@@ -249,6 +485,11 @@ export class SpectreSDK {
         user,
         strategy: strategyKey,
         position: positionPDA,
+        baseVault: strategy.baseVault,
+        userBaseAccount: getAssociatedTokenAddressSync(strategy.baseMint, user),
+        assetVault: strategy.assetVault,
+        userAssetAccount: getAssociatedTokenAddressSync(strategy.assetMint, user),
+        tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
@@ -276,6 +517,40 @@ export class SpectreSDK {
     }
   }
 
+  /**
+   * Fetch the allowlisted swap venue of a base/asset pair, or null if the
+   * pair has none
+   */
+  async getSwapVenue(baseMint: PublicKey, assetMint: PublicKey): Promise<SwapVenue | null> {
+    if (!this.program) {
+      throw new Error('Program not initialized');
+    }
+
+    const [venuePDA] = this.getSwapVenueAddress(baseMint, assetMint);
+    const venue = await (this.program.account as any).swapVenue.fetchNullable(venuePDA);
+    return venue ? ({ publicKey: venuePDA, ...venue } as SwapVenue) : null;
+  }
+
+  /**
+   * Fetch a trader's profile, or null if the trader has no strategies yet
+   */
+  async getTraderProfile(trader: PublicKey): Promise<TraderProfile | null> {
+    if (!this.program) {
+      throw new Error('Program not initialized');
+    }
+
+    const [traderProfilePDA] = this.getTraderProfileAddress(trader);
+    const profile = await (this.program.account as any).traderProfile.fetchNullable(traderProfilePDA);
+    if (!profile) {
+      return null;
+    }
+
+    return {
+      publicKey: traderProfilePDA,
+      ...profile,
+    } as TraderProfile;
+  }
+
   /**
    * Fetch all strategies
    */
@@ -406,13 +681,27 @@ export class SpectreSDK {
   }
 
   /**
-   * Calculate position P&L
+   * Pro-rata pool balances behind a position's shares
+   */
+  getPositionValue(position: UserPosition, strategy: Strategy): { base: BN; asset: BN } {
+    if (strategy.totalShares.isZero()) {
+      return { base: new BN(0), asset: new BN(0) };
+    }
+    return {
+      base: position.shares.mul(strategy.baseBalance).div(strategy.totalShares),
+      asset: position.shares.mul(strategy.assetBalance).div(strategy.totalShares),
+    };
+  }
+
+  /**
+   * Calculate position P&L in base tokens
+   * Only meaningful while the strategy holds no asset
    */
-  calculatePnL(position: UserPosition): {
+  calculatePnL(position: UserPosition, strategy: Strategy): {
     pnl: BN;
     pnlPercentage: number;
   } {
-    const pnl = position.currentBalance.sub(position.initialBalance);
+    const pnl = this.getPositionValue(position, strategy).base.sub(position.initialBalance);
     const pnlPercentage = position.initialBalance.isZero()
       ? 0
       : (pnl.toNumber() / position.initialBalance.toNumber()) * 100;